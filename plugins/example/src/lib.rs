@@ -0,0 +1,37 @@
+//! Example native plugin, build it with `cargo build -p ada-example-plugin` and copy the library
+//! into `ADA_NATIVE_PLUGIN_DIR`.
+
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::network::node::{NodeContext, NodeFunction};
+use ada_executor::network::registry::{NodeDescriptor, NodeRegistry};
+
+fn register(registry: &mut NodeRegistry) {
+    registry.register_fn(NodeDescriptor::new("example.scale", "value multiplied by the `factor` config", &["value"], &["value"]), |config| {
+        let factor = match config.get_item("factor") {
+            Some(Data::Integer(factor)) => *factor,
+            None => 2,
+            Some(other) => return Err(format!("config 'factor' must be an integer, got {:?}", other)),
+        };
+        let execute_fn: NodeFunction = Box::new(move |input: Container, _: &NodeContext| {
+            let mut output = Container::new();
+            if let Some(Data::Integer(value)) = input.get_item("value") {
+                output.add_item("value", Data::Integer(value.saturating_mul(factor)));
+            }
+            Ok(output)
+        });
+        Ok(execute_fn)
+    });
+
+    registry.register_fn(NodeDescriptor::new("example.reverse", "value with its characters reversed", &["value"], &["value"]), |_| {
+        let execute_fn: NodeFunction = Box::new(|input: Container, _: &NodeContext| {
+            let mut output = Container::new();
+            if let Some(Data::Text(value)) = input.get_item("value") {
+                output.add_item("value", Data::Text(value.chars().rev().collect()));
+            }
+            Ok(output)
+        });
+        Ok(execute_fn)
+    });
+}
+
+ada_executor::export_plugin!(register);
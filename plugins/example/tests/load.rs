@@ -0,0 +1,64 @@
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use uuid::Uuid;
+
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::network::plugins::native;
+use ada_executor::network::registry::NodeRegistry;
+
+// A directory holding only the plugin, removed once dropped
+struct PluginDir {
+    dir: PathBuf,
+}
+
+impl PluginDir {
+    // Cargo builds the plugin's cdylib next to the test binary
+    fn new() -> Self {
+        let library = format!("{}ada_example_plugin{}", DLL_PREFIX, DLL_SUFFIX);
+        let deps = std::env::current_exe().unwrap().parent().unwrap().to_path_buf();
+        let dir = std::env::temp_dir().join(format!("ada-native-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::copy(deps.join(&library), dir.join(&library)).unwrap();
+        PluginDir { dir }
+    }
+
+    fn path(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Drop for PluginDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[test]
+fn plugin_registers_node_types() {
+    let dir = PluginDir::new();
+    let mut registry = NodeRegistry::with_builtins();
+    assert_eq!(native::load_dir(&mut registry, dir.path()).unwrap(), 1);
+    assert!(registry.descriptor("example.reverse").is_some());
+
+    let mut config = Container::new();
+    config.add_item("factor", Data::Integer(3));
+    let scale = registry.create_node("example.scale", Uuid::new_v4(), config).unwrap();
+    let mut input = Container::new();
+    input.add_item("value", Data::Integer(14));
+    assert_eq!(scale.execute(input).get_item("value"), Some(&Data::Integer(42)));
+
+    let mut config = Container::new();
+    config.add_item("factor", Data::Text("three".to_string()));
+    assert!(registry.create_node("example.scale", Uuid::new_v4(), config).is_err());
+}
+
+#[test]
+fn non_plugin_files_are_rejected() {
+    let dir = PluginDir::new();
+    let fake = dir.path().join(format!("{}fake{}", DLL_PREFIX, DLL_SUFFIX));
+    fs::write(&fake, "not a library").unwrap();
+    assert!(native::load_library(&mut NodeRegistry::new(), &fake).is_err());
+    assert!(native::load_dir(&mut NodeRegistry::new(), dir.path()).is_err());
+}
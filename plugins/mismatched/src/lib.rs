@@ -0,0 +1,23 @@
+//! Plugin declaring a version of ada-executor it was not built against, the executor has to refuse
+//! loading it. Only used by the tests of `network::plugins::native`.
+
+use std::ffi::c_char;
+
+use ada_executor::network::plugins::native::{PluginDeclaration, NATIVE_ABI_VERSION, RUSTC_VERSION};
+use ada_executor::network::registry::NodeRegistry;
+
+const MISMATCHED_CRATE_VERSION: &str = "0.0.0-mismatched\0";
+
+fn register(_: &mut NodeRegistry) {
+    panic!("a plugin with a mismatched version must not be registered");
+}
+
+// What `export_plugin!` declares, apart from the crate version
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+pub static ada_plugin_declaration: PluginDeclaration = PluginDeclaration {
+    abi_version: NATIVE_ABI_VERSION,
+    rustc_version: RUSTC_VERSION.as_ptr() as *const c_char,
+    crate_version: MISMATCHED_CRATE_VERSION.as_ptr() as *const c_char,
+    register,
+};
@@ -0,0 +1,16 @@
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+
+use ada_executor::network::plugins::native;
+use ada_executor::network::registry::NodeRegistry;
+
+#[test]
+fn plugin_built_against_another_version_is_rejected() {
+    // Cargo builds the plugin's cdylib next to the test binary
+    let library = std::env::current_exe().unwrap().parent().unwrap()
+        .join(format!("{}ada_mismatched_plugin{}", DLL_PREFIX, DLL_SUFFIX));
+    let mut registry = NodeRegistry::new();
+
+    let error = native::load_library(&mut registry, &library).unwrap_err();
+    assert!(error.to_string().contains("plugin built against ada-executor 0.0.0-mismatched,"), "{}", error);
+    assert_eq!(registry.descriptors().count(), 0);
+}
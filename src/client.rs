@@ -1,15 +1,25 @@
-use std::io::{self, Write};
+use std::io::{self, Read, Write};
 use std::net::TcpStream;
+#[cfg(feature = "tls")]
+use std::path::Path;
 use std::vec;
 
-use crate::network::data_container::{Container, Data};
-use crate::network::data_container::Data::{Float, Integer};
-use crate::protocol::commands::{AdaCommand, AdaCommandHeader, AdaCommandType};
+use uuid::Uuid;
 
-mod protocol;
-mod network;
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::network::data_container::Data::{Float, Integer};
+use ada_executor::protocol::auth;
+use ada_executor::protocol::commands::{AdaCommand, AdaCommandHeader, AdaCommandType};
+use ada_executor::protocol::protocol_parser::read_command;
+#[cfg(feature = "tls")]
+use ada_executor::protocol::tls;
 
+/// Without arguments, sends the sample command forever.
+///
+/// `client dot <network> [--status]` prints the network as Graphviz DOT, `--status` colours
+/// nodes by their last execution.
 pub fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
     let server_address = "127.0.0.1:7878";
     let mut data = Container::new();
     data.add_item("int", Integer(1));
@@ -43,28 +53,24 @@ pub fn main() -> io::Result<()> {
                 network: None,
             },
             data: Some(data),
+            origin: None,
         }
     ];
 
 
     match TcpStream::connect(server_address) {
-        Ok(mut stream) => {
+        Ok(stream) => {
             println!("Successfully connected to server at {}", server_address);
-            loop {
-                for command in &commands {
-                    let to_send = command.to_bytes();
-                    if let Err(e) = stream.write_all(&to_send) {
-                        eprintln!("Failed to send data: {}", e);
-                        break;
-                    }
-                    if let Err(e) = stream.flush() {
-                        eprintln!("Failed to flush stream: {}", e);
-                        break;
-                    }
-                    // println!("Binary data sent: {:?}", to_send);
-                }
-                // thread::sleep(Duration::from_millis(100));
+            #[cfg(feature = "tls")]
+            if let Ok(ca) = std::env::var("ADA_TLS_CA") {
+                let client_cert = std::env::var("ADA_TLS_CLIENT_CERT").ok();
+                let client_key = std::env::var("ADA_TLS_CLIENT_KEY").ok();
+                let client_identity = client_cert.as_deref().map(Path::new).zip(client_key.as_deref().map(Path::new));
+                let server_name = std::env::var("ADA_TLS_SERVER_NAME").unwrap_or_else(|_| "localhost".to_string());
+                let config = tls::client_config(Path::new(&ca), client_identity)?;
+                return run(tls::connect(stream, &server_name, config)?, &args, &commands);
             }
+            return run(stream, &args, &commands);
         }
         Err(e) => {
             eprintln!("Failed to connect to server: {}", e);
@@ -72,3 +78,132 @@ pub fn main() -> io::Result<()> {
     }
     Ok(())
 }
+
+fn run<S: Read + Write>(mut stream: S, args: &[String], commands: &[AdaCommand]) -> io::Result<()> {
+    if let (Ok(identity), Ok(secret)) = (std::env::var("ADA_IDENTITY"), std::env::var("ADA_SECRET")) {
+        if let Err(e) = auth::authenticate(&mut stream, &identity, &secret) {
+            eprintln!("Failed to authenticate as {}: {}", identity, e);
+            return Err(e);
+        }
+    }
+    match args.first().map(String::as_str) {
+        None => {
+            send_forever(stream, commands);
+            Ok(())
+        }
+        Some("dot") => export_dot(stream, &args[1..]),
+        Some("run") => query_run(stream, &args[1..]),
+        Some("watch") => watch(stream, &args[1..]),
+        Some(other) => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("unknown subcommand '{}'", other))),
+    }
+}
+
+fn export_dot<S: Read + Write>(mut stream: S, args: &[String]) -> io::Result<()> {
+    let network = args.first()
+        .and_then(|network| Uuid::parse_str(network).ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "usage: client dot <network> [--status]"))?;
+    let mut data = Container::new();
+    data.add_item("status", Data::Boolean(args.iter().any(|arg| arg == "--status")));
+    let mut command = AdaCommand::new(AdaCommandType::ExportGraph);
+    command.header.network = Some(network);
+    command.data = Some(data);
+    stream.write_all(&command.to_bytes())?;
+    stream.flush()?;
+
+    let response = read_command(&mut stream)?;
+    match (&response.header.command_type, response.data.as_ref().and_then(|data| data.get_item("dot"))) {
+        (AdaCommandType::Response, Some(Data::Text(dot))) => {
+            print!("{}", dot);
+            Ok(())
+        }
+        _ => Err(io::Error::other(format!("server answered {}", response))),
+    }
+}
+
+// Prints the run's result and trace as sorted `key=value` lines
+fn query_run<S: Read + Write>(mut stream: S, args: &[String]) -> io::Result<()> {
+    let ids: Vec<Uuid> = args.iter().filter_map(|arg| Uuid::parse_str(arg).ok()).collect();
+    let (network, run) = match ids.as_slice() {
+        [network, run] => (*network, *run),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "usage: client run <network> <run>")),
+    };
+    let mut data = Container::new();
+    data.add_item("run", Data::Text(run.to_string()));
+    let mut command = AdaCommand::new(AdaCommandType::QueryRun);
+    command.header.network = Some(network);
+    command.data = Some(data);
+    stream.write_all(&command.to_bytes())?;
+    stream.flush()?;
+
+    let response = read_command(&mut stream)?;
+    match (&response.header.command_type, response.data.as_ref()) {
+        (AdaCommandType::Response, Some(data)) => {
+            let mut lines: Vec<String> = data.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+            lines.sort();
+            for line in lines {
+                println!("{}", line);
+            }
+            Ok(())
+        }
+        _ => Err(io::Error::other(format!("server answered {}", response))),
+    }
+}
+
+// Subscribes and prints every pushed event as a line of sorted `key=value` pairs until the server hangs up
+fn watch<S: Read + Write>(mut stream: S, args: &[String]) -> io::Result<()> {
+    let usage = || io::Error::new(io::ErrorKind::InvalidInput, "usage: client watch <network> [node] [--port <port>]");
+    let port = args.iter().position(|arg| arg == "--port").map(|index| args.get(index + 1).ok_or_else(usage)).transpose()?;
+    let ids: Vec<Uuid> = args.iter().filter_map(|arg| Uuid::parse_str(arg).ok()).collect();
+    let (network, node) = match ids.as_slice() {
+        [network] => (*network, None),
+        [network, node] => (*network, Some(*node)),
+        _ => return Err(usage()),
+    };
+    let mut command = AdaCommand::new(AdaCommandType::Subscribe);
+    command.header.network = Some(network);
+    command.header.node = node;
+    if let Some(port) = port {
+        let mut data = Container::new();
+        data.add_item("port", Data::Text(port.clone()));
+        command.data = Some(data);
+    }
+    stream.write_all(&command.to_bytes())?;
+    stream.flush()?;
+
+    let response = read_command(&mut stream)?;
+    if !matches!(response.header.command_type, AdaCommandType::Response) {
+        return Err(io::Error::other(format!("server answered {}", response)));
+    }
+    loop {
+        let event = read_command(&mut stream)?;
+        if !matches!(event.header.command_type, AdaCommandType::Event) {
+            continue;
+        }
+        let mut pairs: Vec<String> = event.data.iter().flat_map(Container::iter).map(|(key, value)| format!("{}={}", key, value)).collect();
+        pairs.sort();
+        let node = event.header.node.map(|node| node.to_string()).unwrap_or_default();
+        println!("{} {}", node, pairs.join(" "));
+    }
+}
+
+fn send_forever<S: Read + Write>(mut stream: S, commands: &[AdaCommand]) {
+    loop {
+        for command in commands {
+            let to_send = command.to_bytes();
+            if let Err(e) = stream.write_all(&to_send) {
+                eprintln!("Failed to send data: {}", e);
+                break;
+            }
+            if let Err(e) = stream.flush() {
+                eprintln!("Failed to flush stream: {}", e);
+                break;
+            }
+            // println!("Binary data sent: {:?}", to_send);
+            if let Err(e) = read_command(&mut stream) {
+                eprintln!("Failed to read response: {}", e);
+                break;
+            }
+        }
+        // thread::sleep(Duration::from_millis(100));
+    }
+}
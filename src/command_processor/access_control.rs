@@ -0,0 +1,106 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use uuid::Uuid;
+
+/// Identity used for commands from connections that did not authenticate.
+pub const ANONYMOUS: &str = "anonymous";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    Read,
+    Execute,
+    Modify,
+    /// Implies every other permission
+    Admin,
+}
+
+impl TryFrom<&str> for Permission {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "read" => Ok(Permission::Read),
+            "execute" => Ok(Permission::Execute),
+            "modify" => Ok(Permission::Modify),
+            "admin" => Ok(Permission::Admin),
+            _ => Err(format!("unknown permission '{}'", value)),
+        }
+    }
+}
+
+/// Network a grant applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NetworkScope {
+    Any,
+    Network(Uuid),
+}
+
+/// Per-network permissions of every identity, anything not granted is denied.
+///
+/// Loaded from a text file with one `<identity> <network-uuid|*> <permission>[,<permission>...]`
+/// grant per line, `#` starts a comment. Use `anonymous` as identity for unauthenticated clients.
+#[derive(Default)]
+pub struct AccessControl {
+    grants: HashMap<String, HashMap<NetworkScope, HashSet<Permission>>>,
+}
+
+impl AccessControl {
+    pub fn new() -> Self {
+        AccessControl {
+            grants: HashMap::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let mut access_control = AccessControl::new();
+        for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            access_control.parse_grant(line)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{}:{}: {}", path.display(), number + 1, e)))?;
+        }
+        log::info!("Loaded access control for {} identities from {}", access_control.grants.len(), path.display());
+        Ok(access_control)
+    }
+
+    pub fn grant(&mut self, identity: &str, scope: NetworkScope, permission: Permission) {
+        self.grants.entry(identity.to_string())
+            .or_default()
+            .entry(scope)
+            .or_default()
+            .insert(permission);
+    }
+
+    pub fn is_allowed(&self, identity: &str, network: Option<Uuid>, permission: Permission) -> bool {
+        let scopes = match self.grants.get(identity) {
+            Some(scopes) => scopes,
+            None => return false,
+        };
+        let specific = network.map(NetworkScope::Network);
+        [Some(NetworkScope::Any), specific].iter()
+            .flatten()
+            .filter_map(|scope| scopes.get(scope))
+            .any(|granted| granted.contains(&permission) || granted.contains(&Permission::Admin))
+    }
+
+    fn parse_grant(&mut self, line: &str) -> Result<(), String> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (identity, scope, permissions) = match fields.as_slice() {
+            [identity, scope, permissions] => (identity, scope, permissions),
+            _ => return Err("expected '<identity> <network-uuid|*> <permissions>'".to_string()),
+        };
+        let scope = match *scope {
+            "*" => NetworkScope::Any,
+            id => NetworkScope::Network(Uuid::parse_str(id).map_err(|e| format!("invalid network '{}': {}", id, e))?),
+        };
+        for permission in permissions.split(',') {
+            self.grant(identity, scope, Permission::try_from(permission.trim())?);
+        }
+        Ok(())
+    }
+}
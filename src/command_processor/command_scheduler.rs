@@ -1,27 +1,84 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use std::thread;
+use std::thread::{self, JoinHandle};
 use std::time::Duration;
+#[cfg(feature = "serde")]
+use std::time::Instant;
 
-use crate::protocol::commands::AdaCommand;
+use crate::command_processor::dispatcher::Dispatcher;
+use crate::protocol::commands::{AdaCommand, AdaCommandType};
+use crate::protocol::wal::WriteAheadLog;
 
-pub fn listen(command_queue: Arc<Mutex<Vec<AdaCommand>>>) {
-    let handle = thread::spawn(move || {
+/// What the scheduler does besides handling queued commands.
+#[derive(Clone, Default)]
+pub struct SchedulerOptions {
+    /// Writes a snapshot to the dispatcher's snapshot path this often
+    pub snapshot_interval: Option<Duration>,
+    /// Once set, the scheduler writes a final snapshot, including the commands still queued, and stops
+    pub shutdown: Option<Arc<AtomicBool>>,
+    /// Logged commands are marked done here once handled
+    pub wal: Option<Arc<WriteAheadLog>>,
+}
+
+pub fn listen(command_queue: Arc<Mutex<Vec<AdaCommand>>>, dispatcher: Dispatcher) {
+    drop(start(command_queue, dispatcher, SchedulerOptions::default()));
+}
+
+/// Handles queued commands on a thread of its own, which only finishes after a shutdown.
+pub fn start(command_queue: Arc<Mutex<Vec<AdaCommand>>>, mut dispatcher: Dispatcher, options: SchedulerOptions) -> JoinHandle<()> {
+    thread::spawn(move || {
+        #[cfg(feature = "serde")]
+        let mut last_snapshot = Instant::now();
         loop {
-            {
+            // Take the whole backlog so producers are not blocked while commands run
+            let (mut commands, stopping): (VecDeque<AdaCommand>, bool) = {
                 let mut queue = command_queue.lock().unwrap();
-                if queue.len() > 0 { log::info!("Working on {} queued commands", queue.len()); }
-                while queue.len() > 0 {
-                    let next_command = queue.pop();
-                    if next_command.is_some() {
-                        let command = next_command.unwrap();
-                        log::debug!("Handle command {:?} with data [{:?}]", command.header, command.data)
+                let stopping = options.shutdown.as_ref().is_some_and(|shutdown| shutdown.load(Ordering::SeqCst));
+                (queue.drain(..).collect(), stopping)
+            };
+            if stopping {
+                let pending: Vec<AdaCommand> = commands.into();
+                log::info!("Shutting down with {} queued commands", pending.len());
+                #[cfg(feature = "serde")]
+                write_snapshot(&dispatcher, &pending);
+                return;
+            }
+            if !commands.is_empty() { log::info!("Working on {} queued commands", commands.len()); }
+            while let Some(command) = commands.pop_front() {
+                log::debug!("Handle command {:?} with data [{:?}]", command.header, command.data);
+                let wal_sequence = command.origin.as_ref().and_then(|origin| origin.wal_sequence);
+                if matches!(command.header.command_type, AdaCommandType::Snapshot) {
+                    let mut pending: Vec<AdaCommand> = commands.iter().cloned().collect();
+                    pending.extend(command_queue.lock().unwrap().iter().cloned());
+                    dispatcher.handle_queued(command, &pending);
+                } else {
+                    dispatcher.handle(command);
+                }
+                if let (Some(wal), Some(sequence)) = (&options.wal, wal_sequence) {
+                    if let Err(e) = wal.mark_done(sequence) {
+                        log::error!("Unable to mark command {} done in the write-ahead log: {}", sequence, e);
                     }
                 }
             }
 
-            thread::sleep(Duration::from_millis(1000));
+            #[cfg(feature = "serde")]
+            if options.snapshot_interval.is_some_and(|interval| last_snapshot.elapsed() >= interval) {
+                let pending: Vec<AdaCommand> = command_queue.lock().unwrap().clone();
+                write_snapshot(&dispatcher, &pending);
+                last_snapshot = Instant::now();
+            }
+
+            thread::sleep(Duration::from_millis(10));
         }
-    });
+    })
+}
 
-    drop(handle);
-}
\ No newline at end of file
+#[cfg(feature = "serde")]
+fn write_snapshot(dispatcher: &Dispatcher, pending: &[AdaCommand]) {
+    if let Some(path) = dispatcher.snapshot_path() {
+        if let Err(e) = dispatcher.snapshot(path, pending) {
+            log::error!("Unable to write snapshot to {}: {}", path.display(), e);
+        }
+    }
+}
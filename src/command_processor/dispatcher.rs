@@ -0,0 +1,805 @@
+use std::collections::{HashMap, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, SystemTime};
+#[cfg(feature = "serde")]
+use std::fs;
+#[cfg(feature = "serde")]
+use std::io::{Error, ErrorKind};
+#[cfg(feature = "serde")]
+use std::path::{Path, PathBuf};
+
+use uuid::Uuid;
+
+use crate::command_processor::access_control::{AccessControl, Permission, ANONYMOUS};
+#[cfg(feature = "serde")]
+use crate::command_processor::snapshot;
+use crate::network::data_container::{Container, Data};
+use crate::network::graph::{Edge, FailurePolicy, Graph, NodeStatus};
+use crate::network::node::{CancellationToken, ExecutionPolicy};
+use crate::network::registry::NodeRegistry;
+use crate::network::run::{NodeEvent, RunOptions, RunReport};
+use crate::protocol::commands::{AdaCommand, AdaCommandType};
+
+/// Owns every registered network and turns queued commands into responses.
+///
+/// Runs execute on their own threads against a snapshot of the network, so the network can be
+/// changed and runs cancelled while they are in progress.
+pub struct Dispatcher {
+    networks: HashMap<Uuid, Arc<Graph>>,
+    registry: NodeRegistry,
+    access_control: Option<AccessControl>,
+    runs: Arc<Mutex<RunState>>,
+    #[cfg(feature = "serde")]
+    snapshot_path: Option<PathBuf>,
+}
+
+/// Finished runs kept per network for `QueryRun` unless set with `Dispatcher::with_history_limit`.
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+// Shared between the dispatcher and its run threads
+struct RunState {
+    active: HashMap<Uuid, ActiveRun>,
+    // Status of every node's latest execution, per network
+    statuses: HashMap<Uuid, HashMap<Uuid, NodeStatus>>,
+    // Finished runs per network, oldest first
+    history: HashMap<Uuid, VecDeque<RunReport>>,
+    history_limit: usize,
+    subscriptions: HashMap<Uuid, Subscription>,
+}
+
+impl RunState {
+    fn record(&mut self, report: RunReport) {
+        self.statuses.entry(report.network).or_default().extend(report.statuses.iter());
+        let history = self.history.entry(report.network).or_default();
+        history.push_back(report);
+        while history.len() > self.history_limit {
+            history.pop_front();
+        }
+    }
+
+    // Pushes `event` to every matching subscription, dropping those whose connection is gone
+    fn publish(&mut self, event: &NodeEvent) {
+        self.subscriptions.retain(|id, subscription| {
+            let event = match subscription.event(id, event) {
+                Some(event) => event,
+                None => return true,
+            };
+            if subscription.reply.send(event).is_err() {
+                log::debug!("Connection of subscription {} is gone, dropping it", id);
+                return false;
+            }
+            true
+        });
+    }
+}
+
+struct Subscription {
+    network: Uuid,
+    node: Option<Uuid>,
+    port: Option<String>,
+    connection: Uuid,
+    reply: Sender<AdaCommand>,
+}
+
+impl Subscription {
+    // The `Event` frame telling subscription `id` about `event`, None if it does not match
+    fn event(&self, id: &Uuid, event: &NodeEvent) -> Option<AdaCommand> {
+        if event.network != self.network || self.node.is_some_and(|node| node != event.node) {
+            return None;
+        }
+        let mut data = Container::new();
+        data.add_item("subscription", Data::Text(id.to_string()));
+        data.add_item("run.id", Data::Text(event.run.to_string()));
+        data.add_item("status", Data::Text(event.status.to_string()));
+        match &self.port {
+            Some(port) => {
+                let value = event.output.as_ref().and_then(|output| output.get_item(port))?;
+                data.add_item(port, value.clone());
+            }
+            None => {
+                if let Some(output) = &event.output {
+                    data.merge(output);
+                }
+                if let Some(error) = &event.error {
+                    data.add_item("error", Data::Text(error.message.clone()));
+                }
+            }
+        }
+        let mut command = AdaCommand::new(AdaCommandType::Event);
+        command.header.network = Some(event.network);
+        command.header.node = Some(event.node);
+        command.data = Some(data);
+        Some(command)
+    }
+}
+
+struct ActiveRun {
+    network: Uuid,
+    token: CancellationToken,
+    // CancelRun commands answered once the run stopped
+    cancellations: Vec<AdaCommand>,
+}
+
+impl Default for Dispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dispatcher {
+    pub fn new() -> Self {
+        Dispatcher {
+            networks: HashMap::new(),
+            registry: NodeRegistry::with_builtins(),
+            access_control: None,
+            runs: Arc::new(Mutex::new(RunState {
+                active: HashMap::new(),
+                statuses: HashMap::new(),
+                history: HashMap::new(),
+                history_limit: DEFAULT_HISTORY_LIMIT,
+                subscriptions: HashMap::new(),
+            })),
+            #[cfg(feature = "serde")]
+            snapshot_path: None,
+        }
+    }
+
+    /// Node types available to `AddNode`, defaults to the built-in nodes.
+    pub fn with_registry(mut self, registry: NodeRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Checks every command from a client against `access_control` before it touches a network.
+    pub fn with_access_control(mut self, access_control: AccessControl) -> Self {
+        self.access_control = Some(access_control);
+        self
+    }
+
+    /// How many finished runs `QueryRun` can still find per network, older ones are dropped.
+    pub fn with_history_limit(self, limit: usize) -> Self {
+        self.runs.lock().unwrap().history_limit = limit;
+        self
+    }
+
+    /// Loads every `*.json` graph file in `dir` as a network, see `Graph::load`.
+    #[cfg(feature = "serde")]
+    pub fn load_networks(&mut self, dir: &Path) -> std::io::Result<usize> {
+        let mut paths: Vec<_> = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<_>>()?;
+        paths.retain(|path| path.extension().is_some_and(|extension| extension == "json"));
+        paths.sort();
+
+        for path in &paths {
+            let graph = Graph::load(path, &self.registry)?;
+            if self.networks.contains_key(&graph.id()) {
+                return Err(Error::new(ErrorKind::InvalidData, format!("{}: network {} already loaded", path.display(), graph.id())));
+            }
+            log::info!("Loaded network {} from {}", graph.id(), path.display());
+            self.networks.insert(graph.id(), Arc::new(graph));
+        }
+        Ok(paths.len())
+    }
+
+    /// Where the `Snapshot` command writes snapshots, see `snapshot`.
+    #[cfg(feature = "serde")]
+    pub fn with_snapshot_path(mut self, path: PathBuf) -> Self {
+        self.snapshot_path = Some(path);
+        self
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn snapshot_path(&self) -> Option<&Path> {
+        self.snapshot_path.as_deref()
+    }
+
+    /// Writes every network with its node state and the `pending` commands to `path`, returns how
+    /// many commands were written.
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&self, path: &Path, pending: &[AdaCommand]) -> std::io::Result<usize> {
+        let queued = snapshot::write(path, self.networks.values().map(Arc::as_ref), pending)?;
+        log::info!("Wrote snapshot of {} networks and {} queued commands to {}", self.networks.len(), queued, path.display());
+        Ok(queued)
+    }
+
+    /// Loads the networks of a snapshot, replacing networks with the same id, and returns the
+    /// commands that were still queued.
+    #[cfg(feature = "serde")]
+    pub fn restore(&mut self, path: &Path) -> std::io::Result<Vec<AdaCommand>> {
+        let snapshot = snapshot::read(path, &self.registry)?;
+        log::info!("Restored {} networks and {} queued commands from {}", snapshot.networks.len(), snapshot.queue.len(), path.display());
+        for graph in snapshot.networks {
+            self.networks.insert(graph.id(), Arc::new(graph));
+        }
+        Ok(snapshot.queue)
+    }
+
+    /// Handles `command` and returns its response, waiting for runs to finish.
+    pub fn dispatch(&mut self, command: &AdaCommand) -> AdaCommand {
+        self.process(command, None, &[]).expect("responses are only deferred to a reply channel")
+    }
+
+    /// Handles `command` and sends the response to its origin, runs answer from their own thread
+    /// once they finish.
+    pub fn handle(&mut self, command: AdaCommand) {
+        self.handle_queued(command, &[]);
+    }
+
+    /// Like `handle`, with the commands still queued behind `command`, which a `Snapshot` includes.
+    pub fn handle_queued(&mut self, command: AdaCommand, pending: &[AdaCommand]) {
+        let reply = command.origin.as_ref().map(|origin| origin.reply.clone());
+        if let Some(response) = self.process(&command, reply.as_ref(), pending) {
+            if let Some(reply) = reply {
+                if reply.send(response).is_err() {
+                    log::debug!("Origin of {:?} is gone, dropping response", command.header);
+                }
+            }
+        }
+    }
+
+    // Returns None if the response will be sent to `deferred` later
+    fn process(&mut self, command: &AdaCommand, deferred: Option<&Sender<AdaCommand>>, #[cfg_attr(not(feature = "serde"), allow(unused_variables))] pending: &[AdaCommand]) -> Option<AdaCommand> {
+        if let Some(permission) = Dispatcher::required_permission(&command.header.command_type) {
+            if !self.is_allowed(command, permission) {
+                log::warn!("Denied {:?} on network {:?}", command.header.command_type, command.header.network);
+                let mut data = Container::new();
+                data.add_item("error", Data::Text(format!("{:?} permission required", permission).to_lowercase()));
+                return Some(command.reply(AdaCommandType::PermissionDenied, Some(data)));
+            }
+        }
+
+        let response = match command.header.command_type {
+            AdaCommandType::CreateNetwork => self.create_network(command),
+            AdaCommandType::DeleteNetwork => self.delete_network(command),
+            AdaCommandType::AddNode => self.add_node(command),
+            AdaCommandType::RemoveNode => self.remove_node(command),
+            AdaCommandType::ConnectNodes => self.connect_nodes(command, true),
+            AdaCommandType::DisconnectNodes => self.connect_nodes(command, false),
+            AdaCommandType::ListNodeTypes => self.list_node_types(command),
+            AdaCommandType::ExportGraph => self.export_graph(command),
+            AdaCommandType::ExecuteNode => return self.run_node(command, false, deferred),
+            AdaCommandType::PropagateNode => return self.run_node(command, true, deferred),
+            AdaCommandType::CancelRun => return self.cancel_run(command, deferred),
+            AdaCommandType::QueryRun => self.query_run(command),
+            AdaCommandType::Subscribe => self.subscribe(command),
+            AdaCommandType::Unsubscribe => self.unsubscribe(command),
+            AdaCommandType::CloseConnection => self.close_connection(command),
+            #[cfg(feature = "serde")]
+            AdaCommandType::Snapshot => self.take_snapshot(command, pending),
+            _ => command.error("unsupported command type"),
+        };
+        Some(response)
+    }
+
+    fn required_permission(command_type: &AdaCommandType) -> Option<Permission> {
+        match command_type {
+            AdaCommandType::CreateNetwork | AdaCommandType::DeleteNetwork => Some(Permission::Admin),
+            AdaCommandType::Snapshot => Some(Permission::Admin),
+            AdaCommandType::AddNode | AdaCommandType::RemoveNode => Some(Permission::Modify),
+            AdaCommandType::ConnectNodes | AdaCommandType::DisconnectNodes => Some(Permission::Modify),
+            AdaCommandType::ExecuteNode | AdaCommandType::PropagateNode => Some(Permission::Execute),
+            AdaCommandType::CancelRun => Some(Permission::Execute),
+            AdaCommandType::ExportGraph | AdaCommandType::QueryRun => Some(Permission::Read),
+            AdaCommandType::Subscribe | AdaCommandType::Unsubscribe => Some(Permission::Read),
+            _ => None,
+        }
+    }
+
+    // Commands without an origin were produced by the executor itself and are always allowed
+    fn is_allowed(&self, command: &AdaCommand, permission: Permission) -> bool {
+        match (&self.access_control, &command.origin) {
+            (Some(access_control), Some(origin)) => {
+                let identity = origin.identity.as_deref().unwrap_or(ANONYMOUS);
+                // A snapshot holds every network, so it needs the permission on all of them
+                let network = match command.header.command_type {
+                    AdaCommandType::Snapshot => None,
+                    _ => command.header.network,
+                };
+                access_control.is_allowed(identity, network, permission)
+            }
+            _ => true,
+        }
+    }
+
+    // `failure_policy` in the data is parsed as a `FailurePolicy`, e.g. `abort` or `error_port:failed`
+    fn create_network(&mut self, command: &AdaCommand) -> AdaCommand {
+        let id = match command.header.network {
+            Some(id) if !id.is_nil() => id,
+            _ => Uuid::new_v4(),
+        };
+        if self.networks.contains_key(&id) {
+            return command.error("network already exists");
+        }
+        let failure_policy = match command.data.as_ref().and_then(|data| data.get_item("failure_policy")) {
+            Some(Data::Text(policy)) => match policy.parse::<FailurePolicy>() {
+                Ok(policy) => policy,
+                Err(e) => return command.error(&e),
+            },
+            Some(other) => return command.error(&format!("failure_policy must be text, got {:?}", other)),
+            None => FailurePolicy::default(),
+        };
+        self.networks.insert(id, Arc::new(Graph::with_id(id).with_failure_policy(failure_policy)));
+        log::info!("Created network {}", id);
+
+        let mut data = Container::new();
+        data.add_item("network", Data::Text(id.to_string()));
+        let mut response = command.reply(AdaCommandType::Response, Some(data));
+        response.header.network = Some(id);
+        response
+    }
+
+    fn delete_network(&mut self, command: &AdaCommand) -> AdaCommand {
+        match command.header.network.and_then(|id| self.networks.remove(&id)) {
+            Some(graph) => {
+                let mut runs = self.runs.lock().unwrap();
+                runs.statuses.remove(&graph.id());
+                runs.history.remove(&graph.id());
+                runs.subscriptions.retain(|_, subscription| subscription.network != graph.id());
+                drop(runs);
+                log::info!("Deleted network {:?}", command.header.network);
+                command.reply(AdaCommandType::Response, None)
+            }
+            None => command.error("unknown network"),
+        }
+    }
+
+    // Runs in progress keep using the snapshot they started with
+    fn graph_mut(&mut self, network: Option<Uuid>) -> Option<&mut Graph> {
+        network.and_then(|id| self.networks.get_mut(&id)).map(Arc::make_mut)
+    }
+
+    // AddNode carries the node type under `type` and execution settings under `node.*`, every other
+    // item is the node's config
+    fn add_node(&mut self, command: &AdaCommand) -> AdaCommand {
+        if !command.header.network.is_some_and(|id| self.networks.contains_key(&id)) {
+            return command.error("unknown network");
+        }
+        let mut config = command.data.clone().unwrap_or_default();
+        let type_name = match config.remove_item("type") {
+            Some(Data::Text(type_name)) => type_name,
+            _ => return command.error("missing node type"),
+        };
+        let policy = match ExecutionPolicy::take_from(&mut config) {
+            Ok(policy) => policy,
+            Err(e) => return command.error(&e),
+        };
+        let id = match command.header.node {
+            Some(id) if !id.is_nil() => id,
+            _ => Uuid::new_v4(),
+        };
+        // Replacing the node would leave it with the old node's state and edges
+        if command.header.network.and_then(|network| self.networks.get(&network)).is_some_and(|graph| graph.get_node(&id).is_some()) {
+            return command.error("node already exists");
+        }
+        let node = match self.registry.create_node(&type_name, id, config) {
+            Ok(node) => node,
+            Err(e) => return command.error(&e),
+        };
+        if let Some(graph) = self.graph_mut(command.header.network) {
+            graph.add_node(node.with_policy(policy));
+        }
+        log::info!("Added {} node {} to network {:?}", type_name, id, command.header.network);
+
+        let mut data = Container::new();
+        data.add_item("node", Data::Text(id.to_string()));
+        let mut response = command.reply(AdaCommandType::Response, Some(data));
+        response.header.node = Some(id);
+        response
+    }
+
+    fn remove_node(&mut self, command: &AdaCommand) -> AdaCommand {
+        let graph = match self.graph_mut(command.header.network) {
+            Some(graph) => graph,
+            None => return command.error("unknown network"),
+        };
+        match command.header.node.and_then(|id| graph.remove_node(&id)) {
+            Some(_) => command.reply(AdaCommandType::Response, None),
+            None => command.error("unknown node"),
+        }
+    }
+
+    // The edge starts at the header's node, `from_port`, `to` and `to_port` describe the rest
+    fn connect_nodes(&mut self, command: &AdaCommand, connect: bool) -> AdaCommand {
+        let edge = match Dispatcher::edge(command) {
+            Some(edge) => edge,
+            None => return command.error("expected from_port, to and to_port"),
+        };
+        let graph = match self.graph_mut(command.header.network) {
+            Some(graph) => graph,
+            None => return command.error("unknown network"),
+        };
+        let result = if connect {
+            graph.connect(edge)
+        } else if graph.disconnect(&edge) {
+            Ok(())
+        } else {
+            Err("unknown edge".to_string())
+        };
+        match result {
+            Ok(_) => command.reply(AdaCommandType::Response, None),
+            Err(e) => command.error(&e),
+        }
+    }
+
+    fn edge(command: &AdaCommand) -> Option<Edge> {
+        let data = command.data.as_ref()?;
+        let text = |key: &str| match data.get_item(key) {
+            Some(Data::Text(value)) => Some(value.clone()),
+            _ => None,
+        };
+        Some(Edge {
+            from: command.header.node?,
+            from_port: text("from_port")?,
+            to: Uuid::parse_str(&text("to")?).ok()?,
+            to_port: text("to_port")?,
+        })
+    }
+
+    // Every type is listed as `<type>.description`, `<type>.inputs` and `<type>.outputs`
+    fn list_node_types(&self, command: &AdaCommand) -> AdaCommand {
+        let mut data = Container::new();
+        for descriptor in self.registry.descriptors() {
+            let name = &descriptor.type_name;
+            data.add_item(&format!("{}.description", name), Data::Text(descriptor.description.clone()));
+            data.add_item(&format!("{}.inputs", name), Data::Text(descriptor.inputs.join(",")));
+            data.add_item(&format!("{}.outputs", name), Data::Text(descriptor.outputs.join(",")));
+        }
+        command.reply(AdaCommandType::Response, Some(data))
+    }
+
+    // Answers with the DOT source under `dot`, a `status` of true colours nodes by their last execution
+    fn export_graph(&self, command: &AdaCommand) -> AdaCommand {
+        let graph = match command.header.network.and_then(|id| self.networks.get(&id)) {
+            Some(graph) => graph,
+            None => return command.error("unknown network"),
+        };
+        let with_status = matches!(command.data.as_ref().and_then(|data| data.get_item("status")), Some(Data::Boolean(true)));
+        let runs = self.runs.lock().unwrap();
+        let statuses = if with_status { runs.statuses.get(&graph.id()) } else { None };
+
+        let mut data = Container::new();
+        data.add_item("dot", Data::Text(graph.to_dot(statuses)));
+        command.reply(AdaCommandType::Response, Some(data))
+    }
+
+    // Execute answers with the node's output, propagate with every executed node's output keyed
+    // `<node>.<key>`, failures are reported as an output under `error`. Both add `run.id`,
+    // `run.status`, the comma separated `run.incomplete` if any node did not finish and
+    // `run.failed` if any node failed, with each failure described under `run.error.<node>`.
+    //
+    // The arguments may carry `run.id` to choose the run's id and `run.timeout_ms` to bound it.
+    fn run_node(&mut self, command: &AdaCommand, propagate: bool, deferred: Option<&Sender<AdaCommand>>) -> Option<AdaCommand> {
+        let graph = match command.header.network.and_then(|id| self.networks.get(&id)) {
+            Some(graph) => Arc::clone(graph),
+            None => return Some(command.error("unknown network")),
+        };
+        let node = match command.header.node.filter(|id| graph.get_node(id).is_some()) {
+            Some(node) => node,
+            None => return Some(command.error("unknown node")),
+        };
+        let mut arguments = command.data.clone().unwrap_or_default();
+        let options = match Dispatcher::run_options(&mut arguments) {
+            Ok(options) => options,
+            Err(e) => return Some(command.error(&e)),
+        };
+
+        {
+            let mut runs = self.runs.lock().unwrap();
+            if runs.active.contains_key(&options.id) {
+                return Some(command.error("run already exists"));
+            }
+            runs.active.insert(options.id, ActiveRun {
+                network: graph.id(),
+                token: options.token.clone(),
+                cancellations: Vec::new(),
+            });
+        }
+        log::info!("Starting run {} at node {} of network {}", options.id, node, graph.id());
+
+        let request = AdaCommand { header: command.header.clone(), data: None, origin: None };
+        let runs = Arc::clone(&self.runs);
+        let reply = deferred.cloned();
+        let run_id = options.id;
+        let run = thread::spawn(move || {
+            // Whatever happens to the run, it has to leave the active runs and everyone waiting on it gets an answer
+            let report = panic::catch_unwind(AssertUnwindSafe(|| {
+                let (events, received) = mpsc::channel::<NodeEvent>();
+                let publisher = {
+                    let runs = Arc::clone(&runs);
+                    thread::spawn(move || {
+                        for event in received {
+                            runs.lock().unwrap().publish(&event);
+                        }
+                    })
+                };
+                let options = RunOptions { events: Some(events), ..options };
+                let report = graph.run(&node, arguments, propagate, &options).expect("node exists in the snapshot");
+                // Every event is pushed before the run's response
+                drop(options);
+                let _ = publisher.join();
+                report
+            }));
+            let report = report.inspect_err(|_| log::error!("Run {} panicked", run_id)).ok();
+            let response = match &report {
+                Some(report) => Dispatcher::run_response(&request, report, propagate),
+                None => request.error("run failed"),
+            };
+
+            let cancellations = {
+                let mut runs = runs.lock().unwrap_or_else(PoisonError::into_inner);
+                let cancellations = runs.active.remove(&run_id).map(|run| run.cancellations).unwrap_or_default();
+                if let Some(report) = &report {
+                    runs.record(report.clone());
+                }
+                cancellations
+            };
+            for cancellation in cancellations {
+                if let Some(origin) = &cancellation.origin {
+                    let reply = match &report {
+                        Some(report) => cancellation.reply(AdaCommandType::Response, Some(Dispatcher::run_summary(report))),
+                        None => cancellation.error("run failed"),
+                    };
+                    let _ = origin.reply.send(reply);
+                }
+            }
+
+            match reply {
+                Some(reply) => {
+                    if reply.send(response).is_err() {
+                        log::debug!("Origin of run {} is gone, dropping response", run_id);
+                    }
+                    None
+                }
+                None => Some(response),
+            }
+        });
+
+        if deferred.is_some() {
+            return None;
+        }
+        match run.join() {
+            Ok(response) => response,
+            Err(_) => Some(command.error("run failed")),
+        }
+    }
+
+    fn run_options(arguments: &mut Container) -> Result<RunOptions, String> {
+        let mut options = RunOptions::new();
+        match arguments.remove_item("run.id") {
+            Some(Data::Text(id)) => options.id = Uuid::parse_str(&id).map_err(|e| format!("invalid run.id: {}", e))?,
+            Some(other) => return Err(format!("run.id must be text, got {:?}", other)),
+            None => {}
+        }
+        match arguments.remove_item("run.timeout_ms") {
+            Some(Data::Integer(timeout)) if timeout > 0 => options.timeout = Some(Duration::from_millis(timeout as u64)),
+            Some(other) => return Err(format!("run.timeout_ms must be a positive integer, got {:?}", other)),
+            None => {}
+        }
+        Ok(options)
+    }
+
+    fn run_response(request: &AdaCommand, report: &RunReport, propagate: bool) -> AdaCommand {
+        let mut data = Container::new();
+        if propagate {
+            for (id, output) in &report.outputs {
+                for (key, value) in output.iter() {
+                    data.add_item(&format!("{}.{}", id, key), value.clone());
+                }
+            }
+            for (id, error) in &report.errors {
+                data.add_item(&format!("{}.error", id), Data::Text(error.message.clone()));
+            }
+        } else if let Some(output) = report.outputs.values().next() {
+            data.merge(output);
+        } else if let Some(error) = report.errors.values().next() {
+            data.merge(&error.to_container());
+        }
+        data.merge(&Dispatcher::run_summary(report));
+        request.reply(AdaCommandType::Response, Some(data))
+    }
+
+    fn run_summary(report: &RunReport) -> Container {
+        let mut data = Container::new();
+        data.add_item("run.id", Data::Text(report.id.to_string()));
+        data.add_item("run.status", Data::Text(report.status.to_string()));
+        let incomplete = report.incomplete();
+        if !incomplete.is_empty() {
+            let incomplete: Vec<String> = incomplete.iter().map(Uuid::to_string).collect();
+            data.add_item("run.incomplete", Data::Text(incomplete.join(",")));
+        }
+        let failed = report.failed();
+        if !failed.is_empty() {
+            for id in &failed {
+                data.add_item(&format!("run.error.{}", id), Data::Text(report.errors[id].to_string()));
+            }
+            let failed: Vec<String> = failed.iter().map(Uuid::to_string).collect();
+            data.add_item("run.failed", Data::Text(failed.join(",")));
+        }
+        data
+    }
+
+    // CancelRun names the run under `run` and the run's network in its header. With a reply channel
+    // the response waits until the run stopped and reports it like the run's own response, without
+    // node outputs. Otherwise it is sent right away with `run.status` set to `cancelling`.
+    fn cancel_run(&mut self, command: &AdaCommand, deferred: Option<&Sender<AdaCommand>>) -> Option<AdaCommand> {
+        let run = match Dispatcher::run_argument(command) {
+            Ok(run) => run,
+            Err(e) => return Some(command.error(&e)),
+        };
+        let mut runs = self.runs.lock().unwrap();
+        let active = match runs.active.get_mut(&run).filter(|active| Some(active.network) == command.header.network) {
+            Some(active) => active,
+            None => return Some(command.error("unknown run")),
+        };
+        active.token.cancel();
+        log::info!("Cancelling run {}", run);
+
+        if deferred.is_some() {
+            active.cancellations.push(command.clone());
+            return None;
+        }
+        let mut data = Container::new();
+        data.add_item("run.id", Data::Text(run.to_string()));
+        data.add_item("run.status", Data::Text("cancelling".to_string()));
+        Some(command.reply(AdaCommandType::Response, Some(data)))
+    }
+
+    // QueryRun names the run under `run` and its network in the header. A run in progress only
+    // reports `run.status` as `running`. A finished run is described like a propagate response,
+    // plus `run.node`, `run.started_at` (seconds since the epoch), `run.duration_ms` and its
+    // trace: `trace.count` attempts with their `node`, `attempt`, `status`, `started_at`,
+    // `duration_ms`, `error`, `input.<key>` and `output.<key>` under `trace.<index>.`.
+    fn query_run(&self, command: &AdaCommand) -> AdaCommand {
+        let run = match Dispatcher::run_argument(command) {
+            Ok(run) => run,
+            Err(e) => return command.error(&e),
+        };
+        let runs = self.runs.lock().unwrap();
+        if runs.active.get(&run).is_some_and(|active| Some(active.network) == command.header.network) {
+            let mut data = Container::new();
+            data.add_item("run.id", Data::Text(run.to_string()));
+            data.add_item("run.status", Data::Text("running".to_string()));
+            return command.reply(AdaCommandType::Response, Some(data));
+        }
+        let report = command.header.network
+            .and_then(|network| runs.history.get(&network))
+            .and_then(|history| history.iter().find(|report| report.id == run));
+        match report {
+            Some(report) => {
+                let mut response = Dispatcher::run_response(command, report, true);
+                if let Some(data) = response.data.as_mut() {
+                    data.merge(&Dispatcher::run_trace(report));
+                }
+                response
+            }
+            None => command.error("unknown run"),
+        }
+    }
+
+    fn run_trace(report: &RunReport) -> Container {
+        let mut data = Container::new();
+        data.add_item("run.node", Data::Text(report.node.to_string()));
+        data.add_item("run.started_at", Data::Float(seconds_since_epoch(report.started_at)));
+        data.add_item("run.duration_ms", Data::Float(report.duration.as_secs_f64() * 1000.0));
+        data.add_item("trace.count", Data::Integer(report.trace.len() as i32));
+        for (index, entry) in report.trace.iter().enumerate() {
+            let key = |name: &str| format!("trace.{}.{}", index, name);
+            data.add_item(&key("node"), Data::Text(entry.node.to_string()));
+            data.add_item(&key("attempt"), Data::Integer(entry.attempt as i32));
+            data.add_item(&key("status"), Data::Text(entry.status.to_string()));
+            data.add_item(&key("started_at"), Data::Float(seconds_since_epoch(entry.started_at)));
+            data.add_item(&key("duration_ms"), Data::Float(entry.duration.as_secs_f64() * 1000.0));
+            if let Some(error) = &entry.error {
+                data.add_item(&key("error"), Data::Text(error.to_string()));
+            }
+            for (name, value) in entry.input.iter() {
+                data.add_item(&key(&format!("input.{}", name)), value.clone());
+            }
+            for (name, value) in entry.output.iter().flat_map(Container::iter) {
+                data.add_item(&key(&format!("output.{}", name)), value.clone());
+            }
+        }
+        data
+    }
+
+    // Subscribe registers the origin's connection for `Event` frames of the header's network, limited
+    // to the header's node if set and to the output `port` in the data if set. Answers with the
+    // subscription's id under `subscription`.
+    fn subscribe(&mut self, command: &AdaCommand) -> AdaCommand {
+        let graph = match command.header.network.and_then(|id| self.networks.get(&id)) {
+            Some(graph) => graph,
+            None => return command.error("unknown network"),
+        };
+        if command.header.node.is_some_and(|id| graph.get_node(&id).is_none()) {
+            return command.error("unknown node");
+        }
+        let port = match command.data.as_ref().and_then(|data| data.get_item("port")) {
+            Some(Data::Text(port)) => Some(port.clone()),
+            Some(other) => return command.error(&format!("port must be text, got {:?}", other)),
+            None => None,
+        };
+        let (connection, reply) = match command.origin.as_ref().and_then(|origin| Some((origin.connection?, origin.reply.clone()))) {
+            Some(connection) => connection,
+            None => return command.error("subscriptions need a persistent connection"),
+        };
+
+        let id = Uuid::new_v4();
+        self.runs.lock().unwrap().subscriptions.insert(id, Subscription {
+            network: graph.id(),
+            node: command.header.node,
+            port,
+            connection,
+            reply,
+        });
+        log::info!("Connection {} subscribed to network {} as {}", connection, graph.id(), id);
+
+        let mut data = Container::new();
+        data.add_item("subscription", Data::Text(id.to_string()));
+        command.reply(AdaCommandType::Response, Some(data))
+    }
+
+    // Unsubscribe names the subscription under `subscription`, only its own connection may remove it
+    fn unsubscribe(&mut self, command: &AdaCommand) -> AdaCommand {
+        let id = match command.data.as_ref().and_then(|data| data.get_item("subscription")) {
+            Some(Data::Text(id)) => match Uuid::parse_str(id) {
+                Ok(id) => id,
+                Err(e) => return command.error(&format!("invalid subscription: {}", e)),
+            },
+            _ => return command.error("missing subscription"),
+        };
+        let connection = command.origin.as_ref().and_then(|origin| origin.connection);
+        let mut runs = self.runs.lock().unwrap();
+        match runs.subscriptions.get(&id) {
+            Some(subscription) if Some(subscription.connection) == connection => {
+                runs.subscriptions.remove(&id);
+                command.reply(AdaCommandType::Response, None)
+            }
+            _ => command.error("unknown subscription"),
+        }
+    }
+
+    // Transports send CloseConnection on behalf of a connection that went away
+    fn close_connection(&mut self, command: &AdaCommand) -> AdaCommand {
+        if let Some(connection) = command.origin.as_ref().and_then(|origin| origin.connection) {
+            let mut runs = self.runs.lock().unwrap();
+            runs.subscriptions.retain(|_, subscription| subscription.connection != connection);
+        }
+        command.reply(AdaCommandType::Response, None)
+    }
+
+    // Answers with the number of `networks` and `queued` commands written
+    #[cfg(feature = "serde")]
+    fn take_snapshot(&self, command: &AdaCommand, pending: &[AdaCommand]) -> AdaCommand {
+        let path = match &self.snapshot_path {
+            Some(path) => path,
+            None => return command.error("snapshots are not configured"),
+        };
+        let queued = match self.snapshot(path, pending) {
+            Ok(queued) => queued,
+            Err(e) => {
+                log::error!("Unable to write snapshot: {}", e);
+                return command.error(&format!("unable to write snapshot: {}", e));
+            }
+        };
+        let mut data = Container::new();
+        data.add_item("networks", Data::Integer(self.networks.len() as i32));
+        data.add_item("queued", Data::Integer(queued as i32));
+        command.reply(AdaCommandType::Response, Some(data))
+    }
+
+    // The run a CancelRun or QueryRun refers to
+    fn run_argument(command: &AdaCommand) -> Result<Uuid, String> {
+        match command.data.as_ref().and_then(|data| data.get_item("run")) {
+            Some(Data::Text(run)) => Uuid::parse_str(run).map_err(|e| format!("invalid run: {}", e)),
+            _ => Err("missing run".to_string()),
+        }
+    }
+}
+
+fn seconds_since_epoch(time: SystemTime) -> f64 {
+    time.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs_f64()
+}
@@ -1,2 +1,5 @@
 pub mod command_scheduler;
-
+pub mod dispatcher;
+pub mod access_control;
+#[cfg(feature = "serde")]
+pub mod snapshot;
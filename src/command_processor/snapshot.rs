@@ -0,0 +1,118 @@
+//! Snapshot file holding every network with its node state and the commands still queued.
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "taken_at": 1760000000.25,
+//!   "networks": [{ "id": "5f0c…", "nodes": […], "edges": […] }],
+//!   "queue": [{ "identity": "alice", "command": { "header": { "version": 0, "command_type": "PropagateNode", … }, "data": … } }]
+//! }
+//! ```
+//!
+//! Networks use the graph file format, see `network::graph_file`. Queued commands keep the
+//! identity they were sent with, so restored commands are checked against the access control
+//! list like any other. Commands tied to a connection, which does not survive a restart, are left out,
+//! and so are commands held by the write-ahead log.
+
+use std::fs::{self, File};
+use std::io::{Error, ErrorKind, Write};
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::command_processor::access_control::ANONYMOUS;
+use crate::network::graph::Graph;
+use crate::network::graph_file::GraphFile;
+use crate::network::registry::NodeRegistry;
+use crate::protocol::commands::{AdaCommand, AdaCommandType, CommandOrigin};
+
+/// Bumped whenever the snapshot file format changes incompatibly.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotFile {
+    version: u32,
+    /// Seconds since the epoch
+    taken_at: f64,
+    #[serde(default)]
+    networks: Vec<GraphFile>,
+    #[serde(default)]
+    queue: Vec<QueuedCommand>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct QueuedCommand {
+    /// Unset for commands the executor queued itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    identity: Option<String>,
+    command: AdaCommand,
+}
+
+/// What a snapshot held, see `write` and `read`.
+pub struct Snapshot {
+    pub networks: Vec<Graph>,
+    pub queue: Vec<AdaCommand>,
+}
+
+/// Writes `networks` and `queue` to `path`, replacing an earlier snapshot only once the new one is
+/// complete. Returns how many of the queued commands were written.
+pub fn write<'a>(path: &Path, networks: impl IntoIterator<Item = &'a Graph>, queue: &[AdaCommand]) -> std::io::Result<usize> {
+    let networks = networks.into_iter().map(Graph::to_file).collect::<std::io::Result<_>>()?;
+    let queue: Vec<QueuedCommand> = queue.iter()
+        .filter(|command| !matches!(command.header.command_type, AdaCommandType::CloseConnection | AdaCommandType::Subscribe | AdaCommandType::Unsubscribe))
+        // The write-ahead log replays these itself
+        .filter(|command| command.origin.as_ref().is_none_or(|origin| origin.wal_sequence.is_none()))
+        .map(|command| QueuedCommand {
+            identity: command.origin.as_ref().map(|origin| origin.identity.clone().unwrap_or_else(|| ANONYMOUS.to_string())),
+            command: command.clone(),
+        })
+        .collect();
+    let queued = queue.len();
+    let file = SnapshotFile {
+        version: SNAPSHOT_VERSION,
+        taken_at: SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs_f64(),
+        networks,
+        queue,
+    };
+    let json = serde_json::to_string(&file).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let mut file = File::create(&partial)?;
+    file.write_all(json.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&partial, path)?;
+    // The rename only survives a crash once the directory holding it is synced
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    File::open(dir)?.sync_all()?;
+    Ok(queued)
+}
+
+/// Reads a snapshot, building its nodes through `registry`. Responses to restored commands are dropped.
+pub fn read(path: &Path, registry: &NodeRegistry) -> std::io::Result<Snapshot> {
+    let invalid = |message: String| Error::new(ErrorKind::InvalidData, format!("{}: {}", path.display(), message));
+    let file: SnapshotFile = serde_json::from_str(&fs::read_to_string(path)?).map_err(|e| invalid(e.to_string()))?;
+    if file.version != SNAPSHOT_VERSION {
+        return Err(invalid(format!("snapshot version {} is not supported, expected {}", file.version, SNAPSHOT_VERSION)));
+    }
+
+    let networks = file.networks.into_iter()
+        .map(|network| {
+            let id = network.id;
+            Graph::from_file(network, registry).map_err(|e| invalid(format!("network {}: {}", id, e)))
+        })
+        .collect::<std::io::Result<_>>()?;
+    let queue = file.queue.into_iter()
+        .map(|queued| {
+            let mut command = queued.command;
+            command.origin = queued.identity.map(|identity| {
+                let (reply, _) = mpsc::channel();
+                CommandOrigin::new(reply).with_identity(identity)
+            });
+            command
+        })
+        .collect();
+    Ok(Snapshot { networks, queue })
+}
@@ -0,0 +1,3 @@
+pub mod protocol;
+pub mod command_processor;
+pub mod network;
@@ -1,14 +1,18 @@
-use std::ops::DerefMut;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use log::LevelFilter;
 
 use simple_logger::SimpleLogger;
 
-use command_processor::command_scheduler;
-
-mod protocol;
-mod command_processor;
-mod network;
+use ada_executor::command_processor::access_control::AccessControl;
+use ada_executor::command_processor::command_scheduler::{self, SchedulerOptions};
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::network::registry::NodeRegistry;
+use ada_executor::protocol;
+use ada_executor::protocol::auth::KeyStore;
+use ada_executor::protocol::capture::Capture;
+use ada_executor::protocol::wal::WriteAheadLog;
 
 fn main() {
     SimpleLogger::new()
@@ -18,12 +22,208 @@ fn main() {
 
     let server_address = "127.0.0.1:7878";
     let command_queue = Arc::new(Mutex::new(Vec::new()));
+
+    let key_store = std::env::var("ADA_KEY_STORE").ok().map(|path| {
+        Arc::new(KeyStore::load(path.as_ref()).expect("Invalid key store"))
+    });
+
+    // Commands accepted but not handled before the last stop, queued again behind the snapshot's
+    let (wal, replayed) = match std::env::var("ADA_WAL_DIR") {
+        Ok(dir) => {
+            let (wal, replayed) = WriteAheadLog::open(dir.as_ref()).expect("Invalid write-ahead log");
+            (Some(Arc::new(wal)), replayed)
+        }
+        Err(_) => (None, Vec::new()),
+    };
+
+    let capture = std::env::var("ADA_CAPTURE").ok().map(|path| {
+        log::info!("Capturing every accepted command to {}", path);
+        Arc::new(Capture::open(path.as_ref()).expect("Invalid capture file"))
+    });
+
     let command_queue_server = Arc::clone(&command_queue);
     let server = protocol::tcp_server::TcpServer::new(server_address, command_queue_server);
+    let server = match &key_store {
+        Some(key_store) => server.with_key_store(Arc::clone(key_store)),
+        None => server,
+    };
+    let server = match &wal {
+        Some(wal) => server.with_wal(Arc::clone(wal)),
+        None => server,
+    };
+    let server = match &capture {
+        Some(capture) => server.with_capture(Arc::clone(capture)),
+        None => server,
+    };
+
+    #[cfg(feature = "tls")]
+    let server = match (std::env::var("ADA_TLS_CERT"), std::env::var("ADA_TLS_KEY")) {
+        (Ok(cert), Ok(key)) => {
+            let client_ca = std::env::var("ADA_TLS_CLIENT_CA").ok();
+            let config = protocol::tls::server_config(cert.as_ref(), key.as_ref(), client_ca.as_deref().map(std::path::Path::new))
+                .expect("Invalid TLS configuration");
+            server.with_tls(config)
+        }
+        _ => server,
+    };
+
+    let registry = NodeRegistry::with_builtins();
+    #[cfg(feature = "wasm")]
+    let registry = match std::env::var("ADA_PLUGIN_DIR") {
+        Ok(plugin_dir) => {
+            let mut registry = registry;
+            let count = ada_executor::network::plugins::wasm::load_dir(&mut registry, plugin_dir.as_ref(), wasm_limits()).expect("Invalid WebAssembly plugin");
+            log::info!("Loaded {} WebAssembly node types from {}", count, plugin_dir);
+            registry
+        }
+        Err(_) => registry,
+    };
+    #[cfg(feature = "plugins")]
+    let registry = match std::env::var("ADA_NATIVE_PLUGIN_DIR") {
+        Ok(plugin_dir) => {
+            let mut registry = registry;
+            let count = ada_executor::network::plugins::native::load_dir(&mut registry, plugin_dir.as_ref()).expect("Invalid native plugin");
+            log::info!("Loaded {} native plugins from {}", count, plugin_dir);
+            registry
+        }
+        Err(_) => registry,
+    };
+    #[cfg(feature = "process")]
+    let registry = match std::env::var("ADA_ALLOW_PROCESS_EXEC") {
+        Ok(commands) => {
+            let mut registry = registry;
+            let allowed: Vec<String> = commands.split(',').map(str::trim).filter(|command| !command.is_empty()).map(String::from).collect();
+            log::info!("process.exec may run {}", allowed.join(", "));
+            ada_executor::network::nodes::process::register(&mut registry, allowed);
+            registry
+        }
+        Err(_) => registry,
+    };
+
+    let mut dispatcher = match std::env::var("ADA_ACL") {
+        Ok(path) => Dispatcher::new().with_access_control(AccessControl::load(path.as_ref()).expect("Invalid access control list")),
+        Err(_) => Dispatcher::new(),
+    }.with_registry(registry);
+    if let Some(limit) = std::env::var("ADA_RUN_HISTORY").ok().and_then(|limit| limit.parse().ok()) {
+        dispatcher = dispatcher.with_history_limit(limit);
+    }
 
+    #[cfg(feature = "serde")]
+    if let Ok(graph_dir) = std::env::var("ADA_GRAPH_DIR") {
+        let count = dispatcher.load_networks(graph_dir.as_ref()).expect("Invalid graph file");
+        log::info!("Preloaded {} networks from {}", count, graph_dir);
+    }
+
+    #[cfg(feature = "serde")]
+    if let Ok(snapshot) = std::env::var("ADA_SNAPSHOT") {
+        let path = std::path::PathBuf::from(snapshot);
+        if path.exists() {
+            let queued = dispatcher.restore(&path).expect("Invalid snapshot");
+            command_queue.lock().unwrap().extend(queued);
+        }
+        dispatcher = dispatcher.with_snapshot_path(path);
+    }
+    // Behind the older commands restored from the snapshot, which leaves out those in the write-ahead log
+    command_queue.lock().unwrap().extend(replayed);
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let options = SchedulerOptions {
+        snapshot_interval: std::env::var("ADA_SNAPSHOT_INTERVAL_S").ok()
+            .and_then(|seconds| seconds.parse().ok())
+            .map(Duration::from_secs),
+        shutdown: Some(Arc::clone(&shutdown)),
+        wal: wal.clone(),
+    };
     let command_queue_listener = Arc::clone(&command_queue);
-    command_scheduler::listen(command_queue_listener);
+    let scheduler = Mutex::new(Some(command_scheduler::start(command_queue_listener, dispatcher, options)));
+    // Lets the scheduler write its final snapshot before the process exits
+    ctrlc::set_handler(move || {
+        shutdown.store(true, Ordering::SeqCst);
+        if let Some(scheduler) = scheduler.lock().unwrap().take() {
+            let _ = scheduler.join();
+        }
+        std::process::exit(0);
+    }).expect("Unable to install signal handler");
+
+    #[cfg(unix)]
+    if let Ok(socket_path) = std::env::var("ADA_UNIX_SOCKET") {
+        let mode = std::env::var("ADA_UNIX_SOCKET_MODE").ok()
+            .and_then(|mode| u32::from_str_radix(&mode, 8).ok())
+            .unwrap_or(protocol::unix_server::DEFAULT_SOCKET_MODE);
+        let unix_server = protocol::unix_server::UnixServer::new(&socket_path, mode, Arc::clone(&command_queue));
+        let unix_server = match &key_store {
+            Some(key_store) => unix_server.with_key_store(Arc::clone(key_store)),
+            None => unix_server,
+        };
+        let unix_server = match &wal {
+            Some(wal) => unix_server.with_wal(Arc::clone(wal)),
+            None => unix_server,
+        };
+        let unix_server = match &capture {
+            Some(capture) => unix_server.with_capture(Arc::clone(capture)),
+            None => unix_server,
+        };
+        std::thread::spawn(move || {
+            if let Err(e) = unix_server.run() {
+                log::error!("Unix socket server stopped: {}", e);
+            }
+        });
+    }
+
+    #[cfg(feature = "http")]
+    if let Ok(http_address) = std::env::var("ADA_HTTP_ADDRESS") {
+        let gateway = protocol::http_gateway::HttpGateway::new(&http_address, Arc::clone(&command_queue));
+        let gateway = match &key_store {
+            Some(key_store) => gateway.with_key_store(Arc::clone(key_store)),
+            None => gateway,
+        };
+        let gateway = match &wal {
+            Some(wal) => gateway.with_wal(Arc::clone(wal)),
+            None => gateway,
+        };
+        let gateway = match &capture {
+            Some(capture) => gateway.with_capture(Arc::clone(capture)),
+            None => gateway,
+        };
+        std::thread::spawn(move || {
+            if let Err(e) = gateway.run() {
+                log::error!("HTTP gateway stopped: {}", e);
+            }
+        });
+    }
+
+    #[cfg(feature = "websocket")]
+    if let Ok(websocket_address) = std::env::var("ADA_WS_ADDRESS") {
+        let websocket_server = protocol::websocket_server::WebSocketServer::new(&websocket_address, Arc::clone(&command_queue));
+        let websocket_server = match &key_store {
+            Some(key_store) => websocket_server.with_key_store(Arc::clone(key_store)),
+            None => websocket_server,
+        };
+        let websocket_server = match &wal {
+            Some(wal) => websocket_server.with_wal(Arc::clone(wal)),
+            None => websocket_server,
+        };
+        let websocket_server = match &capture {
+            Some(capture) => websocket_server.with_capture(Arc::clone(capture)),
+            None => websocket_server,
+        };
+        std::thread::spawn(move || {
+            if let Err(e) = websocket_server.run() {
+                log::error!("WebSocket server stopped: {}", e);
+            }
+        });
+    }
 
 
     let _ = server.run();
 }
+
+// Most fuel and memory a WebAssembly node may ask for, `ADA_WASM_MAX_FUEL` and `ADA_WASM_MAX_MEMORY_BYTES`
+#[cfg(feature = "wasm")]
+fn wasm_limits() -> ada_executor::network::plugins::wasm::Limits {
+    let defaults = ada_executor::network::plugins::wasm::Limits::default();
+    ada_executor::network::plugins::wasm::Limits {
+        fuel: std::env::var("ADA_WASM_MAX_FUEL").ok().and_then(|fuel| fuel.parse().ok()).unwrap_or(defaults.fuel),
+        max_memory_bytes: std::env::var("ADA_WASM_MAX_MEMORY_BYTES").ok().and_then(|bytes| bytes.parse().ok()).unwrap_or(defaults.max_memory_bytes),
+    }
+}
@@ -1,9 +1,9 @@
 use std::collections::HashMap;
 use std::fmt;
-use crate::protocol::commands::AdaCommandType;
 
 // Define an enum that can hold different types of data
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum Data {
     Integer(i32) = 1,
@@ -49,7 +49,10 @@ impl fmt::Display for Data {
     }
 }
 
-#[derive(Debug, Clone)]
+// Serialized as a plain map of key to value, e.g. `{"int": {"Integer": 1}}`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Container {
     items: HashMap<String, Data>,
 }
@@ -67,7 +70,6 @@ impl Container {
                 Data::Float(_) => size += 8,
                 Data::Text(value) =>  size += value.len() + 1,
                 Data::Boolean(_) => size += 1,
-                _ => log::warn!("Unknown data type encountered, skipping value"),
             }
         }
         size as u32
@@ -98,7 +100,6 @@ impl Container {
                     buffer.extend_from_slice(&bytes);
                 }
                 Data::Boolean(b) => buffer.push(if *b { 1 } else { 0 }),
-                _ => log::warn!("Unknown data type encountered, skipping value"),
             }
         }
 
@@ -106,6 +107,12 @@ impl Container {
     }
 }
 
+impl Default for Container {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Container {
     pub fn new() -> Self {
         Container {
@@ -120,4 +127,27 @@ impl Container {
     pub fn get_item(&self, key: &str) -> Option<&Data> {
         self.items.get(key)
     }
+
+    pub fn remove_item(&mut self, key: &str) -> Option<Data> {
+        self.items.remove(key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Data)> {
+        self.items.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Copies every item of `other` into `self`, replacing items with the same key.
+    pub fn merge(&mut self, other: &Container) {
+        for (key, value) in &other.items {
+            self.items.insert(key.clone(), value.clone());
+        }
+    }
 }
\ No newline at end of file
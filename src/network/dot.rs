@@ -0,0 +1,53 @@
+//! Graphviz DOT rendering of a graph, e.g. `dot -Tsvg network.dot > network.svg`.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use uuid::Uuid;
+
+use crate::network::graph::{Graph, NodeStatus};
+
+fn colour(status: NodeStatus) -> &'static str {
+    match status {
+        NodeStatus::Completed => "palegreen",
+        NodeStatus::NoOutput => "khaki",
+        NodeStatus::Skipped => "lightgrey",
+        NodeStatus::TimedOut => "orange",
+        NodeStatus::Cancelled => "lightsteelblue",
+        NodeStatus::Failed => "salmon",
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl Graph {
+    /// Nodes are labelled with type and id, edges with their ports. With `statuses`, nodes are
+    /// filled in the colour of their last execution status.
+    pub fn to_dot(&self, statuses: Option<&HashMap<Uuid, NodeStatus>>) -> String {
+        let mut nodes: Vec<_> = self.nodes().collect();
+        nodes.sort_by_key(|node| node.id());
+
+        let mut dot = String::new();
+        let _ = writeln!(dot, "digraph \"{}\" {{", self.id());
+        let _ = writeln!(dot, "    node [shape=box];");
+        for node in nodes {
+            let label = format!("\"{}\\n{}\"", escape(node.node_type()), node.id());
+            match statuses.and_then(|statuses| statuses.get(&node.id())) {
+                Some(status) => {
+                    let _ = writeln!(dot, "    \"{}\" [label={}, style=filled, fillcolor={}];", node.id(), label, colour(*status));
+                }
+                None => {
+                    let _ = writeln!(dot, "    \"{}\" [label={}];", node.id(), label);
+                }
+            }
+        }
+        for edge in self.edges() {
+            let label = format!("\"{} -> {}\"", escape(&edge.from_port), escape(&edge.to_port));
+            let _ = writeln!(dot, "    \"{}\" -> \"{}\" [label={}];", edge.from, edge.to, label);
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
@@ -1,22 +1,217 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
 use uuid::Uuid;
+
+use crate::network::data_container::Container;
 use crate::network::node::Node;
+use crate::network::run::RunOptions;
+use crate::network::state::StateStore;
+
+/// Connects the `from_port` output of one node to the `to_port` input of another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Edge {
+    pub from: Uuid,
+    pub from_port: String,
+    pub to: Uuid,
+    pub to_port: String,
+}
+
+/// Outcome of a node's latest execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeStatus {
+    Completed,
+    /// Ran but produced an empty container
+    NoOutput,
+    /// Downstream of the executed node but never ready to run
+    Skipped,
+    /// Exceeded its own or the run's timeout
+    TimedOut,
+    /// Still pending or running when the run was cancelled
+    Cancelled,
+    /// Returned an error on its last attempt
+    Failed,
+}
+
+impl NodeStatus {
+    pub fn of(output: &Container) -> Self {
+        if output.is_empty() { NodeStatus::NoOutput } else { NodeStatus::Completed }
+    }
+}
+
+impl fmt::Display for NodeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeStatus::Completed => write!(f, "completed"),
+            NodeStatus::NoOutput => write!(f, "no_output"),
+            NodeStatus::Skipped => write!(f, "skipped"),
+            NodeStatus::TimedOut => write!(f, "timed_out"),
+            NodeStatus::Cancelled => write!(f, "cancelled"),
+            NodeStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
 
-struct Graph {
-    nodes: HashMap<Uuid, Node>,
+/// What a run does downstream of a node that failed for good.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum FailurePolicy {
+    /// Nodes depending on the failed node are skipped, the rest of the run goes on
+    #[default]
+    SkipDependents,
+    /// The error message is sent along the edges leaving this port, other dependents are skipped
+    ErrorPort(String),
+    /// The run stops
+    Abort,
+}
+
+impl fmt::Display for FailurePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailurePolicy::SkipDependents => write!(f, "skip_dependents"),
+            FailurePolicy::ErrorPort(port) => write!(f, "error_port:{}", port),
+            FailurePolicy::Abort => write!(f, "abort"),
+        }
+    }
+}
+
+impl FromStr for FailurePolicy {
+    type Err = String;
+
+    /// Parses the `Display` form, `error_port` alone routes to the `error` port.
+    fn from_str(value: &str) -> Result<Self, String> {
+        match value.split_once(':') {
+            None if value == "skip_dependents" => Ok(FailurePolicy::SkipDependents),
+            None if value == "abort" => Ok(FailurePolicy::Abort),
+            None if value == "error_port" => Ok(FailurePolicy::ErrorPort("error".to_string())),
+            Some(("error_port", port)) if !port.is_empty() => Ok(FailurePolicy::ErrorPort(port.to_string())),
+            _ => Err(format!("unknown failure policy '{}'", value)),
+        }
+    }
+}
+
+/// Cloning is cheap, nodes are shared between the clones.
+#[derive(Clone)]
+pub struct Graph {
+    id: Uuid,
+    nodes: HashMap<Uuid, Arc<Node>>,
+    edges: Vec<Edge>,
+    failure_policy: FailurePolicy,
+    // Shared by clones, so a network changed through `Arc::make_mut` keeps its nodes' state
+    state: StateStore,
+}
+
+impl Default for Graph {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Graph {
-    fn new() -> Self {
+    /// Empty graph with a random id.
+    pub fn new() -> Self {
+        Graph::with_id(Uuid::new_v4())
+    }
+
+    pub fn with_id(id: Uuid) -> Self {
         Graph {
+            id,
             nodes: HashMap::new(),
+            edges: Vec::new(),
+            failure_policy: FailurePolicy::default(),
+            state: StateStore::new(),
+        }
+    }
+
+    pub fn with_failure_policy(mut self, failure_policy: FailurePolicy) -> Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn failure_policy(&self) -> &FailurePolicy {
+        &self.failure_policy
+    }
+
+    /// State the graph's nodes keep between executions.
+    pub fn state(&self) -> &StateStore {
+        &self.state
+    }
+
+    pub fn add_node(&mut self, node: Node) {
+        self.nodes.insert(node.id, Arc::new(node));
+    }
+
+    pub fn get_node(&self, id: &Uuid) -> Option<&Node> {
+        self.nodes.get(id).map(Arc::as_ref)
+    }
+
+    /// Removes the node together with every edge touching it and its state.
+    pub fn remove_node(&mut self, id: &Uuid) -> Option<Arc<Node>> {
+        self.edges.retain(|edge| edge.from != *id && edge.to != *id);
+        self.state.remove(id);
+        self.nodes.remove(id)
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.values().map(Arc::as_ref)
+    }
+
+    pub(crate) fn shared_node(&self, id: &Uuid) -> Option<Arc<Node>> {
+        self.nodes.get(id).cloned()
+    }
+
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    pub fn connect(&mut self, edge: Edge) -> Result<(), String> {
+        if !self.nodes.contains_key(&edge.from) || !self.nodes.contains_key(&edge.to) {
+            return Err("unknown node".to_string());
+        }
+        if !self.edges.contains(&edge) {
+            self.edges.push(edge);
         }
+        Ok(())
     }
 
-    fn add_node<F>(&mut self, node: Node)
-        where
-            F: Fn() + 'static + Send + Sync,
-    {
-        self.nodes.insert(node.id, node);
+    pub fn disconnect(&mut self, edge: &Edge) -> bool {
+        let count = self.edges.len();
+        self.edges.retain(|existing| existing != edge);
+        self.edges.len() != count
     }
-}
\ No newline at end of file
+
+    /// Executes only the given node, a failure is returned as its message under `error`.
+    pub fn execute(&self, id: &Uuid, arguments: Container) -> Option<Container> {
+        let report = self.run(id, arguments, false, &RunOptions::default())?;
+        match report.errors.get(id) {
+            Some(error) => Some(error.to_container()),
+            None => Some(report.outputs.into_values().next().unwrap_or_default()),
+        }
+    }
+
+    /// Executes the given node and everything downstream of it, returning the output of every executed node.
+    ///
+    /// See `Graph::run` for the order nodes run in.
+    pub fn propagate(&self, id: &Uuid, arguments: Container) -> Option<HashMap<Uuid, Container>> {
+        self.run(id, arguments, true, &RunOptions::default()).map(|report| report.outputs)
+    }
+
+    pub(crate) fn reachable_from(&self, id: &Uuid) -> HashSet<Uuid> {
+        let mut reachable = HashSet::from([*id]);
+        let mut pending = vec![*id];
+        while let Some(current) = pending.pop() {
+            for edge in self.edges.iter().filter(|edge| edge.from == current) {
+                if reachable.insert(edge.to) {
+                    pending.push(edge.to);
+                }
+            }
+        }
+        reachable
+    }
+}
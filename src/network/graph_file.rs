@@ -0,0 +1,207 @@
+//! JSON file format of a graph.
+//!
+//! ```json
+//! {
+//!   "id": "5f0c…",
+//!   "failure_policy": "error_port:error",
+//!   "nodes": [{
+//!     "id": "9b1d…", "type": "math.add", "config": { "b": { "Integer": 1 } }, "timeout_ms": 500,
+//!     "retry": { "max_attempts": 3, "backoff": "exponential", "delay_ms": 100, "max_delay_ms": 1000, "on": ["transient"] },
+//!     "state": { "count": { "Integer": 7 } }
+//!   }],
+//!   "edges": [{ "from": "9b1d…", "from_port": "result", "to": "…", "to_port": "value" }]
+//! }
+//! ```
+//!
+//! Nodes, edges and config items are written in a stable order so saved files diff cleanly.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::network::data_container::{Container, Data};
+use crate::network::graph::{Edge, FailurePolicy, Graph};
+use crate::network::node::{Backoff, ExecutionPolicy, NodeErrorKind, RetryPolicy, CLOSURE_NODE_TYPE, DEFAULT_RETRY_MAX_DELAY_MS};
+use crate::network::registry::NodeRegistry;
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct GraphFile {
+    pub(crate) id: Uuid,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    failure_policy: Option<String>,
+    #[serde(default)]
+    nodes: Vec<NodeFile>,
+    #[serde(default)]
+    edges: Vec<Edge>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct NodeFile {
+    id: Uuid,
+    #[serde(rename = "type")]
+    node_type: String,
+    #[serde(default)]
+    config: BTreeMap<String, Data>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timeout_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry: Option<RetryFile>,
+    /// What the node kept in its `NodeState`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    state: BTreeMap<String, Data>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RetryFile {
+    max_attempts: u32,
+    #[serde(default = "RetryFile::fixed")]
+    backoff: String,
+    #[serde(default = "RetryFile::default_delay_ms")]
+    delay_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_delay_ms: Option<u64>,
+    #[serde(default = "RetryFile::default_on")]
+    on: Vec<String>,
+}
+
+// Settings left out take the defaults of `RetryPolicy`, as they do on `AddNode`
+impl RetryFile {
+    fn fixed() -> String {
+        "fixed".to_string()
+    }
+
+    fn default_delay_ms() -> u64 {
+        match RetryPolicy::default().backoff {
+            Backoff::Fixed(delay) | Backoff::Exponential { initial: delay, .. } => delay.as_millis() as u64,
+        }
+    }
+
+    fn default_on() -> Vec<String> {
+        RetryPolicy::default().retry_on.iter().map(|kind| kind.name().to_string()).collect()
+    }
+
+    fn from_policy(policy: &RetryPolicy) -> Option<Self> {
+        if *policy == RetryPolicy::default() {
+            return None;
+        }
+        let (backoff, delay, max_delay) = match policy.backoff {
+            Backoff::Fixed(delay) => (RetryFile::fixed(), delay, None),
+            Backoff::Exponential { initial, max } => ("exponential".to_string(), initial, Some(max.as_millis() as u64)),
+        };
+        Some(RetryFile {
+            max_attempts: policy.max_attempts,
+            backoff,
+            delay_ms: delay.as_millis() as u64,
+            max_delay_ms: max_delay,
+            on: policy.retry_on.iter().map(|kind| kind.name().to_string()).collect(),
+        })
+    }
+
+    fn to_policy(&self) -> Result<RetryPolicy, String> {
+        let delay = Duration::from_millis(self.delay_ms);
+        let backoff = match self.backoff.as_str() {
+            "fixed" => Backoff::Fixed(delay),
+            "exponential" => Backoff::Exponential {
+                initial: delay,
+                max: Duration::from_millis(self.max_delay_ms.unwrap_or(DEFAULT_RETRY_MAX_DELAY_MS)),
+            },
+            other => return Err(format!("unknown backoff '{}'", other)),
+        };
+        Ok(RetryPolicy {
+            max_attempts: self.max_attempts.max(1),
+            backoff,
+            retry_on: self.on.iter().map(|kind| kind.parse::<NodeErrorKind>()).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+fn invalid_data(path: &Path, message: impl std::fmt::Display) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("{}: {}", path.display(), message))
+}
+
+impl Graph {
+    /// Reads a graph file, building its nodes through `registry` and restoring their state.
+    pub fn load(path: &Path, registry: &NodeRegistry) -> std::io::Result<Graph> {
+        let file: GraphFile = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| invalid_data(path, e))?;
+        Graph::from_file(file, registry).map_err(|e| invalid_data(path, e))
+    }
+
+    /// Writes the graph and its nodes' state as pretty printed JSON, fails for nodes built from closures.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let file = self.to_file()?;
+        let mut json = serde_json::to_string_pretty(&file).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        json.push('\n');
+        fs::write(path, json)
+    }
+
+    pub(crate) fn from_file(file: GraphFile, registry: &NodeRegistry) -> Result<Graph, String> {
+        let failure_policy = match &file.failure_policy {
+            Some(policy) => policy.parse()?,
+            None => FailurePolicy::default(),
+        };
+        let mut graph = Graph::with_id(file.id).with_failure_policy(failure_policy);
+        for node in file.nodes {
+            if graph.get_node(&node.id).is_some() {
+                return Err(format!("duplicate node {}", node.id));
+            }
+            let mut config = Container::new();
+            for (key, value) in node.config {
+                config.add_item(&key, value);
+            }
+            let retry = match &node.retry {
+                Some(retry) => retry.to_policy().map_err(|e| format!("node {}: {}", node.id, e))?,
+                None => RetryPolicy::default(),
+            };
+            let policy = ExecutionPolicy {
+                timeout: node.timeout_ms.map(Duration::from_millis),
+                retry,
+            };
+            if !node.state.is_empty() {
+                let mut state = Container::new();
+                for (key, value) in node.state {
+                    state.add_item(&key, value);
+                }
+                graph.state().set(node.id, state);
+            }
+            let node = registry.create_node(&node.node_type, node.id, config)
+                .map_err(|e| format!("node {}: {}", node.id, e))?;
+            graph.add_node(node.with_policy(policy));
+        }
+        for edge in file.edges {
+            let description = format!("edge {}.{} -> {}.{}", edge.from, edge.from_port, edge.to, edge.to_port);
+            graph.connect(edge).map_err(|e| format!("{}: {}", description, e))?;
+        }
+        Ok(graph)
+    }
+
+    pub(crate) fn to_file(&self) -> std::io::Result<GraphFile> {
+        let mut nodes = Vec::new();
+        for node in self.nodes() {
+            if node.node_type() == CLOSURE_NODE_TYPE {
+                return Err(Error::new(ErrorKind::InvalidInput, format!("node {} has no registered type", node.id())));
+            }
+            nodes.push(NodeFile {
+                id: node.id(),
+                node_type: node.node_type().to_string(),
+                config: node.config().iter().map(|(key, value)| (key.clone(), value.clone())).collect(),
+                timeout_ms: node.policy().timeout.map(|timeout| timeout.as_millis() as u64),
+                retry: RetryFile::from_policy(&node.policy().retry),
+                state: self.state().get(&node.id()).iter().flat_map(Container::iter).map(|(key, value)| (key.clone(), value.clone())).collect(),
+            });
+        }
+        nodes.sort_by_key(|node| node.id);
+
+        Ok(GraphFile {
+            id: self.id(),
+            failure_policy: Some(self.failure_policy().to_string()).filter(|_| *self.failure_policy() != FailurePolicy::default()),
+            nodes,
+            edges: self.edges().to_vec(),
+        })
+    }
+}
@@ -1,3 +1,11 @@
 pub mod node;
 pub mod data_container;
-pub mod graph;
\ No newline at end of file
+pub mod graph;
+pub mod registry;
+pub mod run;
+pub mod state;
+pub mod nodes;
+pub mod dot;
+pub mod plugins;
+#[cfg(feature = "serde")]
+pub(crate) mod graph_file;
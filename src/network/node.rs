@@ -1,43 +1,367 @@
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use uuid::Uuid;
 
-use crate::network::data_container::Container;
+use crate::network::data_container::{Container, Data};
+use crate::network::state::NodeState;
+
+/// Behaviour of a node, turns the input container into the output container or fails.
+pub type NodeFunction = Box<dyn Fn(Container, &NodeContext) -> NodeResult + Send + Sync>;
+
+pub type NodeResult = Result<Container, NodeError>;
+
+/// Type name of nodes built directly from a closure instead of through a `NodeRegistry`.
+pub const CLOSURE_NODE_TYPE: &str = "closure";
+
+/// Broad cause of a `NodeError`, retry policies select the kinds worth another attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeErrorKind {
+    /// Temporary failure such as an unavailable service
+    Transient,
+    /// The node or something it waited on took too long
+    Timeout,
+    Io,
+    /// The input or config can not be processed, retrying will not help
+    InvalidInput,
+    Internal,
+}
+
+impl NodeErrorKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeErrorKind::Transient => "transient",
+            NodeErrorKind::Timeout => "timeout",
+            NodeErrorKind::Io => "io",
+            NodeErrorKind::InvalidInput => "invalid_input",
+            NodeErrorKind::Internal => "internal",
+        }
+    }
+}
+
+impl fmt::Display for NodeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for NodeErrorKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, String> {
+        match value {
+            "transient" => Ok(NodeErrorKind::Transient),
+            "timeout" => Ok(NodeErrorKind::Timeout),
+            "io" => Ok(NodeErrorKind::Io),
+            "invalid_input" => Ok(NodeErrorKind::InvalidInput),
+            "internal" => Ok(NodeErrorKind::Internal),
+            _ => Err(format!("unknown error kind '{}'", value)),
+        }
+    }
+}
+
+/// Failure of a single node execution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeError {
+    pub kind: NodeErrorKind,
+    pub message: String,
+}
+
+impl NodeError {
+    pub fn new(kind: NodeErrorKind, message: impl Into<String>) -> Self {
+        NodeError { kind, message: message.into() }
+    }
+
+    pub fn transient(message: impl Into<String>) -> Self {
+        NodeError::new(NodeErrorKind::Transient, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        NodeError::new(NodeErrorKind::InvalidInput, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        NodeError::new(NodeErrorKind::Internal, message)
+    }
+
+    /// Container holding the message under `error`, the way failures are reported to clients.
+    pub fn to_container(&self) -> Container {
+        let mut container = Container::new();
+        container.add_item("error", Data::Text(self.message.clone()));
+        container
+    }
+}
+
+impl fmt::Display for NodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)
+    }
+}
+
+impl std::error::Error for NodeError {}
+
+/// Shared flag telling a node or run to stop early.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Handed to every node execution, long running nodes should check `is_cancelled` and return early.
+#[derive(Debug, Clone)]
+pub struct NodeContext {
+    run: Uuid,
+    node: Uuid,
+    token: CancellationToken,
+    deadline: Option<Instant>,
+    state: NodeState,
+}
+
+impl NodeContext {
+    /// Context with a state of its own, see `with_state`.
+    pub fn new(run: Uuid, node: Uuid, token: CancellationToken, deadline: Option<Instant>) -> Self {
+        NodeContext { run, node, token, deadline, state: NodeState::detached(node) }
+    }
+
+    pub fn with_state(mut self, state: NodeState) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// Context of an execution outside of any run, which is never cancelled.
+    pub fn detached(node: Uuid) -> Self {
+        NodeContext::new(Uuid::nil(), node, CancellationToken::new(), None)
+    }
+
+    pub fn run_id(&self) -> Uuid {
+        self.run
+    }
+
+    pub fn node_id(&self) -> Uuid {
+        self.node
+    }
+
+    /// Set once the run was cancelled or the node ran past its deadline.
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled() || self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// State the node keeps between executions in its network.
+    pub fn state(&self) -> &NodeState {
+        &self.state
+    }
+}
+
+/// Wait between two attempts of a failing node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backoff {
+    Fixed(Duration),
+    /// Doubles after every retry, starting at `initial` and capped at `max`
+    Exponential { initial: Duration, max: Duration },
+}
+
+impl Backoff {
+    /// Delay before the given retry, the first retry is 1.
+    pub fn delay(&self, retry: u32) -> Duration {
+        match *self {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential { initial, max } => {
+                let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+                initial.saturating_mul(factor).min(max)
+            }
+        }
+    }
+}
+
+/// Which failures of a node are attempted again, and how often.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Attempts including the first one, 1 disables retries
+    pub max_attempts: u32,
+    pub backoff: Backoff,
+    pub retry_on: Vec<NodeErrorKind>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            backoff: Backoff::Fixed(Duration::from_millis(DEFAULT_RETRY_DELAY_MS)),
+            retry_on: vec![NodeErrorKind::Transient, NodeErrorKind::Timeout],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether `error`, raised by the given attempt, is worth another one.
+    pub fn should_retry(&self, attempt: u32, error: &NodeError) -> bool {
+        attempt < self.max_attempts && self.retry_on.contains(&error.kind)
+    }
+}
+
+const DEFAULT_RETRY_DELAY_MS: u64 = 100;
+pub(crate) const DEFAULT_RETRY_MAX_DELAY_MS: u64 = 10_000;
+
+/// How the executor runs a node, independent of the node's type.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionPolicy {
+    /// Every attempt counts as timed out once exceeded
+    pub timeout: Option<Duration>,
+    pub retry: RetryPolicy,
+}
+
+impl ExecutionPolicy {
+    /// Takes the `node.*` settings out of an `AddNode` container, leaving the node's config.
+    ///
+    /// Besides `node.timeout_ms` these are the retry settings:
+    /// - `node.retry.max_attempts` (default 1)
+    /// - `node.retry.backoff`: `fixed` (default) or `exponential`
+    /// - `node.retry.delay_ms` (default 100): the fixed or initial delay
+    /// - `node.retry.max_delay_ms` (default 10000): cap of the exponential delay
+    /// - `node.retry.on` (default `transient,timeout`): comma separated error kinds to retry
+    pub fn take_from(config: &mut Container) -> Result<Self, String> {
+        let mut policy = ExecutionPolicy::default();
+        if let Some(timeout) = take_integer(config, "node.timeout_ms", 1)? {
+            policy.timeout = Some(Duration::from_millis(timeout));
+        }
+        if let Some(max_attempts) = take_integer(config, "node.retry.max_attempts", 1)? {
+            policy.retry.max_attempts = max_attempts.min(u32::MAX as u64) as u32;
+        }
+        let delay = take_integer(config, "node.retry.delay_ms", 0)?.unwrap_or(DEFAULT_RETRY_DELAY_MS);
+        let max_delay = take_integer(config, "node.retry.max_delay_ms", 0)?.unwrap_or(DEFAULT_RETRY_MAX_DELAY_MS);
+        policy.retry.backoff = match config.remove_item("node.retry.backoff") {
+            None => Backoff::Fixed(Duration::from_millis(delay)),
+            Some(Data::Text(backoff)) if backoff == "fixed" => Backoff::Fixed(Duration::from_millis(delay)),
+            Some(Data::Text(backoff)) if backoff == "exponential" => Backoff::Exponential {
+                initial: Duration::from_millis(delay),
+                max: Duration::from_millis(max_delay),
+            },
+            Some(other) => return Err(format!("node.retry.backoff must be fixed or exponential, got {:?}", other)),
+        };
+        match config.remove_item("node.retry.on") {
+            Some(Data::Text(kinds)) => {
+                policy.retry.retry_on = kinds.split(',')
+                    .map(str::trim)
+                    .filter(|kind| !kind.is_empty())
+                    .map(NodeErrorKind::from_str)
+                    .collect::<Result<_, _>>()?;
+            }
+            Some(other) => return Err(format!("node.retry.on must be text, got {:?}", other)),
+            None => {}
+        }
+        Ok(policy)
+    }
+}
+
+fn take_integer(config: &mut Container, key: &str, min: i32) -> Result<Option<u64>, String> {
+    match config.remove_item(key) {
+        Some(Data::Integer(value)) if value >= min => Ok(Some(value as u64)),
+        Some(other) => Err(format!("{} must be an integer of at least {}, got {:?}", key, min, other)),
+        None => Ok(None),
+    }
+}
 
 pub struct Node {
     pub(crate) id: Uuid,
-    execute_fn: Box<dyn Fn(Container) + Send + Sync>,
-    dependents: HashMap<Uuid, Arc<Mutex<Node>>>,
+    node_type: String,
+    config: Container,
+    policy: ExecutionPolicy,
+    execute_fn: NodeFunction,
 }
 
 impl Node {
     pub fn new<F>(id: Uuid, execute_fn: F) -> Self
         where
-            F: Fn(Container) + 'static + Send + Sync,
+            F: Fn(Container) -> Container + 'static + Send + Sync,
+    {
+        Node::with_type(id, CLOSURE_NODE_TYPE, Container::new(), Box::new(move |input, _: &NodeContext| Ok(execute_fn(input))))
+    }
+
+    /// Like `new`, for closures that can fail.
+    pub fn try_new<F>(id: Uuid, execute_fn: F) -> Self
+        where
+            F: Fn(Container) -> NodeResult + 'static + Send + Sync,
     {
+        Node::with_type(id, CLOSURE_NODE_TYPE, Container::new(), Box::new(move |input, _: &NodeContext| execute_fn(input)))
+    }
+
+    /// Node created from a registered type, keeps the type name and config it was built from.
+    pub fn with_type(id: Uuid, node_type: &str, config: Container, execute_fn: NodeFunction) -> Self {
         Node {
             id,
-            execute_fn: Box::new(execute_fn),
-            dependents: HashMap::new(),
+            node_type: node_type.to_string(),
+            config,
+            policy: ExecutionPolicy::default(),
+            execute_fn,
         }
     }
 
-    pub(crate) fn add_dependent(&mut self, dependent: Arc<Mutex<Node>>) {
-        self.dependents.insert(dependent.lock().unwrap().id, dependent.clone());
+    pub fn with_policy(mut self, policy: ExecutionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
     }
 
-    pub fn execute(&self, arguments: Container) {
+    pub fn node_type(&self) -> &str {
+        &self.node_type
+    }
+
+    pub fn config(&self) -> &Container {
+        &self.config
+    }
+
+    pub fn policy(&self) -> &ExecutionPolicy {
+        &self.policy
+    }
+
+    /// Runs the node outside of any run, a failure is returned as its message under `error`.
+    pub fn execute(&self, arguments: Container) -> Container {
+        self.try_execute(arguments).unwrap_or_else(|error| error.to_container())
+    }
+
+    pub fn try_execute(&self, arguments: Container) -> NodeResult {
+        self.execute_with(arguments, &NodeContext::detached(self.id))
+    }
+
+    /// A panic inside the node is caught and returned as an `Internal` error.
+    pub fn execute_with(&self, arguments: Container, context: &NodeContext) -> NodeResult {
         log::debug!("Arguments: {:?}", arguments);
-        (self.execute_fn)(arguments);
+        panic::catch_unwind(AssertUnwindSafe(|| (self.execute_fn)(arguments, context))).unwrap_or_else(|payload| {
+            log::error!("Node {} panicked", self.id);
+            Err(NodeError::internal(format!("panicked: {}", panic_message(payload.as_ref()))))
+        })
     }
+}
 
-    pub fn propagate(&self, arguments: Container) {
-        let cloned_arguments = arguments.clone();
-        for dependent in &self.dependents {
-            let node = dependent.1.lock().unwrap();
-            log::debug!("Propagate call to {}({:?})", node.id, arguments);
-            (node.execute_fn)(cloned_arguments.clone());
-        }
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,52 @@
+use crate::network::data_container::{Container, Data};
+use crate::network::node::{NodeContext, NodeFunction};
+use crate::network::nodes::single;
+use crate::network::registry::{NodeDescriptor, NodeRegistry};
+
+/// Conversions of `value` between the container data types.
+pub fn register(registry: &mut NodeRegistry) {
+    register_conversion(registry, "convert.to_integer", "value as Integer, floats are truncated", to_integer);
+    register_conversion(registry, "convert.to_float", "value as Float", to_float);
+    register_conversion(registry, "convert.to_text", "value as Text", |value| Some(Data::Text(value.to_string())));
+    register_conversion(registry, "convert.to_boolean", "value as Boolean, numbers are true unless zero", to_boolean);
+}
+
+fn to_integer(value: &Data) -> Option<Data> {
+    match value {
+        Data::Integer(value) => Some(Data::Integer(*value)),
+        Data::Float(value) if value.is_finite() => Some(Data::Integer(*value as i32)),
+        Data::Text(value) => value.trim().parse().ok().map(Data::Integer),
+        Data::Boolean(value) => Some(Data::Integer(*value as i32)),
+        _ => None,
+    }
+}
+
+fn to_float(value: &Data) -> Option<Data> {
+    match value {
+        Data::Integer(value) => Some(Data::Float(*value as f64)),
+        Data::Float(value) => Some(Data::Float(*value)),
+        Data::Text(value) => value.trim().parse().ok().map(Data::Float),
+        Data::Boolean(value) => Some(Data::Float(if *value { 1.0 } else { 0.0 })),
+    }
+}
+
+fn to_boolean(value: &Data) -> Option<Data> {
+    match value {
+        Data::Integer(value) => Some(Data::Boolean(*value != 0)),
+        Data::Float(value) => Some(Data::Boolean(*value != 0.0)),
+        Data::Text(value) => value.trim().parse().ok().map(Data::Boolean),
+        Data::Boolean(value) => Some(Data::Boolean(*value)),
+    }
+}
+
+fn register_conversion(registry: &mut NodeRegistry, type_name: &str, description: &str, convert: fn(&Data) -> Option<Data>) {
+    registry.register_fn(NodeDescriptor::new(type_name, description, &["value"], &["result"]), move |_| {
+        let execute_fn: NodeFunction = Box::new(move |input: Container, _: &NodeContext| {
+            Ok(match input.get_item("value").and_then(convert) {
+                Some(result) => single("result", result),
+                None => Container::new(),
+            })
+        });
+        Ok(execute_fn)
+    });
+}
@@ -0,0 +1,56 @@
+use crate::network::data_container::{Container, Data};
+use crate::network::node::{NodeContext, NodeFunction};
+use crate::network::nodes::text_config;
+use crate::network::registry::{NodeDescriptor, NodeRegistry};
+
+/// Nodes routing data without changing it.
+pub fn register(registry: &mut NodeRegistry) {
+    registry.register_fn(NodeDescriptor::new("flow.constant", "Outputs its config, inputs override config items", &[], &[]), |config| {
+        let config = config.clone();
+        let execute_fn: NodeFunction = Box::new(move |input: Container, _: &NodeContext| {
+            let mut output = config.clone();
+            output.merge(&input);
+            Ok(output)
+        });
+        Ok(execute_fn)
+    });
+
+    registry.register_fn(NodeDescriptor::new("flow.pass", "Outputs its input unchanged", &[], &[]), |_| {
+        let execute_fn: NodeFunction = Box::new(|input: Container, _: &NodeContext| Ok(input));
+        Ok(execute_fn)
+    });
+
+    // Edges into the same node already merge into one input container, so this only names the intent
+    registry.register_fn(NodeDescriptor::new("flow.merge", "Outputs every item received from its incoming edges", &[], &[]), |_| {
+        let execute_fn: NodeFunction = Box::new(|input: Container, _: &NodeContext| Ok(input));
+        Ok(execute_fn)
+    });
+
+    registry.register_fn(NodeDescriptor::new("flow.gate", "Outputs value while the open input is true", &["value", "open"], &["value"]), |_| {
+        let execute_fn: NodeFunction = Box::new(|input: Container, _: &NodeContext| {
+            let mut output = Container::new();
+            if let (Some(Data::Boolean(true)), Some(value)) = (input.get_item("open"), input.get_item("value")) {
+                output.add_item("value", value.clone());
+            }
+            Ok(output)
+        });
+        Ok(execute_fn)
+    });
+
+    registry.register_fn(NodeDescriptor::new("flow.filter", "Outputs its input if the `key` config is present and equals the `equals` config", &[], &[]), |config| {
+        let key = text_config(config, "key", "")?;
+        if key.is_empty() {
+            return Err("config 'key' is required".to_string());
+        }
+        let expected = config.get_item("equals").cloned();
+        let execute_fn: NodeFunction = Box::new(move |input: Container, _: &NodeContext| {
+            let matches = match (input.get_item(&key), &expected) {
+                (Some(value), Some(expected)) => value == expected,
+                (Some(_), None) => true,
+                (None, _) => false,
+            };
+            Ok(if matches { input } else { Container::new() })
+        });
+        Ok(execute_fn)
+    });
+}
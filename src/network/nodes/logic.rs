@@ -0,0 +1,65 @@
+use std::cmp::Ordering;
+
+use crate::network::data_container::{Container, Data};
+use crate::network::node::{NodeContext, NodeFunction};
+use crate::network::nodes::math::as_float;
+use crate::network::nodes::single;
+use crate::network::registry::{NodeDescriptor, NodeRegistry};
+
+/// Comparisons of `a` and `b` and boolean logic, all writing a `Boolean` result.
+pub fn register(registry: &mut NodeRegistry) {
+    register_comparison(registry, "logic.equal", "a == b", |ordering| ordering == Ordering::Equal);
+    register_comparison(registry, "logic.not_equal", "a != b", |ordering| ordering != Ordering::Equal);
+    register_comparison(registry, "logic.less", "a < b", |ordering| ordering == Ordering::Less);
+    register_comparison(registry, "logic.less_equal", "a <= b", |ordering| ordering != Ordering::Greater);
+    register_comparison(registry, "logic.greater", "a > b", |ordering| ordering == Ordering::Greater);
+    register_comparison(registry, "logic.greater_equal", "a >= b", |ordering| ordering != Ordering::Less);
+
+    register_boolean(registry, "logic.and", "a && b", |a, b| a && b);
+    register_boolean(registry, "logic.or", "a || b", |a, b| a || b);
+    register_boolean(registry, "logic.xor", "a ^ b", |a, b| a ^ b);
+    registry.register_fn(NodeDescriptor::new("logic.not", "!value", &["value"], &["result"]), |_| {
+        let execute_fn: NodeFunction = Box::new(|input: Container, _: &NodeContext| Ok(match input.get_item("value") {
+            Some(Data::Boolean(value)) => single("result", Data::Boolean(!value)),
+            _ => Container::new(),
+        }));
+        Ok(execute_fn)
+    });
+}
+
+// Numbers compare by value across Integer and Float, other types only with their own kind
+fn compare(a: &Data, b: &Data) -> Option<Ordering> {
+    match (a, b) {
+        (Data::Text(a), Data::Text(b)) => Some(a.cmp(b)),
+        (Data::Boolean(a), Data::Boolean(b)) => Some(a.cmp(b)),
+        _ => as_float(a)?.partial_cmp(&as_float(b)?),
+    }
+}
+
+fn register_comparison(registry: &mut NodeRegistry, type_name: &str, description: &str, predicate: fn(Ordering) -> bool) {
+    registry.register_fn(NodeDescriptor::new(type_name, description, &["a", "b"], &["result"]), move |_| {
+        let execute_fn: NodeFunction = Box::new(move |input: Container, _: &NodeContext| {
+            let ordering = match (input.get_item("a"), input.get_item("b")) {
+                (Some(a), Some(b)) => compare(a, b),
+                _ => None,
+            };
+            Ok(match ordering {
+                Some(ordering) => single("result", Data::Boolean(predicate(ordering))),
+                None => Container::new(),
+            })
+        });
+        Ok(execute_fn)
+    });
+}
+
+fn register_boolean(registry: &mut NodeRegistry, type_name: &str, description: &str, operator: fn(bool, bool) -> bool) {
+    registry.register_fn(NodeDescriptor::new(type_name, description, &["a", "b"], &["result"]), move |_| {
+        let execute_fn: NodeFunction = Box::new(move |input: Container, _: &NodeContext| {
+            Ok(match (input.get_item("a"), input.get_item("b")) {
+                (Some(Data::Boolean(a)), Some(Data::Boolean(b))) => single("result", Data::Boolean(operator(*a, *b))),
+                _ => Container::new(),
+            })
+        });
+        Ok(execute_fn)
+    });
+}
@@ -0,0 +1,52 @@
+use crate::network::data_container::{Container, Data};
+use crate::network::node::{NodeContext, NodeFunction};
+use crate::network::nodes::single;
+use crate::network::registry::{NodeDescriptor, NodeRegistry};
+
+/// Arithmetic on `Integer` and `Float`, mixing both promotes to `Float`.
+pub fn register(registry: &mut NodeRegistry) {
+    register_operator(registry, "math.add", "a + b", i32::checked_add, |a, b| a + b);
+    register_operator(registry, "math.subtract", "a - b", i32::checked_sub, |a, b| a - b);
+    register_operator(registry, "math.multiply", "a * b", i32::checked_mul, |a, b| a * b);
+    register_operator(registry, "math.divide", "a / b, integer division for two integers", i32::checked_div, |a, b| a / b);
+    register_operator(registry, "math.modulo", "a % b", i32::checked_rem, |a, b| a % b);
+    register_operator(registry, "math.min", "Smaller of a and b", |a, b| Some(a.min(b)), f64::min);
+    register_operator(registry, "math.max", "Larger of a and b", |a, b| Some(a.max(b)), f64::max);
+}
+
+pub(crate) fn as_float(data: &Data) -> Option<f64> {
+    match data {
+        Data::Integer(value) => Some(*value as f64),
+        Data::Float(value) => Some(*value),
+        _ => None,
+    }
+}
+
+// Integer overflow and division by zero yield an empty output
+fn apply(a: &Data, b: &Data, integer_op: fn(i32, i32) -> Option<i32>, float_op: fn(f64, f64) -> f64) -> Option<Data> {
+    match (a, b) {
+        (Data::Integer(a), Data::Integer(b)) => integer_op(*a, *b).map(Data::Integer),
+        _ => Some(Data::Float(float_op(as_float(a)?, as_float(b)?))),
+    }
+}
+
+fn register_operator(registry: &mut NodeRegistry, type_name: &str, description: &str, integer_op: fn(i32, i32) -> Option<i32>, float_op: fn(f64, f64) -> f64) {
+    let node_type = type_name.to_string();
+    registry.register_fn(NodeDescriptor::new(type_name, description, &["a", "b"], &["result"]), move |_| {
+        let node_type = node_type.clone();
+        let execute_fn: NodeFunction = Box::new(move |input: Container, _: &NodeContext| {
+            let result = match (input.get_item("a"), input.get_item("b")) {
+                (Some(a), Some(b)) => apply(a, b, integer_op, float_op),
+                _ => None,
+            };
+            Ok(match result {
+                Some(result) => single("result", result),
+                None => {
+                    log::warn!("{} cannot compute a result from {:?}", node_type, input);
+                    Container::new()
+                }
+            })
+        });
+        Ok(execute_fn)
+    });
+}
@@ -0,0 +1,46 @@
+//! Node types registered by default, see `NodeRegistry::with_builtins`.
+//!
+//! Binary operators read the `a` and `b` inputs, unary ones `value`, and most write `result`.
+//! An input of the wrong type yields an empty output instead of a value.
+
+use crate::network::data_container::{Container, Data};
+use crate::network::registry::NodeRegistry;
+
+pub mod convert;
+pub mod flow;
+pub mod logic;
+pub mod math;
+#[cfg(feature = "process")]
+pub mod process;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod sink;
+pub mod state;
+pub mod text;
+
+pub fn register_builtins(registry: &mut NodeRegistry) {
+    math::register(registry);
+    logic::register(registry);
+    text::register(registry);
+    convert::register(registry);
+    flow::register(registry);
+    sink::register(registry);
+    state::register(registry);
+    #[cfg(feature = "scripting")]
+    script::register(registry);
+}
+
+// Container holding only `value` under `key`
+pub(crate) fn single(key: &str, value: Data) -> Container {
+    let mut container = Container::new();
+    container.add_item(key, value);
+    container
+}
+
+pub(crate) fn text_config(config: &Container, key: &str, default: &str) -> Result<String, String> {
+    match config.get_item(key) {
+        Some(Data::Text(value)) => Ok(value.clone()),
+        Some(other) => Err(format!("config '{}' must be text, got {:?}", key, other)),
+        None => Ok(default.to_string()),
+    }
+}
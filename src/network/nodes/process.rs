@@ -0,0 +1,257 @@
+use std::io::{Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::network::data_container::{Container, Data};
+use crate::network::node::{NodeContext, NodeError, NodeErrorKind, NodeFunction};
+use crate::network::nodes::text::format;
+use crate::network::nodes::text_config;
+use crate::network::registry::{NodeDescriptor, NodeRegistry};
+
+const DEFAULT_TIMEOUT_MS: i32 = 10_000;
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+// How long stderr may still be read once a timed out process was killed
+const STDERR_GRACE: Duration = Duration::from_millis(100);
+// Bytes kept of stdout and of stderr, the rest is read and dropped
+const MAX_OUTPUT_SIZE: usize = 1024 * 1024;
+
+/// `process.exec` runs the executable in the `command` config, which has to be one of `allowed`.
+///
+/// The node type lets clients run programs on the executor's host, so it is not part of the
+/// built-ins and only registered with the commands an operator allowed, see `ADA_ALLOW_PROCESS_EXEC`.
+///
+/// Config:
+/// - `arg0`, `arg1`, ...: arguments, `{key}` placeholders are filled from the input
+/// - `stdin` (default true): write the input as a JSON object to stdin
+/// - `env` (default false): export every input item as `ADA_INPUT_<KEY>`
+/// - `timeout_ms` (default 10000): kill the process once exceeded
+///
+/// A JSON object on stdout becomes the output, any other stdout is returned as `stdout`. The output
+/// always holds `exit_code` and, if anything was written to it, `stderr`. A process that could not be
+/// started fails with an `Io` error, one that ran past `timeout_ms` with a `Timeout` error and one
+/// exiting with another code than 0 with an `Internal` error, the latter two holding its stderr.
+/// Only the first MiB of stderr is kept, a process writing more than that to stdout fails.
+pub fn register(registry: &mut NodeRegistry, allowed: Vec<String>) {
+    let descriptor = NodeDescriptor::new("process.exec", "Runs the `command` config as a subprocess", &[], &["exit_code", "stderr", "stdout"]);
+    registry.register_fn(descriptor, move |config| {
+        let process = Process::from_config(config)?;
+        if !allowed.contains(&process.command) {
+            return Err(format!("command '{}' is not allowed", process.command));
+        }
+        let execute_fn: NodeFunction = Box::new(move |input: Container, context: &NodeContext| {
+            process.run(&input, context).inspect_err(|e| log::warn!("process.exec {}: {}", process.command, e))
+        });
+        Ok(execute_fn)
+    });
+}
+
+struct Process {
+    command: String,
+    args: Vec<String>,
+    stdin: bool,
+    env: bool,
+    timeout: Duration,
+}
+
+impl Process {
+    fn from_config(config: &Container) -> Result<Self, String> {
+        let command = text_config(config, "command", "")?;
+        if command.is_empty() {
+            return Err("config 'command' is required".to_string());
+        }
+        let mut args = Vec::new();
+        while let Some(arg) = config.get_item(&format!("arg{}", args.len())) {
+            args.push(arg.to_string());
+        }
+        let flag = |key: &str, default: bool| match config.get_item(key) {
+            Some(Data::Boolean(value)) => Ok(*value),
+            Some(other) => Err(format!("config '{}' must be a boolean, got {:?}", key, other)),
+            None => Ok(default),
+        };
+        let timeout = match config.get_item("timeout_ms") {
+            Some(Data::Integer(timeout)) if *timeout > 0 => *timeout,
+            Some(other) => return Err(format!("config 'timeout_ms' must be a positive integer, got {:?}", other)),
+            None => DEFAULT_TIMEOUT_MS,
+        };
+        Ok(Process {
+            command,
+            args,
+            stdin: flag("stdin", true)?,
+            env: flag("env", false)?,
+            timeout: Duration::from_millis(timeout as u64),
+        })
+    }
+
+    fn run(&self, input: &Container, context: &NodeContext) -> Result<Container, NodeError> {
+        let mut command = Command::new(&self.command);
+        command.args(self.args.iter().map(|arg| format(arg, input)))
+            .stdin(if self.stdin { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if self.env {
+            for (key, value) in input.iter() {
+                command.env(env_name(key), value.to_string());
+            }
+        }
+        let mut child = command.spawn().map_err(|e| NodeError::new(NodeErrorKind::Io, format!("failed to start: {}", e)))?;
+
+        // Pipes are drained on their own threads so a chatty process cannot block on a full pipe
+        if let Some(mut stdin) = child.stdin.take() {
+            let json = to_json(input).to_string();
+            thread::spawn(move || stdin.write_all(json.as_bytes()));
+        }
+        let stdout = Drain::start(child.stdout.take());
+        let stderr = Drain::start(child.stderr.take());
+
+        let status = match wait(&mut child, self.timeout, context) {
+            Ok(status) => status,
+            Err(mut e) => {
+                let stderr = stderr.so_far(STDERR_GRACE);
+                let stderr = String::from_utf8_lossy(&stderr);
+                if !stderr.trim().is_empty() {
+                    e.message = format!("{}, stderr: {}", e.message, stderr.trim_end());
+                }
+                return Err(e);
+            }
+        };
+        let (stdout, stdout_truncated) = stdout.finish();
+        let (stderr, _) = stderr.finish();
+        let stderr = String::from_utf8_lossy(&stderr);
+
+        if !status.success() {
+            // Processes killed by a signal have no exit code
+            let mut message = format!("exited with code {}", status.code().unwrap_or(-1));
+            if !stderr.trim().is_empty() {
+                message = format!("{}, stderr: {}", message, stderr.trim_end());
+            }
+            return Err(NodeError::internal(message));
+        }
+        if stdout_truncated {
+            return Err(NodeError::internal(format!("stdout exceeds {} bytes", MAX_OUTPUT_SIZE)));
+        }
+
+        let mut output = match serde_json::from_slice(&stdout) {
+            Ok(serde_json::Value::Object(items)) => from_json(items),
+            _ => {
+                let mut output = Container::new();
+                let stdout = String::from_utf8_lossy(&stdout);
+                if !stdout.trim().is_empty() {
+                    output.add_item("stdout", Data::Text(stdout.trim_end().to_string()));
+                }
+                output
+            }
+        };
+        output.add_item("exit_code", Data::Integer(0));
+        if !stderr.trim().is_empty() {
+            output.add_item("stderr", Data::Text(stderr.trim_end().to_string()));
+        }
+        Ok(output)
+    }
+}
+
+// Also kills the process once the run is cancelled
+fn wait(child: &mut Child, timeout: Duration, context: &NodeContext) -> Result<std::process::ExitStatus, NodeError> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(status),
+            Ok(None) if Instant::now() >= deadline || context.is_cancelled() => {
+                let _ = child.kill();
+                let _ = child.wait();
+                if context.is_cancelled() {
+                    return Err(NodeError::internal("cancelled"));
+                }
+                return Err(NodeError::new(NodeErrorKind::Timeout, format!("timed out after {}ms", timeout.as_millis())));
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(e) => return Err(NodeError::new(NodeErrorKind::Io, e.to_string())),
+        }
+    }
+}
+
+// A pipe read on its own thread, what was read is available before the pipe closes. The pipe is
+// read to the end, but only the first `MAX_OUTPUT_SIZE` bytes are kept
+struct Drain {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    truncated: Arc<AtomicBool>,
+    reader: thread::JoinHandle<()>,
+}
+
+impl Drain {
+    fn start<R: Read + Send + 'static>(pipe: Option<R>) -> Self {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let truncated = Arc::new(AtomicBool::new(false));
+        let (read, dropped) = (Arc::clone(&buffer), Arc::clone(&truncated));
+        let reader = thread::spawn(move || {
+            let Some(mut pipe) = pipe else { return };
+            let mut chunk = [0; 4096];
+            while let Ok(size @ 1..) = pipe.read(&mut chunk) {
+                let mut buffer = read.lock().unwrap();
+                let kept = size.min(MAX_OUTPUT_SIZE - buffer.len());
+                buffer.extend_from_slice(&chunk[..kept]);
+                if kept < size {
+                    dropped.store(true, Ordering::Relaxed);
+                }
+            }
+        });
+        Drain { buffer, truncated, reader }
+    }
+
+    // Everything kept until the pipe closed, and whether anything was dropped
+    fn finish(self) -> (Vec<u8>, bool) {
+        let _ = self.reader.join();
+        (std::mem::take(&mut *self.buffer.lock().unwrap()), self.truncated.load(Ordering::Relaxed))
+    }
+
+    // Children of a killed process may keep the pipe open, so only wait up to `grace` for it to close
+    fn so_far(self, grace: Duration) -> Vec<u8> {
+        let deadline = Instant::now() + grace;
+        while !self.reader.is_finished() && Instant::now() < deadline {
+            thread::sleep(POLL_INTERVAL);
+        }
+        std::mem::take(&mut *self.buffer.lock().unwrap())
+    }
+}
+
+fn env_name(key: &str) -> String {
+    let key: String = key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    format!("ADA_INPUT_{}", key)
+}
+
+// Scripts see plain JSON values rather than the tagged `Data` serialization
+fn to_json(container: &Container) -> serde_json::Value {
+    let items = container.iter().map(|(key, value)| {
+        let value = match value {
+            Data::Integer(value) => serde_json::Value::from(*value),
+            Data::Float(value) => serde_json::Value::from(*value),
+            Data::Text(value) => serde_json::Value::from(value.as_str()),
+            Data::Boolean(value) => serde_json::Value::from(*value),
+        };
+        (key.clone(), value)
+    });
+    serde_json::Value::Object(items.collect())
+}
+
+// Nested values are kept as their JSON text, nulls are dropped
+fn from_json(items: serde_json::Map<String, serde_json::Value>) -> Container {
+    let mut container = Container::new();
+    for (key, value) in items {
+        let value = match value {
+            serde_json::Value::Null => continue,
+            serde_json::Value::Bool(value) => Data::Boolean(value),
+            serde_json::Value::Number(number) => match number.as_i64().and_then(|value| i32::try_from(value).ok()) {
+                Some(value) => Data::Integer(value),
+                None => Data::Float(number.as_f64().unwrap_or(f64::NAN)),
+            },
+            serde_json::Value::String(value) => Data::Text(value),
+            other => Data::Text(other.to_string()),
+        };
+        container.add_item(&key, value);
+    }
+    container
+}
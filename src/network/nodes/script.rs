@@ -0,0 +1,111 @@
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+
+use crate::network::data_container::{Container, Data};
+use crate::network::node::{NodeContext, NodeError, NodeFunction, NodeResult};
+use crate::network::nodes::{single, text_config};
+use crate::network::registry::{NodeDescriptor, NodeRegistry};
+
+const DEFAULT_MAX_OPERATIONS: i32 = 100_000;
+// Bound what a script can allocate within its operation budget
+const MAX_STRING_SIZE: usize = 1024 * 1024;
+const MAX_ARRAY_SIZE: usize = 100_000;
+const MAX_MAP_SIZE: usize = 100_000;
+const MAX_EXPR_DEPTH: usize = 64;
+const MAX_FUNCTION_EXPR_DEPTH: usize = 32;
+
+/// `script.rhai` runs the Rhai script in the `script` config.
+///
+/// The input is bound to the map `input`. A returned map becomes the output, any other value is
+/// returned as `result`. A script failing at runtime, exceeding the `max_operations` config
+/// (default 100000) or building strings, arrays or maps beyond fixed sizes fails the node.
+pub fn register(registry: &mut NodeRegistry) {
+    let descriptor = NodeDescriptor::new("script.rhai", "Runs the Rhai `script` config with the input bound to `input`", &[], &["result"]);
+    registry.register_fn(descriptor, |config| {
+        let script = text_config(config, "script", "")?;
+        let max_operations = match config.get_item("max_operations") {
+            Some(Data::Integer(max_operations)) if *max_operations > 0 => *max_operations,
+            Some(other) => return Err(format!("config 'max_operations' must be a positive integer, got {:?}", other)),
+            None => DEFAULT_MAX_OPERATIONS,
+        };
+
+        // Syntax errors surface when the node is added rather than on every run
+        let ast = limited_engine().compile(&script).map_err(|e| format!("invalid script: {}", e))?;
+
+        let execute_fn: NodeFunction = Box::new(move |input: Container, context: &NodeContext| run(max_operations as u64, &ast, input, context));
+        Ok(execute_fn)
+    });
+}
+
+fn limited_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_string_size(MAX_STRING_SIZE);
+    engine.set_max_array_size(MAX_ARRAY_SIZE);
+    engine.set_max_map_size(MAX_MAP_SIZE);
+    engine.set_max_expr_depths(MAX_EXPR_DEPTH, MAX_FUNCTION_EXPR_DEPTH);
+    engine
+}
+
+// The engine is built per execution so the progress callback can watch this execution's context
+fn engine(max_operations: u64, context: &NodeContext) -> Engine {
+    let mut engine = limited_engine();
+    engine.set_max_operations(max_operations);
+    engine.on_print(|text| log::info!("script.rhai: {}", text));
+    engine.on_debug(|text, _, position| log::debug!("script.rhai {}: {}", position, text));
+    let context = context.clone();
+    engine.on_progress(move |_| context.is_cancelled().then(|| Dynamic::from("cancelled")));
+    engine
+}
+
+fn run(max_operations: u64, ast: &AST, input: Container, context: &NodeContext) -> NodeResult {
+    let engine = engine(max_operations, context);
+    let mut scope = Scope::new();
+    scope.push("input", to_map(&input));
+    match engine.eval_ast_with_scope::<Dynamic>(&mut scope, ast) {
+        Ok(value) if value.is_map() => {
+            let mut output = Container::new();
+            for (key, value) in value.cast::<Map>() {
+                if let Some(value) = to_data(value) {
+                    output.add_item(&key, value);
+                }
+            }
+            Ok(output)
+        }
+        Ok(value) => Ok(match to_data(value) {
+            Some(value) => single("result", value),
+            None => Container::new(),
+        }),
+        Err(e) => {
+            log::warn!("script.rhai failed: {}", e);
+            Err(NodeError::internal(e.to_string()))
+        }
+    }
+}
+
+fn to_map(container: &Container) -> Map {
+    container.iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Data::Integer(value) => Dynamic::from_int(*value as rhai::INT),
+                Data::Float(value) => Dynamic::from_float(*value),
+                Data::Text(value) => Dynamic::from(value.clone()),
+                Data::Boolean(value) => Dynamic::from_bool(*value),
+            };
+            (key.as_str().into(), value)
+        })
+        .collect()
+}
+
+// Integers outside the `i32` range become floats, unit values are dropped
+fn to_data(value: Dynamic) -> Option<Data> {
+    if value.is_unit() {
+        None
+    } else if let Ok(value) = value.as_int() {
+        Some(i32::try_from(value).map(Data::Integer).unwrap_or(Data::Float(value as f64)))
+    } else if let Ok(value) = value.as_float() {
+        Some(Data::Float(value))
+    } else if let Ok(value) = value.as_bool() {
+        Some(Data::Boolean(value))
+    } else {
+        Some(Data::Text(value.to_string()))
+    }
+}
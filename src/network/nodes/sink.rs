@@ -0,0 +1,22 @@
+use log::Level;
+
+use crate::network::data_container::Container;
+use crate::network::node::{NodeContext, NodeFunction};
+use crate::network::nodes::text_config;
+use crate::network::registry::{NodeDescriptor, NodeRegistry};
+
+/// Nodes consuming their input, their output is always empty.
+pub fn register(registry: &mut NodeRegistry) {
+    registry.register_fn(NodeDescriptor::new("sink.log", "Logs its input with the `label` config at the `level` config", &[], &[]), |config| {
+        let label = text_config(config, "label", "sink.log")?;
+        let level = text_config(config, "level", "info")?;
+        let level: Level = level.parse().map_err(|_| format!("unknown log level '{}'", level))?;
+        let execute_fn: NodeFunction = Box::new(move |input: Container, _: &NodeContext| {
+            let mut items: Vec<String> = input.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+            items.sort();
+            log::log!(level, "{}: {}", label, items.join(" "));
+            Ok(Container::new())
+        });
+        Ok(execute_fn)
+    });
+}
@@ -0,0 +1,82 @@
+use crate::network::data_container::{Container, Data};
+use crate::network::node::{NodeContext, NodeError, NodeFunction};
+use crate::network::nodes::math::as_float;
+use crate::network::nodes::{single, text_config};
+use crate::network::registry::{NodeDescriptor, NodeRegistry};
+
+/// Nodes remembering earlier executions through `NodeContext::state`.
+pub fn register(registry: &mut NodeRegistry) {
+    registry.register_fn(NodeDescriptor::new("state.counter", "Counts its executions, a true reset input starts over", &["reset"], &["count"]), |_| {
+        let execute_fn: NodeFunction = Box::new(|input: Container, context: &NodeContext| {
+            let reset = matches!(input.get_item("reset"), Some(Data::Boolean(true)));
+            let count = context.state().update("count", 0, |count: i32| if reset { 0 } else { count.saturating_add(1) });
+            Ok(single("count", Data::Integer(count)))
+        });
+        Ok(execute_fn)
+    });
+
+    registry.register_fn(NodeDescriptor::new("state.accumulate", "Running sum of value, a Float once any value was one", &["value"], &["sum"]), |_| {
+        let execute_fn: NodeFunction = Box::new(|input: Container, context: &NodeContext| {
+            let value = match input.get_item("value") {
+                Some(value @ (Data::Integer(_) | Data::Float(_))) => value.clone(),
+                other => return Err(NodeError::invalid_input(format!("value must be a number, got {:?}", other))),
+            };
+            let sum = context.state().with(|state| {
+                let sum = match (state.get_item("sum"), &value) {
+                    (None, value) => value.clone(),
+                    (Some(Data::Integer(sum)), Data::Integer(value)) => sum.checked_add(*value).map(Data::Integer)
+                        .ok_or_else(|| NodeError::invalid_input("sum overflows"))?,
+                    (Some(sum), value) => Data::Float(as_float(sum).unwrap_or_default() + as_float(value).unwrap_or_default()),
+                };
+                state.add_item("sum", sum.clone());
+                Ok(sum)
+            })?;
+            Ok(single("sum", sum))
+        });
+        Ok(execute_fn)
+    });
+
+    registry.register_fn(NodeDescriptor::new("state.moving_average", "Average of the last `window` config values", &["value"], &["average"]), |config| {
+        let window = match config.get_item("window") {
+            Some(Data::Integer(window)) if *window > 0 => *window,
+            Some(other) => return Err(format!("config 'window' must be a positive integer, got {:?}", other)),
+            None => 10,
+        };
+        let execute_fn: NodeFunction = Box::new(move |input: Container, context: &NodeContext| {
+            let value = input.get_item("value").and_then(as_float)
+                .ok_or_else(|| NodeError::invalid_input(format!("value must be a number, got {:?}", input.get_item("value"))))?;
+            // The last values are kept as a ring under `value.<slot>`, `next` is the slot to overwrite
+            let average = context.state().with(|state| {
+                let next = match state.get_item("next") {
+                    Some(Data::Integer(next)) => *next % window,
+                    _ => 0,
+                };
+                state.add_item(&format!("value.{}", next), Data::Float(value));
+                state.add_item("next", Data::Integer((next + 1) % window));
+                let values: Vec<f64> = (0..window)
+                    .filter_map(|slot| state.get_item(&format!("value.{}", slot)).and_then(as_float))
+                    .collect();
+                values.iter().sum::<f64>() / values.len() as f64
+            });
+            Ok(single("average", Data::Float(average)))
+        });
+        Ok(execute_fn)
+    });
+
+    registry.register_fn(NodeDescriptor::new("state.distinct", "Outputs its input unless the `key` config item equals the previous one", &[], &[]), |config| {
+        let key = text_config(config, "key", "value")?;
+        let execute_fn: NodeFunction = Box::new(move |input: Container, context: &NodeContext| {
+            let value = match input.get_item(&key) {
+                Some(value) => value.clone(),
+                None => return Ok(Container::new()),
+            };
+            let changed = context.state().with(|state| {
+                let changed = state.get_item("last") != Some(&value);
+                state.add_item("last", value);
+                changed
+            });
+            Ok(if changed { input } else { Container::new() })
+        });
+        Ok(execute_fn)
+    });
+}
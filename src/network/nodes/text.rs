@@ -0,0 +1,71 @@
+use crate::network::data_container::{Container, Data};
+use crate::network::node::{NodeContext, NodeFunction};
+use crate::network::nodes::{single, text_config};
+use crate::network::registry::{NodeDescriptor, NodeRegistry};
+
+/// String handling, any input is turned into text through its `Display` form.
+pub fn register(registry: &mut NodeRegistry) {
+    registry.register_fn(NodeDescriptor::new("text.concat", "a and b joined by the `separator` config", &["a", "b"], &["result"]), |config| {
+        let separator = text_config(config, "separator", "")?;
+        let execute_fn: NodeFunction = Box::new(move |input: Container, _: &NodeContext| {
+            Ok(match (input.get_item("a"), input.get_item("b")) {
+                (Some(a), Some(b)) => single("result", Data::Text(format!("{}{}{}", a, separator, b))),
+                _ => Container::new(),
+            })
+        });
+        Ok(execute_fn)
+    });
+
+    registry.register_fn(NodeDescriptor::new("text.format", "Fills `{key}` placeholders of the `template` config from the inputs", &[], &["result"]), |config| {
+        let template = text_config(config, "template", "")?;
+        let execute_fn: NodeFunction = Box::new(move |input: Container, _: &NodeContext| Ok(single("result", Data::Text(format(&template, &input)))));
+        Ok(execute_fn)
+    });
+
+    registry.register_fn(NodeDescriptor::new("text.split", "Splits value at the `separator` config into outputs 0, 1, ...", &["value"], &["count"]), |config| {
+        let separator = text_config(config, "separator", ",")?;
+        if separator.is_empty() {
+            return Err("config 'separator' must not be empty".to_string());
+        }
+        let execute_fn: NodeFunction = Box::new(move |input: Container, _: &NodeContext| {
+            let value = match input.get_item("value") {
+                Some(Data::Text(value)) => value,
+                _ => return Ok(Container::new()),
+            };
+            let mut output = Container::new();
+            let mut count = 0;
+            for (index, part) in value.split(separator.as_str()).enumerate() {
+                output.add_item(&index.to_string(), Data::Text(part.to_string()));
+                count += 1;
+            }
+            output.add_item("count", Data::Integer(count));
+            Ok(output)
+        });
+        Ok(execute_fn)
+    });
+}
+
+// Placeholders without a matching input are kept as they are
+pub(crate) fn format(template: &str, input: &Container) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let placeholder = &rest[start..];
+        match placeholder.find('}') {
+            Some(end) => {
+                match input.get_item(&placeholder[1..end]) {
+                    Some(value) => result.push_str(&value.to_string()),
+                    None => result.push_str(&placeholder[..=end]),
+                }
+                rest = &placeholder[end + 1..];
+            }
+            None => {
+                rest = placeholder;
+                break;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
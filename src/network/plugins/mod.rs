@@ -0,0 +1,5 @@
+//! Node types loaded at runtime from a plugin directory.
+
+pub mod native;
+#[cfg(feature = "wasm")]
+pub mod wasm;
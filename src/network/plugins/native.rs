@@ -0,0 +1,127 @@
+//! Native node types from `cdylib` plugins.
+//!
+//! A plugin crate depends on `ada-executor` and exports the function adding its factories to the
+//! registry:
+//!
+//! ```ignore
+//! fn register(registry: &mut NodeRegistry) {
+//!     registry.register_fn(...);
+//! }
+//!
+//! ada_executor::export_plugin!(register);
+//! ```
+//!
+//! Rust has no stable ABI, so a plugin has to be built by the same compiler against the same
+//! version of this crate. Both are checked along with `NATIVE_ABI_VERSION` before `register` runs.
+//! Plugins only need the declaration, loading them takes the `plugins` feature. Loaded libraries
+//! stay loaded for the life of the process.
+
+use std::ffi::c_char;
+#[cfg(feature = "plugins")]
+use std::ffi::CStr;
+#[cfg(feature = "plugins")]
+use std::fs;
+#[cfg(feature = "plugins")]
+use std::io::{Error, ErrorKind};
+#[cfg(feature = "plugins")]
+use std::path::Path;
+
+#[cfg(feature = "plugins")]
+use libloading::Library;
+
+use crate::network::registry::NodeRegistry;
+
+/// Bumped whenever `PluginDeclaration` changes.
+pub const NATIVE_ABI_VERSION: u32 = 4;
+
+#[doc(hidden)]
+pub const RUSTC_VERSION: &str = concat!(env!("ADA_RUSTC_VERSION"), "\0");
+#[doc(hidden)]
+pub const CRATE_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+
+#[cfg(feature = "plugins")]
+const DECLARATION_SYMBOL: &[u8] = b"ada_plugin_declaration\0";
+
+/// Exported by every plugin as `ada_plugin_declaration`, see `export_plugin!`.
+///
+/// The version fields only use C types, so they can be checked even for plugins built by another
+/// compiler. `register` is only called once they match.
+#[repr(C)]
+pub struct PluginDeclaration {
+    pub abi_version: u32,
+    pub rustc_version: *const c_char,
+    pub crate_version: *const c_char,
+    pub register: fn(&mut NodeRegistry),
+}
+
+// Only holds pointers to static strings
+unsafe impl Sync for PluginDeclaration {}
+
+/// Declares `$register`, a `fn(&mut NodeRegistry)`, as the plugin's entry point.
+#[macro_export]
+macro_rules! export_plugin {
+    ($register:expr) => {
+        #[no_mangle]
+        #[allow(non_upper_case_globals)]
+        pub static ada_plugin_declaration: $crate::network::plugins::native::PluginDeclaration = $crate::network::plugins::native::PluginDeclaration {
+            abi_version: $crate::network::plugins::native::NATIVE_ABI_VERSION,
+            rustc_version: $crate::network::plugins::native::RUSTC_VERSION.as_ptr() as *const ::std::ffi::c_char,
+            crate_version: $crate::network::plugins::native::CRATE_VERSION.as_ptr() as *const ::std::ffi::c_char,
+            register: $register,
+        };
+    };
+}
+
+#[cfg(feature = "plugins")]
+fn expected(version: &str) -> &str {
+    version.trim_end_matches('\0')
+}
+
+/// Loads the plugin at `path` and lets it register its node types.
+#[cfg(feature = "plugins")]
+pub fn load_library(registry: &mut NodeRegistry, path: &Path) -> std::io::Result<()> {
+    let invalid = |message: String| Error::new(ErrorKind::InvalidData, format!("{}: {}", path.display(), message));
+
+    // Safety: loading runs the library's initialisers, plugins are trusted native code
+    let library = unsafe { Library::new(path) }.map_err(|e| invalid(e.to_string()))?;
+    let declaration = unsafe { library.get::<*const PluginDeclaration>(DECLARATION_SYMBOL) }
+        .map_err(|_| invalid("not a plugin, ada_plugin_declaration is missing".to_string()))?;
+    // Safety: the symbol is a `PluginDeclaration` static, whose version fields are C types
+    let declaration = unsafe { &**declaration };
+
+    if declaration.abi_version != NATIVE_ABI_VERSION {
+        return Err(invalid(format!("plugin ABI version {} is not supported, expected {}", declaration.abi_version, NATIVE_ABI_VERSION)));
+    }
+    // Safety: with a matching ABI version both fields point to NUL terminated strings
+    let (rustc_version, crate_version) = unsafe {
+        (CStr::from_ptr(declaration.rustc_version).to_string_lossy(), CStr::from_ptr(declaration.crate_version).to_string_lossy())
+    };
+    if rustc_version != expected(RUSTC_VERSION) {
+        return Err(invalid(format!("plugin built by '{}', the executor by '{}'", rustc_version, expected(RUSTC_VERSION))));
+    }
+    if crate_version != expected(CRATE_VERSION) {
+        return Err(invalid(format!("plugin built against ada-executor {}, running {}", crate_version, expected(CRATE_VERSION))));
+    }
+
+    (declaration.register)(registry);
+    // Factories and nodes point into the library's code
+    std::mem::forget(library);
+    Ok(())
+}
+
+/// Loads every shared library in `dir`, returns how many were loaded.
+#[cfg(feature = "plugins")]
+pub fn load_dir(registry: &mut NodeRegistry, dir: &Path) -> std::io::Result<usize> {
+    let mut paths: Vec<_> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    paths.retain(|path| path.extension().is_some_and(|extension| extension == std::env::consts::DLL_EXTENSION));
+    paths.sort();
+
+    for path in &paths {
+        let before = registry.descriptors().count();
+        load_library(registry, path)?;
+        log::info!("Loaded {} native node types from {}", registry.descriptors().count() - before, path.display());
+    }
+    Ok(paths.len())
+}
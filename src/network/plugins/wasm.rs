@@ -0,0 +1,158 @@
+//! Sandboxed WebAssembly node types.
+//!
+//! Every `<name>.wasm` module in the plugin directory is registered as node type `wasm.<name>`.
+//! Modules must not import anything and export:
+//! - `memory`
+//! - `ada_abi_version() -> i32`, returning `WASM_ABI_VERSION`
+//! - `ada_alloc(len: i32) -> i32`, reserving `len` bytes for the input
+//! - `ada_execute(ptr: i32, len: i32) -> i64`, returning `(out_ptr << 32) | out_len`
+//!
+//! Input and output are containers in the wire format of the command data field. Each execution
+//! runs in a fresh instance limited to the `fuel` and `max_memory_bytes` node config, which may not
+//! exceed the `Limits` the module was registered with.
+
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::sync::Arc;
+
+use wasmi::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::network::data_container::{Container, Data};
+use crate::network::node::{NodeContext, NodeError, NodeFunction};
+use crate::network::registry::{NodeDescriptor, NodeRegistry};
+use crate::protocol::protocol_parser::parse_data_container;
+
+pub const WASM_ABI_VERSION: i32 = 1;
+
+const DEFAULT_FUEL: u64 = 10_000_000;
+const DEFAULT_MAX_MEMORY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Fuel and memory an execution may use.
+///
+/// Passed to `register` and `load_dir`, they cap what the `fuel` and `max_memory_bytes` node config
+/// may ask for, so clients adding nodes cannot lift the sandbox the operator set up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub fuel: u64,
+    pub max_memory_bytes: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits { fuel: DEFAULT_FUEL, max_memory_bytes: DEFAULT_MAX_MEMORY_BYTES }
+    }
+}
+
+impl Limits {
+    // Nodes without a limit in their config get the default, capped by `max`
+    fn from_config(config: &Container, max: &Limits) -> Result<Self, String> {
+        let positive = |key: &str, default: u64, max: u64| match config.get_item(key) {
+            Some(Data::Integer(value)) if *value > 0 && *value as u64 <= max => Ok(*value as u64),
+            Some(Data::Integer(value)) if *value > 0 => Err(format!("config '{}' exceeds the maximum of {}", key, max)),
+            Some(other) => Err(format!("config '{}' must be a positive integer, got {:?}", key, other)),
+            None => Ok(default.min(max)),
+        };
+        Ok(Limits {
+            fuel: positive("fuel", DEFAULT_FUEL, max.fuel)?,
+            max_memory_bytes: positive("max_memory_bytes", DEFAULT_MAX_MEMORY_BYTES as u64, max.max_memory_bytes as u64)? as usize,
+        })
+    }
+}
+
+/// Compiled module shared by every node of its type.
+pub struct WasmPlugin {
+    engine: Engine,
+    module: Module,
+}
+
+impl WasmPlugin {
+    /// Compiles the module and checks its ABI version.
+    pub fn new(wasm: &[u8]) -> Result<Self, String> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, wasm).map_err(|e| format!("invalid module: {}", e))?;
+        if module.imports().len() > 0 {
+            return Err("modules must not import anything".to_string());
+        }
+        let plugin = WasmPlugin { engine, module };
+
+        let mut store = plugin.store(&Limits::default())?;
+        let instance = Linker::new(&plugin.engine).instantiate_and_start(&mut store, &plugin.module).map_err(|e| e.to_string())?;
+        let version = instance.get_typed_func::<(), i32>(&store, "ada_abi_version")
+            .and_then(|abi_version| abi_version.call(&mut store, ()))
+            .map_err(|e| format!("missing ada_abi_version: {}", e))?;
+        if version != WASM_ABI_VERSION {
+            return Err(format!("ABI version {} is not supported, expected {}", version, WASM_ABI_VERSION));
+        }
+        Ok(plugin)
+    }
+
+    fn store(&self, limits: &Limits) -> Result<Store<StoreLimits>, String> {
+        let mut store = Store::new(&self.engine, StoreLimitsBuilder::new().memory_size(limits.max_memory_bytes).build());
+        store.limiter(|limits| limits);
+        store.set_fuel(limits.fuel).map_err(|e| e.to_string())?;
+        Ok(store)
+    }
+
+    fn execute(&self, limits: &Limits, input: &Container) -> Result<Container, String> {
+        let mut store = self.store(limits)?;
+        let instance = Linker::new(&self.engine).instantiate_and_start(&mut store, &self.module).map_err(|e| e.to_string())?;
+        let memory = instance.get_memory(&store, "memory").ok_or("missing memory export")?;
+        let alloc = instance.get_typed_func::<i32, i32>(&store, "ada_alloc").map_err(|e| e.to_string())?;
+        let execute = instance.get_typed_func::<(i32, i32), i64>(&store, "ada_execute").map_err(|e| e.to_string())?;
+
+        let bytes = input.get_bytes();
+        let ptr = alloc.call(&mut store, bytes.len() as i32).map_err(|e| e.to_string())?;
+        memory.write(&mut store, ptr as u32 as usize, &bytes).map_err(|e| e.to_string())?;
+
+        let packed = execute.call(&mut store, (ptr, bytes.len() as i32)).map_err(|e| e.to_string())?;
+        let (out_ptr, out_len) = ((packed as u64 >> 32) as usize, (packed as u64 & 0xffff_ffff) as usize);
+        if out_len > memory.data_size(&store) {
+            return Err("output exceeds memory".to_string());
+        }
+        let mut output = vec![0; out_len];
+        memory.read(&store, out_ptr, &mut output).map_err(|e| e.to_string())?;
+        parse_data_container(output, 0).ok_or_else(|| "malformed output container".to_string())
+    }
+}
+
+/// Registers `plugin` as `type_name`, nodes asking for more than `max` are refused.
+/// Failed executions fail the node with an `Internal` error.
+pub fn register(registry: &mut NodeRegistry, type_name: &str, plugin: WasmPlugin, max: Limits) {
+    let plugin = Arc::new(plugin);
+    let descriptor = NodeDescriptor::new(type_name, "WebAssembly plugin", &[], &[]);
+    let node_type = type_name.to_string();
+    registry.register_fn(descriptor, move |config| {
+        let limits = Limits::from_config(config, &max)?;
+        let plugin = Arc::clone(&plugin);
+        let node_type = node_type.clone();
+        let execute_fn: NodeFunction = Box::new(move |input: Container, _: &NodeContext| {
+            plugin.execute(&limits, &input).map_err(|e| {
+                log::warn!("{} failed: {}", node_type, e);
+                NodeError::internal(e)
+            })
+        });
+        Ok(execute_fn)
+    });
+}
+
+/// Registers every `*.wasm` module in `dir` with the limits capped by `max`, returns how many were loaded.
+pub fn load_dir(registry: &mut NodeRegistry, dir: &Path, max: Limits) -> std::io::Result<usize> {
+    let mut paths: Vec<_> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    paths.retain(|path| path.extension().is_some_and(|extension| extension == "wasm"));
+    paths.sort();
+
+    for path in &paths {
+        let name = path.file_stem().and_then(|name| name.to_str())
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("{}: invalid file name", path.display())))?;
+        let plugin = WasmPlugin::new(&fs::read(path)?)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))?;
+        register(registry, &format!("wasm.{}", name), plugin, max);
+        log::info!("Loaded WebAssembly node type wasm.{} from {}", name, path.display());
+    }
+    Ok(paths.len())
+}
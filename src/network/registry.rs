@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::network::data_container::Container;
+use crate::network::node::{Node, NodeFunction};
+
+/// Describes a registered node type, its ports are the container keys it reads and writes.
+#[derive(Debug, Clone)]
+pub struct NodeDescriptor {
+    /// Registry key such as `math.add`
+    pub type_name: String,
+    pub description: String,
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+}
+
+impl NodeDescriptor {
+    pub fn new(type_name: &str, description: &str, inputs: &[&str], outputs: &[&str]) -> Self {
+        NodeDescriptor {
+            type_name: type_name.to_string(),
+            description: description.to_string(),
+            inputs: inputs.iter().map(|port| port.to_string()).collect(),
+            outputs: outputs.iter().map(|port| port.to_string()).collect(),
+        }
+    }
+}
+
+/// Builds the behaviour of a node type from the node's config.
+pub trait NodeFactory: Send + Sync {
+    fn descriptor(&self) -> &NodeDescriptor;
+
+    fn create(&self, config: &Container) -> Result<NodeFunction, String>;
+}
+
+/// `NodeFactory` backed by a closure.
+pub struct FnNodeFactory<F> {
+    descriptor: NodeDescriptor,
+    create_fn: F,
+}
+
+impl<F> FnNodeFactory<F>
+    where
+        F: Fn(&Container) -> Result<NodeFunction, String> + Send + Sync,
+{
+    pub fn new(descriptor: NodeDescriptor, create_fn: F) -> Self {
+        FnNodeFactory {
+            descriptor,
+            create_fn,
+        }
+    }
+}
+
+impl<F> NodeFactory for FnNodeFactory<F>
+    where
+        F: Fn(&Container) -> Result<NodeFunction, String> + Send + Sync,
+{
+    fn descriptor(&self) -> &NodeDescriptor {
+        &self.descriptor
+    }
+
+    fn create(&self, config: &Container) -> Result<NodeFunction, String> {
+        (self.create_fn)(config)
+    }
+}
+
+/// Maps node type names to the factories building them.
+#[derive(Default)]
+pub struct NodeRegistry {
+    factories: HashMap<String, Box<dyn NodeFactory>>,
+}
+
+impl NodeRegistry {
+    pub fn new() -> Self {
+        NodeRegistry {
+            factories: HashMap::new(),
+        }
+    }
+
+    /// Registry holding the standard library of `crate::network::nodes`.
+    pub fn with_builtins() -> Self {
+        let mut registry = NodeRegistry::new();
+        crate::network::nodes::register_builtins(&mut registry);
+        registry
+    }
+
+    /// Registers `factory` under its descriptor's type name, replacing any previous registration.
+    pub fn register<T: NodeFactory + 'static>(&mut self, factory: T) {
+        let type_name = factory.descriptor().type_name.clone();
+        if self.factories.insert(type_name.clone(), Box::new(factory)).is_some() {
+            log::warn!("Node type {} registered twice, keeping the latest", type_name);
+        }
+    }
+
+    /// Shorthand for registering a `FnNodeFactory`.
+    pub fn register_fn<F>(&mut self, descriptor: NodeDescriptor, create_fn: F)
+        where
+            F: Fn(&Container) -> Result<NodeFunction, String> + Send + Sync + 'static,
+    {
+        self.register(FnNodeFactory::new(descriptor, create_fn));
+    }
+
+    pub fn descriptor(&self, type_name: &str) -> Option<&NodeDescriptor> {
+        self.factories.get(type_name).map(|factory| factory.descriptor())
+    }
+
+    pub fn descriptors(&self) -> impl Iterator<Item = &NodeDescriptor> {
+        self.factories.values().map(|factory| factory.descriptor())
+    }
+
+    pub fn create_node(&self, type_name: &str, id: Uuid, config: Container) -> Result<Node, String> {
+        let factory = self.factories.get(type_name)
+            .ok_or_else(|| format!("unknown node type '{}'", type_name))?;
+        let execute_fn = factory.create(&config)?;
+        Ok(Node::with_type(id, type_name, config, execute_fn))
+    }
+}
@@ -0,0 +1,396 @@
+//! Executing a graph as a run, bounded by timeouts, retrying failed nodes and cancellable from another thread.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use uuid::Uuid;
+
+use crate::network::data_container::{Container, Data};
+use crate::network::graph::{Edge, FailurePolicy, Graph, NodeStatus};
+use crate::network::node::{CancellationToken, Node, NodeContext, NodeError, NodeErrorKind, NodeResult};
+use crate::network::state::NodeState;
+
+// How often a waiting run checks for cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    pub id: Uuid,
+    /// The run stops starting nodes once exceeded, the running node counts as timed out
+    pub timeout: Option<Duration>,
+    pub token: CancellationToken,
+    /// Receives a `NodeEvent` whenever a node of the run finished
+    pub events: Option<Sender<NodeEvent>>,
+}
+
+impl RunOptions {
+    pub fn new() -> Self {
+        RunOptions {
+            id: Uuid::new_v4(),
+            ..RunOptions::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    Completed,
+    TimedOut,
+    Cancelled,
+    /// Stopped by a failing node under `FailurePolicy::Abort`
+    Failed,
+}
+
+impl fmt::Display for RunStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunStatus::Completed => write!(f, "completed"),
+            RunStatus::TimedOut => write!(f, "timed_out"),
+            RunStatus::Cancelled => write!(f, "cancelled"),
+            RunStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// A node of a run reached its final status.
+#[derive(Debug, Clone)]
+pub struct NodeEvent {
+    pub run: Uuid,
+    pub network: Uuid,
+    pub node: Uuid,
+    pub status: NodeStatus,
+    pub output: Option<Container>,
+    pub error: Option<NodeError>,
+}
+
+/// One attempt at executing a node.
+#[derive(Debug, Clone)]
+pub struct TraceEntry {
+    pub node: Uuid,
+    /// Counts from 1, retries of the same node increase it
+    pub attempt: u32,
+    pub started_at: SystemTime,
+    pub duration: Duration,
+    pub input: Container,
+    /// Set if the attempt returned an output
+    pub output: Option<Container>,
+    pub status: NodeStatus,
+    pub error: Option<NodeError>,
+}
+
+/// Outcome of a run.
+#[derive(Debug, Clone)]
+pub struct RunReport {
+    pub id: Uuid,
+    /// Graph the run executed on
+    pub network: Uuid,
+    /// Node the run started at
+    pub node: Uuid,
+    pub status: RunStatus,
+    pub started_at: SystemTime,
+    pub duration: Duration,
+    /// Output of every node that finished
+    pub outputs: HashMap<Uuid, Container>,
+    /// Status of every node the run covered
+    pub statuses: HashMap<Uuid, NodeStatus>,
+    /// Error of the last attempt of every node that failed or timed out
+    pub errors: HashMap<Uuid, NodeError>,
+    /// Every attempt in the order they started
+    pub trace: Vec<TraceEntry>,
+}
+
+impl RunReport {
+    /// Nodes that never finished, sorted by id.
+    pub fn incomplete(&self) -> Vec<Uuid> {
+        let mut incomplete: Vec<Uuid> = self.statuses.iter()
+            .filter(|(_, status)| !matches!(status, NodeStatus::Completed | NodeStatus::NoOutput | NodeStatus::Failed))
+            .map(|(id, _)| *id)
+            .collect();
+        incomplete.sort();
+        incomplete
+    }
+
+    /// Nodes that failed or timed out, sorted by id.
+    pub fn failed(&self) -> Vec<Uuid> {
+        let mut failed: Vec<Uuid> = self.errors.keys().copied().collect();
+        failed.sort();
+        failed
+    }
+}
+
+impl Graph {
+    /// Runs the given node and, with `propagate`, everything downstream of it.
+    ///
+    /// A node runs once all of its upstream nodes reachable from `id` have run, its input is the
+    /// merge of the values their edges deliver. Nodes on a cycle never become ready and are skipped.
+    ///
+    /// Every node executes on its own thread so its timeout and the run's cancellation are honoured
+    /// even if it never returns. Such a node's thread is abandoned, not killed.
+    ///
+    /// A failing node is attempted again as its `RetryPolicy` allows, what happens downstream of a
+    /// node that failed for good follows the graph's `FailurePolicy`. Panics count as failures.
+    pub fn run(&self, id: &Uuid, arguments: Container, propagate: bool, options: &RunOptions) -> Option<RunReport> {
+        self.get_node(id)?;
+        let started_at = SystemTime::now();
+        let started = Instant::now();
+        let run_deadline = options.timeout.map(|timeout| Instant::now() + timeout);
+        let covered = if propagate { self.reachable_from(id) } else { HashSet::from([*id]) };
+        // Edges leading back into the start node would run it a second time
+        let edges: Vec<_> = self.edges().iter()
+            .filter(|edge| propagate && covered.contains(&edge.from) && edge.to != *id)
+            .collect();
+        let mut waiting_on: HashMap<Uuid, usize> = covered.iter().map(|node| (*node, 0)).collect();
+        for edge in &edges {
+            *waiting_on.get_mut(&edge.to).unwrap() += 1;
+        }
+
+        let mut inputs: HashMap<Uuid, Container> = HashMap::from([(*id, arguments)]);
+        let mut outputs: HashMap<Uuid, Container> = HashMap::new();
+        let mut statuses: HashMap<Uuid, NodeStatus> = HashMap::new();
+        let mut errors: HashMap<Uuid, NodeError> = HashMap::new();
+        let mut trace = Vec::new();
+        // Nodes depending on a failed or skipped node
+        let mut blocked: HashSet<Uuid> = HashSet::new();
+        let mut ready = VecDeque::from([*id]);
+        let mut status = RunStatus::Completed;
+
+        while let Some(current) = ready.pop_front() {
+            if options.token.is_cancelled() {
+                status = RunStatus::Cancelled;
+                break;
+            }
+            if run_deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                status = RunStatus::TimedOut;
+                break;
+            }
+
+            let input = inputs.remove(&current).unwrap_or_default();
+            if blocked.contains(&current) {
+                log::debug!("Run {} skips {}, an upstream node failed", options.id, current);
+                statuses.insert(current, NodeStatus::Skipped);
+                for edge in edges.iter().filter(|edge| edge.from == current) {
+                    blocked.insert(edge.to);
+                }
+                release(current, &edges, &mut waiting_on, &mut ready);
+                continue;
+            }
+            log::debug!("Run {} calls {}({:?})", options.id, current, input);
+            let node_status = match self.execute_with_retries(current, input, run_deadline, options, &mut trace) {
+                Ok(Ok(output)) => {
+                    let node_status = NodeStatus::of(&output);
+                    for edge in edges.iter().filter(|edge| edge.from == current) {
+                        if let Some(value) = output.get_item(&edge.from_port) {
+                            inputs.entry(edge.to).or_default().add_item(&edge.to_port, value.clone());
+                        }
+                    }
+                    outputs.insert(current, output);
+                    node_status
+                }
+                Ok(Err(error)) => {
+                    let node_status = status_of_error(&error);
+                    errors.insert(current, error);
+                    node_status
+                }
+                Err(node_status) => node_status,
+            };
+            statuses.insert(current, node_status);
+            if let Some(events) = &options.events {
+                // Nobody listening any more is not the run's concern
+                let _ = events.send(NodeEvent {
+                    run: options.id,
+                    network: self.id(),
+                    node: current,
+                    status: node_status,
+                    output: outputs.get(&current).cloned(),
+                    error: errors.get(&current).cloned(),
+                });
+            }
+
+            match node_status {
+                NodeStatus::Cancelled => {
+                    status = RunStatus::Cancelled;
+                    break;
+                }
+                NodeStatus::TimedOut if run_deadline.is_some_and(|deadline| Instant::now() >= deadline) => {
+                    status = RunStatus::TimedOut;
+                    break;
+                }
+                _ => {}
+            }
+            if let Some(error) = errors.get(&current) {
+                log::warn!("Node {} failed in run {}: {}", current, options.id, error);
+                match self.failure_policy() {
+                    FailurePolicy::Abort => {
+                        status = RunStatus::Failed;
+                        break;
+                    }
+                    FailurePolicy::SkipDependents => {
+                        for edge in edges.iter().filter(|edge| edge.from == current) {
+                            blocked.insert(edge.to);
+                        }
+                    }
+                    FailurePolicy::ErrorPort(port) => {
+                        for edge in edges.iter().filter(|edge| edge.from == current) {
+                            if edge.from_port == *port {
+                                inputs.entry(edge.to).or_default().add_item(&edge.to_port, Data::Text(error.message.clone()));
+                            } else {
+                                blocked.insert(edge.to);
+                            }
+                        }
+                    }
+                }
+            }
+            release(current, &edges, &mut waiting_on, &mut ready);
+        }
+
+        let unfinished = match status {
+            RunStatus::Completed => NodeStatus::Skipped,
+            RunStatus::TimedOut => NodeStatus::TimedOut,
+            RunStatus::Cancelled => NodeStatus::Cancelled,
+            RunStatus::Failed => NodeStatus::Skipped,
+        };
+        for node in covered {
+            statuses.entry(node).or_insert(unfinished);
+        }
+        if status != RunStatus::Completed {
+            log::warn!("Run {} stopped: {}", options.id, status);
+        }
+        Some(RunReport {
+            id: options.id,
+            network: self.id(),
+            node: *id,
+            status,
+            started_at,
+            duration: started.elapsed(),
+            outputs,
+            statuses,
+            errors,
+            trace,
+        })
+    }
+
+    // Attempts the node until it succeeds, fails for good or the run stops, recording every attempt
+    fn execute_with_retries(&self, id: Uuid, input: Container, run_deadline: Option<Instant>, options: &RunOptions, trace: &mut Vec<TraceEntry>) -> Result<NodeResult, NodeStatus> {
+        let node = self.shared_node(&id).ok_or(NodeStatus::Skipped)?;
+        let mut attempt = 1;
+        loop {
+            let started_at = SystemTime::now();
+            let started = Instant::now();
+            let result = execute_bounded(Arc::clone(&node), input.clone(), self.state().node(id), run_deadline, options);
+            let (status, output, error) = match &result {
+                Ok(Ok(output)) => (NodeStatus::of(output), Some(output.clone()), None),
+                Ok(Err(error)) => (status_of_error(error), None, Some(error.clone())),
+                Err(status) => (*status, None, None),
+            };
+            trace.push(TraceEntry {
+                node: id,
+                attempt,
+                started_at,
+                duration: started.elapsed(),
+                input: input.clone(),
+                output,
+                status,
+                error,
+            });
+
+            let retry = &node.policy().retry;
+            match result {
+                Ok(Err(error)) if retry.should_retry(attempt, &error) => {
+                    let delay = retry.backoff.delay(attempt);
+                    log::info!("Node {} failed attempt {} in run {}, retrying in {:?}: {}", id, attempt, options.id, delay, error);
+                    wait(delay, run_deadline, options)?;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+// Counts `id` as done for its dependents, queueing those with nothing left to wait for
+fn release(id: Uuid, edges: &[&Edge], waiting_on: &mut HashMap<Uuid, usize>, ready: &mut VecDeque<Uuid>) {
+    for edge in edges.iter().filter(|edge| edge.from == id) {
+        let remaining = waiting_on.get_mut(&edge.to).unwrap();
+        *remaining -= 1;
+        if *remaining == 0 {
+            ready.push_back(edge.to);
+        }
+    }
+}
+
+fn status_of_error(error: &NodeError) -> NodeStatus {
+    match error.kind {
+        NodeErrorKind::Timeout => NodeStatus::TimedOut,
+        _ => NodeStatus::Failed,
+    }
+}
+
+// Sleeps through a retry delay unless the run stops in the meantime
+fn wait(delay: Duration, run_deadline: Option<Instant>, options: &RunOptions) -> Result<(), NodeStatus> {
+    let until = Instant::now() + delay;
+    loop {
+        if options.token.is_cancelled() {
+            return Err(NodeStatus::Cancelled);
+        }
+        let now = Instant::now();
+        if run_deadline.is_some_and(|deadline| now >= deadline) {
+            return Err(NodeStatus::TimedOut);
+        }
+        if now >= until {
+            return Ok(());
+        }
+        thread::sleep(until.saturating_duration_since(now).min(POLL_INTERVAL));
+    }
+}
+
+// Waits for the node until it returns, its deadline passes or the run is cancelled. Passing the
+// node's own deadline is a `Timeout` error, the run's deadline or cancellation stop the run.
+fn execute_bounded(node: Arc<Node>, input: Container, state: NodeState, run_deadline: Option<Instant>, options: &RunOptions) -> Result<NodeResult, NodeStatus> {
+    let id = node.id();
+    let timeout = node.policy().timeout;
+    let node_deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let deadline = match (node_deadline, run_deadline) {
+        (Some(node), Some(run)) => Some(node.min(run)),
+        (deadline, None) | (None, deadline) => deadline,
+    };
+    let token = CancellationToken::new();
+    let context = NodeContext::new(options.id, id, token.clone(), deadline).with_state(state);
+
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(node.execute_with(input, &context));
+    });
+
+    loop {
+        let wait = match deadline {
+            Some(deadline) => deadline.saturating_duration_since(Instant::now()).min(POLL_INTERVAL),
+            None => POLL_INTERVAL,
+        };
+        match receiver.recv_timeout(wait) {
+            Ok(result) => return Ok(result),
+            Err(RecvTimeoutError::Disconnected) => {
+                log::error!("Node {} stopped without an output", id);
+                return Ok(Err(NodeError::internal("stopped without an output")));
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        if options.token.is_cancelled() {
+            token.cancel();
+            return Err(NodeStatus::Cancelled);
+        }
+        let now = Instant::now();
+        if deadline.is_some_and(|deadline| now >= deadline) {
+            log::warn!("Node {} timed out in run {}", id, options.id);
+            token.cancel();
+            if run_deadline.is_some_and(|deadline| now >= deadline) {
+                return Err(NodeStatus::TimedOut);
+            }
+            let message = format!("timed out after {}ms", timeout.unwrap_or_default().as_millis());
+            return Ok(Err(NodeError::new(NodeErrorKind::Timeout, message)));
+        }
+    }
+}
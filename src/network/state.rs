@@ -0,0 +1,171 @@
+//! State kept by nodes between executions.
+//!
+//! Every graph owns a `StateStore` holding one `Container` per node, a node reaches its own part
+//! through `NodeContext::state`. Values are `Data` so the state can be saved along with the graph.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use uuid::Uuid;
+
+use crate::network::data_container::{Container, Data};
+
+/// Types a `NodeState` can hold.
+pub trait StateValue: Sized {
+    fn into_data(self) -> Data;
+
+    /// None if `data` holds another type.
+    fn from_data(data: &Data) -> Option<Self>;
+}
+
+impl StateValue for i32 {
+    fn into_data(self) -> Data {
+        Data::Integer(self)
+    }
+
+    fn from_data(data: &Data) -> Option<Self> {
+        match data {
+            Data::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl StateValue for f64 {
+    fn into_data(self) -> Data {
+        Data::Float(self)
+    }
+
+    fn from_data(data: &Data) -> Option<Self> {
+        match data {
+            Data::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl StateValue for String {
+    fn into_data(self) -> Data {
+        Data::Text(self)
+    }
+
+    fn from_data(data: &Data) -> Option<Self> {
+        match data {
+            Data::Text(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl StateValue for bool {
+    fn into_data(self) -> Data {
+        Data::Boolean(self)
+    }
+
+    fn from_data(data: &Data) -> Option<Self> {
+        match data {
+            Data::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl StateValue for Data {
+    fn into_data(self) -> Data {
+        self
+    }
+
+    fn from_data(data: &Data) -> Option<Self> {
+        Some(data.clone())
+    }
+}
+
+/// State of every node of a graph, clones share the same state.
+#[derive(Debug, Clone, Default)]
+pub struct StateStore(Arc<Mutex<HashMap<Uuid, Container>>>);
+
+impl StateStore {
+    pub fn new() -> Self {
+        StateStore::default()
+    }
+
+    /// Handle on the state of the given node.
+    pub fn node(&self, id: Uuid) -> NodeState {
+        NodeState { store: self.clone(), node: id }
+    }
+
+    /// Copy of the node's state, None if it never stored anything.
+    pub fn get(&self, id: &Uuid) -> Option<Container> {
+        self.lock().get(id).cloned()
+    }
+
+    /// Replaces the node's state, e.g. when restoring a saved graph.
+    pub fn set(&self, id: Uuid, state: Container) {
+        self.lock().insert(id, state);
+    }
+
+    pub fn remove(&self, id: &Uuid) -> Option<Container> {
+        self.lock().remove(id)
+    }
+
+    /// Copy of every node's state.
+    pub fn snapshot(&self) -> HashMap<Uuid, Container> {
+        self.lock().clone()
+    }
+
+    // Nodes panic while holding the lock at worst, which leaves their state as it was
+    fn lock(&self) -> MutexGuard<'_, HashMap<Uuid, Container>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The part of a `StateStore` belonging to one node.
+///
+/// Runs may execute the same node concurrently, `update` and `with` change the state atomically.
+#[derive(Debug, Clone)]
+pub struct NodeState {
+    store: StateStore,
+    node: Uuid,
+}
+
+impl NodeState {
+    /// State of its own, not shared with any graph.
+    pub fn detached(node: Uuid) -> Self {
+        StateStore::new().node(node)
+    }
+
+    /// None if `key` is unset or holds another type.
+    pub fn get<T: StateValue>(&self, key: &str) -> Option<T> {
+        self.store.lock().get(&self.node).and_then(|state| state.get_item(key)).and_then(T::from_data)
+    }
+
+    pub fn set<T: StateValue>(&self, key: &str, value: T) {
+        self.with(|state| state.add_item(key, value.into_data()));
+    }
+
+    pub fn remove(&self, key: &str) -> Option<Data> {
+        self.store.lock().get_mut(&self.node).and_then(|state| state.remove_item(key))
+    }
+
+    /// Replaces the value under `key`, starting from `default` if unset, and returns the new value.
+    pub fn update<T: StateValue + Clone>(&self, key: &str, default: T, f: impl FnOnce(T) -> T) -> T {
+        self.with(|state| {
+            let current = state.get_item(key).and_then(T::from_data).unwrap_or(default);
+            let updated = f(current);
+            state.add_item(key, updated.clone().into_data());
+            updated
+        })
+    }
+
+    /// Runs `f` on the whole state of the node while no other execution can change it, `f` must not
+    /// use the store again.
+    pub fn with<R>(&self, f: impl FnOnce(&mut Container) -> R) -> R {
+        let mut store = self.store.lock();
+        let state = store.entry(self.node).or_default();
+        f(state)
+    }
+
+    pub fn clear(&self) {
+        self.store.remove(&self.node);
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::path::Path;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::network::data_container::{Container, Data};
+use crate::protocol::commands::{AdaCommand, AdaCommandType};
+use crate::protocol::protocol_parser::read_command;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Shared secrets of every identity allowed to connect.
+///
+/// Loaded from a text file with one `<identity> <secret>` pair per line, `#` starts a comment.
+pub struct KeyStore {
+    keys: HashMap<String, String>,
+}
+
+impl KeyStore {
+    pub fn new(keys: HashMap<String, String>) -> Self {
+        KeyStore { keys }
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let mut keys = HashMap::new();
+        for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            match line.split_once(char::is_whitespace) {
+                Some((identity, secret)) => {
+                    keys.insert(identity.to_string(), secret.trim().to_string());
+                }
+                None => {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("{}:{}: expected '<identity> <secret>'", path.display(), number + 1)));
+                }
+            }
+        }
+        log::info!("Loaded {} identities from {}", keys.len(), path.display());
+        Ok(KeyStore { keys })
+    }
+
+    pub(crate) fn verify_token(&self, identity: &str, token: &str) -> bool {
+        match self.keys.get(identity) {
+            Some(secret) => constant_time_eq(secret.as_bytes(), token.as_bytes()),
+            None => false,
+        }
+    }
+
+    fn verify_response(&self, identity: &str, nonce: &str, response: &str) -> bool {
+        let (secret, response) = match (self.keys.get(identity), from_hex(response)) {
+            (Some(secret), Some(response)) => (secret, response),
+            _ => return false,
+        };
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+        mac.update(nonce.as_bytes());
+        mac.verify_slice(&response).is_ok()
+    }
+}
+
+/// What the connection should do with a frame received before authentication completed.
+pub enum AuthOutcome {
+    /// Handshake finished, the identity applies to every later command of the connection.
+    Authenticated(String, AdaCommand),
+    /// Handshake continues, send the reply and wait for the next frame.
+    Continue(AdaCommand),
+    /// Send the reply and close the connection.
+    Rejected(AdaCommand),
+}
+
+/// Server side of the handshake for one connection.
+///
+/// An `Auth` command carrying `identity` and `token` authenticates with a static token. One
+/// carrying only `identity` starts a challenge: the server answers `AuthChallenge` with a `nonce`
+/// and expects a second `Auth` with `identity` and `response`, the hex encoded HMAC-SHA256 of the
+/// nonce keyed with the identity's secret.
+pub struct Authenticator<'a> {
+    key_store: &'a KeyStore,
+    // Identity and nonce of the challenge awaiting a response
+    challenge: Option<(String, String)>,
+}
+
+impl<'a> Authenticator<'a> {
+    pub fn new(key_store: &'a KeyStore) -> Self {
+        Authenticator {
+            key_store,
+            challenge: None,
+        }
+    }
+
+    pub fn handle(&mut self, command: &AdaCommand) -> AuthOutcome {
+        if !matches!(command.header.command_type, AdaCommandType::Auth) {
+            return AuthOutcome::Rejected(command.error("authentication required"));
+        }
+        let identity = match text_item(command, "identity") {
+            Some(identity) => identity,
+            None => return AuthOutcome::Rejected(command.error("missing identity")),
+        };
+
+        let accepted = if let Some(token) = text_item(command, "token") {
+            self.key_store.verify_token(&identity, &token)
+        } else if let Some(response) = text_item(command, "response") {
+            match self.challenge.take() {
+                Some((challenged, nonce)) if challenged == identity => self.key_store.verify_response(&identity, &nonce, &response),
+                _ => false,
+            }
+        } else {
+            let nonce = to_hex(&[*Uuid::new_v4().as_bytes(), *Uuid::new_v4().as_bytes()].concat());
+            let mut data = Container::new();
+            data.add_item("nonce", Data::Text(nonce.clone()));
+            self.challenge = Some((identity, nonce));
+            return AuthOutcome::Continue(command.reply(AdaCommandType::AuthChallenge, Some(data)));
+        };
+
+        if accepted {
+            log::info!("Authenticated {}", identity);
+            AuthOutcome::Authenticated(identity, command.reply(AdaCommandType::Response, None))
+        } else {
+            log::warn!("Rejected authentication attempt for {}", identity);
+            AuthOutcome::Rejected(command.error("authentication failed"))
+        }
+    }
+}
+
+/// Client side of the challenge-response handshake.
+pub fn authenticate<S: Read + Write>(stream: &mut S, identity: &str, secret: &str) -> std::io::Result<()> {
+    let mut data = Container::new();
+    data.add_item("identity", Data::Text(identity.to_string()));
+    let mut command = AdaCommand::new(AdaCommandType::Auth);
+    command.data = Some(data.clone());
+    stream.write_all(&command.to_bytes())?;
+    stream.flush()?;
+
+    let challenge = read_command(stream)?;
+    let nonce = match (&challenge.header.command_type, text_item(&challenge, "nonce")) {
+        (AdaCommandType::AuthChallenge, Some(nonce)) => nonce,
+        _ => return Err(Error::new(ErrorKind::PermissionDenied, "server did not send a challenge")),
+    };
+
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(nonce.as_bytes());
+    data.add_item("response", Data::Text(to_hex(&mac.finalize().into_bytes())));
+    command.data = Some(data);
+    stream.write_all(&command.to_bytes())?;
+    stream.flush()?;
+
+    match read_command(stream)?.header.command_type {
+        AdaCommandType::Response => Ok(()),
+        _ => Err(Error::new(ErrorKind::PermissionDenied, "authentication failed")),
+    }
+}
+
+fn text_item(command: &AdaCommand, key: &str) -> Option<String> {
+    match command.data.as_ref()?.get_item(key)? {
+        Data::Text(value) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+// Compares digests under a random key, so neither the contents nor the lengths of the values show in the timing
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let key = Uuid::new_v4();
+    let digest = |value: &[u8]| {
+        let mut mac = HmacSha256::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any length");
+        mac.update(value);
+        mac
+    };
+    digest(a).verify_slice(&digest(b).finalize().into_bytes()).is_ok()
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
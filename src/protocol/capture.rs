@@ -0,0 +1,224 @@
+//! Capture of every command the listeners accept, and replay of a capture into a fresh executor.
+//!
+//! Frames are recorded once they were parsed and authenticated, so frames the listeners dropped or
+//! refused are left out. Commands the access control list denies are recorded with their identity
+//! and are denied again when replayed with the same list.
+//!
+//! A capture file starts with `ADACAP1\n`, followed by records, all integers big endian:
+//! - frame: `1`, microseconds since the epoch `u64`, connection id (16 bytes), identity length
+//!   `u16` (`u16::MAX` without identity), identity, frame length `u32`, frame as received
+//! - closed: `2`, microseconds since the epoch `u64`, connection id (16 bytes)
+//!
+//! The identity is the one the connection had authenticated as when the frame arrived, so a replay
+//! can do without the `Auth` handshake, whose challenges never repeat, and still apply access
+//! control. `Auth` frames are never recorded since they carry the client's secret or token, and the
+//! file is only readable by its owner.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Error, ErrorKind, Read, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use uuid::Uuid;
+
+use crate::protocol::commands::{AdaCommand, AdaCommandType, CommandOrigin};
+use crate::protocol::protocol_parser::parse_command;
+use crate::protocol::transport::connection_closed;
+
+const MAGIC: &[u8] = b"ADACAP1\n";
+const FRAME: u8 = 1;
+const CLOSED: u8 = 2;
+const NO_IDENTITY: u16 = u16::MAX;
+
+/// Appends the commands of every connection to a capture file.
+pub struct Capture {
+    file: Mutex<File>,
+}
+
+impl Capture {
+    /// Opens the capture at `path`, later records are appended to an existing one.
+    pub fn open(path: &Path) -> std::io::Result<Capture> {
+        let mut options = OpenOptions::new();
+        options.create(true).append(true).read(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path)?;
+        if file.metadata()?.len() == 0 {
+            file.write_all(MAGIC)?;
+        } else {
+            let mut magic = [0; MAGIC.len()];
+            file.read_exact(&mut magic)?;
+            if magic != MAGIC {
+                return Err(Error::new(ErrorKind::InvalidData, format!("{} is not a capture file", path.display())));
+            }
+        }
+        Ok(Capture { file: Mutex::new(file) })
+    }
+
+    /// Records `frame` as accepted on `connection`, which had authenticated as `identity`.
+    /// `Auth` frames are left out.
+    pub fn frame(&self, connection: Uuid, identity: Option<&str>, frame: &[u8]) -> std::io::Result<()> {
+        // The command type follows the version and the content length
+        if frame.get(5) == Some(&(AdaCommandType::Auth as u8)) {
+            return Ok(());
+        }
+        let mut record = header(FRAME, connection);
+        match identity {
+            Some(identity) if identity.len() < NO_IDENTITY as usize => {
+                record.extend_from_slice(&(identity.len() as u16).to_be_bytes());
+                record.extend_from_slice(identity.as_bytes());
+            }
+            Some(_) => return Err(Error::new(ErrorKind::InvalidInput, "identity too long")),
+            None => record.extend_from_slice(&NO_IDENTITY.to_be_bytes()),
+        }
+        record.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+        record.extend_from_slice(frame);
+        self.file.lock().unwrap().write_all(&record)
+    }
+
+    /// Records that `connection` closed.
+    pub fn closed(&self, connection: Uuid) -> std::io::Result<()> {
+        self.file.lock().unwrap().write_all(&header(CLOSED, connection))
+    }
+}
+
+// Record type, current time and connection, which every record starts with
+fn header(record_type: u8, connection: Uuid) -> Vec<u8> {
+    let micros = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_micros() as u64;
+    let mut record = vec![record_type];
+    record.extend_from_slice(&micros.to_be_bytes());
+    record.extend_from_slice(connection.as_bytes());
+    record
+}
+
+/// One entry of a capture file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaptureRecord {
+    Frame { at: SystemTime, connection: Uuid, identity: Option<String>, frame: Vec<u8> },
+    Closed { at: SystemTime, connection: Uuid },
+}
+
+impl CaptureRecord {
+    pub fn at(&self) -> SystemTime {
+        match self {
+            CaptureRecord::Frame { at, .. } | CaptureRecord::Closed { at, .. } => *at,
+        }
+    }
+}
+
+/// Reads every record of the capture at `path`. A record cut off at the end is ignored.
+pub fn read(path: &Path) -> std::io::Result<Vec<CaptureRecord>> {
+    let bytes = std::fs::read(path)?;
+    if !bytes.starts_with(MAGIC) {
+        return Err(Error::new(ErrorKind::InvalidData, format!("{} is not a capture file", path.display())));
+    }
+    let mut records = Vec::new();
+    let mut offset = MAGIC.len();
+    while let Some((record, length)) = record_at(&bytes[offset..]) {
+        records.push(record);
+        offset += length;
+    }
+    if offset < bytes.len() {
+        log::warn!("Ignoring {} bytes of an incomplete record at the end of {}", bytes.len() - offset, path.display());
+    }
+    Ok(records)
+}
+
+// The complete record at the start of `bytes` and its length
+fn record_at(bytes: &[u8]) -> Option<(CaptureRecord, usize)> {
+    let micros = u64::from_be_bytes(bytes.get(1..9)?.try_into().ok()?);
+    let at = SystemTime::UNIX_EPOCH + Duration::from_micros(micros);
+    let connection = Uuid::from_slice(bytes.get(9..25)?).ok()?;
+    match *bytes.first()? {
+        CLOSED => Some((CaptureRecord::Closed { at, connection }, 25)),
+        FRAME => {
+            let (identity, frame_start) = match u16::from_be_bytes(bytes.get(25..27)?.try_into().ok()?) {
+                NO_IDENTITY => (None, 31),
+                length => {
+                    let end = 27 + length as usize;
+                    (Some(String::from_utf8(bytes.get(27..end)?.to_vec()).ok()?), end + 4)
+                }
+            };
+            let frame_length = u32::from_be_bytes(bytes.get(frame_start - 4..frame_start)?.try_into().ok()?) as usize;
+            let frame = bytes.get(frame_start..frame_start + frame_length)?.to_vec();
+            Some((CaptureRecord::Frame { at, connection, identity, frame }, frame_start + frame_length))
+        }
+        _ => None,
+    }
+}
+
+/// How `replay` spaces out the captured frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pace {
+    /// Keeps the gaps between frames as they were captured
+    RealTime,
+    AsFastAsPossible,
+}
+
+/// Queues the commands of `records` in order, as if they arrived on their original connections.
+///
+/// `Auth` frames are skipped, commands carry the identity recorded with them instead. Every frame
+/// the executor sends back is passed to `responses` together with its connection, and connections
+/// still open at the end of the capture are closed. Returns how many commands were queued.
+pub fn replay(records: &[CaptureRecord], queue: &Mutex<Vec<AdaCommand>>, pace: Pace, responses: &Sender<(Uuid, AdaCommand)>) -> usize {
+    let started = Instant::now();
+    let first = records.first().map(CaptureRecord::at);
+    let mut connections: HashMap<Uuid, Sender<AdaCommand>> = HashMap::new();
+    let mut queued = 0;
+
+    for record in records {
+        if let (Pace::RealTime, Some(first)) = (pace, first) {
+            let due = record.at().duration_since(first).unwrap_or_default();
+            if let Some(wait) = due.checked_sub(started.elapsed()) {
+                thread::sleep(wait);
+            }
+        }
+        match record {
+            CaptureRecord::Frame { connection, identity, frame, .. } => {
+                let Some(mut command) = parse_command(frame) else {
+                    log::warn!("Skipping malformed frame of {} bytes from {}", frame.len(), connection);
+                    continue;
+                };
+                if matches!(command.header.command_type, AdaCommandType::Auth) {
+                    continue;
+                }
+                let reply = connections.entry(*connection).or_insert_with(|| forward(*connection, responses.clone()));
+                command.origin = Some(CommandOrigin::new(reply.clone()).with_identity(identity.clone()).with_connection(*connection));
+                queue.lock().unwrap().push(command);
+                queued += 1;
+            }
+            CaptureRecord::Closed { connection, .. } => {
+                if connections.remove(connection).is_some() {
+                    close(queue, *connection);
+                }
+            }
+        }
+    }
+    for connection in connections.into_keys() {
+        close(queue, connection);
+    }
+    queued
+}
+
+// Nobody is listening any more, so the acknowledgement goes nowhere
+fn close(queue: &Mutex<Vec<AdaCommand>>, connection: Uuid) {
+    let (reply, _) = mpsc::channel();
+    connection_closed(queue, connection, reply);
+}
+
+// A reply channel for `connection` whose frames end up on `responses`
+fn forward(connection: Uuid, responses: Sender<(Uuid, AdaCommand)>) -> Sender<AdaCommand> {
+    let (reply, receiver) = mpsc::channel::<AdaCommand>();
+    thread::spawn(move || {
+        for response in receiver {
+            if responses.send((connection, response)).is_err() {
+                return;
+            }
+        }
+    });
+    reply
+}
@@ -1,58 +1,176 @@
 use std::fmt::{Display, Formatter};
+use std::sync::mpsc::Sender;
 
 use uuid::Uuid;
 
-use crate::network::data_container::Container;
+use crate::network::data_container::{Container, Data};
 
 #[repr(u8)]
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AdaCommandType {
     CloseConnection = 1,
+    Response = 2,
+    Error = 3,
+    PermissionDenied = 4,
+    /// Pushed to subscribers, never a response to a particular command
+    Event = 5,
+    /// Writes a snapshot of every network and the queued commands
+    Snapshot = 6,
+    Auth = 10,
+    AuthChallenge = 11,
+    CreateNetwork = 20,
+    DeleteNetwork = 21,
+    AddNode = 22,
+    RemoveNode = 23,
+    ConnectNodes = 24,
+    DisconnectNodes = 25,
+    ListNodeTypes = 26,
+    ExportGraph = 27,
     ExecuteNode = 30,
     PropagateNode = 31,
-    Unknown,
+    CancelRun = 32,
+    QueryRun = 33,
+    Subscribe = 34,
+    Unsubscribe = 35,
+    Unknown = 255,
 }
 
 
 impl TryFrom<u8> for AdaCommandType {
     type Error = &'static str;
 
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
+    fn try_from(value: u8) -> Result<Self, &'static str> {
         match value {
             1 => Ok(AdaCommandType::CloseConnection),
+            2 => Ok(AdaCommandType::Response),
+            3 => Ok(AdaCommandType::Error),
+            4 => Ok(AdaCommandType::PermissionDenied),
+            5 => Ok(AdaCommandType::Event),
+            6 => Ok(AdaCommandType::Snapshot),
+            10 => Ok(AdaCommandType::Auth),
+            11 => Ok(AdaCommandType::AuthChallenge),
+            20 => Ok(AdaCommandType::CreateNetwork),
+            21 => Ok(AdaCommandType::DeleteNetwork),
+            22 => Ok(AdaCommandType::AddNode),
+            23 => Ok(AdaCommandType::RemoveNode),
+            24 => Ok(AdaCommandType::ConnectNodes),
+            25 => Ok(AdaCommandType::DisconnectNodes),
+            26 => Ok(AdaCommandType::ListNodeTypes),
+            27 => Ok(AdaCommandType::ExportGraph),
             30 => Ok(AdaCommandType::ExecuteNode),
             31 => Ok(AdaCommandType::PropagateNode),
+            32 => Ok(AdaCommandType::CancelRun),
+            33 => Ok(AdaCommandType::QueryRun),
+            34 => Ok(AdaCommandType::Subscribe),
+            35 => Ok(AdaCommandType::Unsubscribe),
             _ => Ok(AdaCommandType::Unknown),
         }
     }
 }
 
 pub const COMMON_HEADER_SIZE: u32 = 1 + 4 + 1 + 16 + 16;
+/// Largest frame, header included, a peer may send. Larger frames are refused before they are buffered.
+pub const MAX_FRAME_SIZE: u32 = 16 * 1024 * 1024;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AdaCommandHeader {
     pub version: u8,
+    // Recomputed by `to_bytes`, so it can be left out when writing commands by hand
+    #[cfg_attr(feature = "serde", serde(default))]
     pub content_length: u32,
     pub command_type: AdaCommandType,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub network: Option<Uuid>,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub node: Option<Uuid>,
 }
 
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AdaCommand {
     pub header: AdaCommandHeader,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub data: Option<Container>,
+    // Set by the transport that received the command, never part of the wire format
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub origin: Option<CommandOrigin>,
 }
 
+/// Connection-side context of a queued command.
+#[derive(Clone)]
+pub struct CommandOrigin {
+    /// Where the scheduler sends the response once the command has been handled
+    pub reply: Sender<AdaCommand>,
+    /// Verified identity of the client, if the transport established one
+    pub identity: Option<String>,
+    /// Set by transports keeping the connection open, which queue a `CloseConnection` with the
+    /// same id once it closed so state tied to the connection can be dropped
+    pub connection: Option<Uuid>,
+    /// Position of the command in the write-ahead log, marked done once the command was handled
+    pub wal_sequence: Option<u64>,
+}
+
+impl CommandOrigin {
+    /// Origin of a command from an unauthenticated client, its responses go to `reply`.
+    pub fn new(reply: Sender<AdaCommand>) -> Self {
+        CommandOrigin {
+            reply,
+            identity: None,
+            connection: None,
+            wal_sequence: None,
+        }
+    }
+
+    pub fn with_identity(mut self, identity: impl Into<Option<String>>) -> Self {
+        self.identity = identity.into();
+        self
+    }
+
+    pub fn with_connection(mut self, connection: Uuid) -> Self {
+        self.connection = Some(connection);
+        self
+    }
+
+    pub fn with_wal_sequence(mut self, wal_sequence: u64) -> Self {
+        self.wal_sequence = Some(wal_sequence);
+        self
+    }
+}
 
 impl AdaCommand {
     pub fn new(command_type: AdaCommandType) -> Self {
         AdaCommand {
             header: { AdaCommandHeader { command_type, content_length: 6, version: 0, node: None, network: None } },
             data: None,
+            origin: None,
+        }
+    }
+
+    /// Builds a command answering `self`, addressed to the same network and node.
+    pub fn reply(&self, command_type: AdaCommandType, data: Option<Container>) -> AdaCommand {
+        AdaCommand {
+            header: AdaCommandHeader {
+                version: self.header.version,
+                content_length: 0,
+                command_type,
+                network: self.header.network,
+                node: self.header.node,
+            },
+            data,
+            origin: None,
         }
     }
 
+    /// Builds an `Error` reply to `self` carrying `message` under the `error` key.
+    pub fn error(&self, message: &str) -> AdaCommand {
+        let mut data = Container::new();
+        data.add_item("error", Data::Text(message.to_string()));
+        self.reply(AdaCommandType::Error, Some(data))
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::new();
         let header = self.header.clone();
@@ -62,26 +180,26 @@ impl AdaCommand {
         bytes.push(header.command_type as u8);
 
         let network_id_bytes = match header.network {
-            Some(uuid) => uuid.as_bytes().clone(),
-            None => Uuid::nil().as_bytes().clone()
+            Some(uuid) => *uuid.as_bytes(),
+            None => *Uuid::nil().as_bytes()
         };
         bytes.extend_from_slice(&network_id_bytes);
 
         let node_id_bytes = match header.node {
-            Some(uuid) => uuid.as_bytes().clone(),
-            None => Uuid::nil().as_bytes().clone()
+            Some(uuid) => *uuid.as_bytes(),
+            None => *Uuid::nil().as_bytes()
         };
         bytes.extend_from_slice(&node_id_bytes);
 
         if let Some(data) = &self.data {
-            bytes.extend_from_slice(&*data.get_bytes());
+            bytes.extend_from_slice(&data.get_bytes());
         }
 
         bytes
     }
 
     fn get_total_bytes(&self) -> u32 {
-        let mut size = COMMON_HEADER_SIZE;
+        let size = COMMON_HEADER_SIZE;
         let mut data_size = 0; // Initialize data_size to 0
 
         if let Some(data) = &self.data {
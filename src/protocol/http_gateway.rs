@@ -0,0 +1,363 @@
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::network::data_container::{Container, Data};
+use crate::protocol::auth::KeyStore;
+use crate::protocol::capture::Capture;
+use crate::protocol::commands::{AdaCommand, AdaCommandType, CommandOrigin};
+use crate::protocol::transport::{self, ConnectionContext};
+use crate::protocol::wal::WriteAheadLog;
+
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(30);
+// Longest request or header line in bytes, and most header lines in a request
+const MAX_LINE_LENGTH: usize = 8 * 1024;
+const MAX_HEADER_COUNT: usize = 100;
+
+/// JSON-over-HTTP front end that feeds the same command queue as `TcpServer`.
+///
+/// Routes:
+/// - `POST /commands` with a serialized `AdaCommand`
+/// - `POST /networks` (optional body `{"network": "<uuid>"}`)
+/// - `DELETE /networks/{network}`
+/// - `GET /node-types`
+/// - `GET /networks/{network}/dot`, the DOT source coloured by last execution status
+/// - `POST /networks/{network}/nodes[/{node}]` with a `Container` holding the node `type` and its config
+/// - `DELETE /networks/{network}/nodes/{node}`
+/// - `POST` or `DELETE /networks/{network}/edges` with `{"from", "from_port", "to", "to_port"}`
+/// - `POST /networks/{network}/nodes/{node}/execute` with a `Container` body
+/// - `POST /networks/{network}/nodes/{node}/propagate` with a `Container` body
+/// - `GET /networks/{network}/runs/{run}`, the run's result and trace
+/// - `POST /networks/{network}/runs/{run}/cancel`
+///
+/// Every route answers with the serialized response command.
+///
+/// With a key store configured, requests authenticate with `Authorization: Bearer <identity>:<token>`.
+///
+/// Request and header lines are limited to 8 KiB and 100 headers, answered with 400 or 431 beyond
+/// that. A client sending nothing for the read timeout, 30 seconds by default, gets a 408.
+pub struct HttpGateway {
+    address: String,
+    context: ConnectionContext,
+    read_timeout: Duration,
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    authorization: Option<String>,
+    body: Vec<u8>,
+}
+
+struct HttpResponse {
+    status: u16,
+    body: String,
+}
+
+impl HttpResponse {
+    fn error(status: u16, message: &str) -> Self {
+        HttpResponse {
+            status,
+            body: serde_json::json!({ "error": message }).to_string(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Default)]
+struct CreateNetworkRequest {
+    network: Option<Uuid>,
+}
+
+#[derive(serde::Deserialize)]
+struct EdgeRequest {
+    from: Uuid,
+    from_port: String,
+    to: Uuid,
+    to_port: String,
+}
+
+impl HttpGateway {
+    pub fn new(address: &str, queue: Arc<Mutex<Vec<AdaCommand>>>) -> Self {
+        Self {
+            address: address.to_string(),
+            context: ConnectionContext::new(queue),
+            read_timeout: DEFAULT_READ_TIMEOUT,
+        }
+    }
+
+    /// Gives up on a request once its client sent nothing for `read_timeout`.
+    pub fn with_read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = read_timeout;
+        self
+    }
+
+    pub fn with_key_store(mut self, key_store: Arc<KeyStore>) -> Self {
+        self.context.key_store = Some(key_store);
+        self
+    }
+
+    /// Logs every command to `wal` before it is queued.
+    pub fn with_wal(mut self, wal: Arc<WriteAheadLog>) -> Self {
+        self.context.wal = Some(wal);
+        self
+    }
+
+    /// Records every command accepted to `capture`, each request as a connection of its own.
+    pub fn with_capture(mut self, capture: Arc<Capture>) -> Self {
+        self.context.capture = Some(capture);
+        self
+    }
+
+    pub fn run(&self) -> std::io::Result<()> {
+        let listener = TcpListener::bind(&self.address)?;
+        log::info!("HTTP gateway listening on {}", self.address);
+        self.serve(listener)
+    }
+
+    /// Answers requests arriving on `listener`, which may be bound to another address than configured.
+    pub fn serve(&self, listener: TcpListener) -> std::io::Result<()> {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let context_clone = self.context.clone();
+                    let read_timeout = self.read_timeout;
+                    thread::spawn(move || {
+                        HttpGateway::handle_client(context_clone, stream, read_timeout)
+                    });
+                }
+                Err(e) => {
+                    log::error!("Error: {}", e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_client(context: ConnectionContext, mut stream: TcpStream, read_timeout: Duration) {
+        if let Err(e) = stream.set_read_timeout(Some(read_timeout)) {
+            log::error!("Failed to set HTTP read timeout: {}", e);
+            return;
+        }
+        let response = match HttpGateway::read_request(&mut stream) {
+            Ok(request) => {
+                log::debug!("{} {}", request.method, request.path);
+                match HttpGateway::authenticate(context.key_store.as_deref(), &request) {
+                    Ok(identity) => HttpGateway::route(&context, identity, request),
+                    Err(response) => response,
+                }
+            }
+            Err(response) => response,
+        };
+
+        let reason = match response.status {
+            200 => "OK",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            408 => "Request Timeout",
+            431 => "Request Header Fields Too Large",
+            504 => "Gateway Timeout",
+            _ => "Internal Server Error",
+        };
+        let message = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            response.status, reason, response.body.len(), response.body
+        );
+        if let Err(e) = stream.write_all(message.as_bytes()).and_then(|_| stream.flush()) {
+            log::error!("Failed to write HTTP response: {}", e);
+        }
+    }
+
+    fn read_request(stream: &mut TcpStream) -> Result<HttpRequest, HttpResponse> {
+        let mut reader = BufReader::new(stream);
+
+        let request_line = HttpGateway::read_line(&mut reader, 400)?;
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().ok_or_else(|| HttpResponse::error(400, "missing method"))?.to_string();
+        let path = parts.next().ok_or_else(|| HttpResponse::error(400, "missing path"))?.to_string();
+
+        let mut content_length = 0;
+        let mut authorization = None;
+        let mut header_count = 0;
+        loop {
+            let line = HttpGateway::read_line(&mut reader, 431)?;
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            header_count += 1;
+            if header_count > MAX_HEADER_COUNT {
+                return Err(HttpResponse::error(431, "too many headers"));
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().map_err(|_| HttpResponse::error(400, "invalid content length"))?;
+                } else if name.trim().eq_ignore_ascii_case("authorization") {
+                    authorization = Some(value.trim().to_string());
+                }
+            }
+        }
+        if content_length > MAX_BODY_SIZE {
+            return Err(HttpResponse::error(400, "request body too large"));
+        }
+
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).map_err(HttpGateway::read_error)?;
+
+        Ok(HttpRequest { method, path, authorization, body })
+    }
+
+    // One line of at most `MAX_LINE_LENGTH` bytes, a longer one is answered with `status`
+    fn read_line(reader: &mut impl BufRead, status: u16) -> Result<String, HttpResponse> {
+        let mut line = Vec::new();
+        reader.take(MAX_LINE_LENGTH as u64 + 1).read_until(b'\n', &mut line).map_err(HttpGateway::read_error)?;
+        if line.len() > MAX_LINE_LENGTH {
+            return Err(HttpResponse::error(status, "line too long"));
+        }
+        String::from_utf8(line).map_err(|e| HttpResponse::error(400, &e.to_string()))
+    }
+
+    fn read_error(e: std::io::Error) -> HttpResponse {
+        match e.kind() {
+            ErrorKind::WouldBlock | ErrorKind::TimedOut => HttpResponse::error(408, "timed out reading the request"),
+            _ => HttpResponse::error(400, &e.to_string()),
+        }
+    }
+
+    // Without a key store every request is accepted anonymously
+    fn authenticate(key_store: Option<&KeyStore>, request: &HttpRequest) -> Result<Option<String>, HttpResponse> {
+        let key_store = match key_store {
+            Some(key_store) => key_store,
+            None => return Ok(None),
+        };
+        let credentials = request.authorization.as_deref()
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|value| value.split_once(':'));
+        match credentials {
+            Some((identity, token)) if key_store.verify_token(identity, token) => Ok(Some(identity.to_string())),
+            _ => Err(HttpResponse::error(401, "authentication failed")),
+        }
+    }
+
+    fn route(context: &ConnectionContext, identity: Option<String>, request: HttpRequest) -> HttpResponse {
+        let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+
+        let command = match (request.method.as_str(), segments.as_slice()) {
+            ("POST", ["commands"]) => serde_json::from_slice::<AdaCommand>(&request.body)
+                .map_err(|e| e.to_string()),
+            ("POST", ["networks"]) => HttpGateway::parse_body::<CreateNetworkRequest>(&request.body)
+                .map(|body| HttpGateway::command(AdaCommandType::CreateNetwork, body.network, None, None)),
+            ("DELETE", ["networks", network]) => HttpGateway::parse_uuid(network)
+                .map(|network| HttpGateway::command(AdaCommandType::DeleteNetwork, Some(network), None, None)),
+            ("GET", ["node-types"]) => Ok(HttpGateway::command(AdaCommandType::ListNodeTypes, None, None, None)),
+            ("GET", ["networks", network, "dot"]) => HttpGateway::parse_uuid(network).map(|network| {
+                let mut data = Container::new();
+                data.add_item("status", Data::Boolean(true));
+                HttpGateway::command(AdaCommandType::ExportGraph, Some(network), None, Some(data))
+            }),
+            ("POST", ["networks", network, "nodes"]) => HttpGateway::parse_uuid(network).and_then(|network| {
+                let data = HttpGateway::parse_body::<Container>(&request.body)?;
+                Ok(HttpGateway::command(AdaCommandType::AddNode, Some(network), None, Some(data)))
+            }),
+            ("POST", ["networks", network, "nodes", node]) => HttpGateway::parse_uuid(network).and_then(|network| {
+                let node = HttpGateway::parse_uuid(node)?;
+                let data = HttpGateway::parse_body::<Container>(&request.body)?;
+                Ok(HttpGateway::command(AdaCommandType::AddNode, Some(network), Some(node), Some(data)))
+            }),
+            ("DELETE", ["networks", network, "nodes", node]) => HttpGateway::parse_uuid(network).and_then(|network| {
+                let node = HttpGateway::parse_uuid(node)?;
+                Ok(HttpGateway::command(AdaCommandType::RemoveNode, Some(network), Some(node), None))
+            }),
+            (method @ ("POST" | "DELETE"), ["networks", network, "edges"]) => HttpGateway::parse_uuid(network).and_then(|network| {
+                let edge: EdgeRequest = serde_json::from_slice(&request.body).map_err(|e| e.to_string())?;
+                let command_type = if method == "POST" { AdaCommandType::ConnectNodes } else { AdaCommandType::DisconnectNodes };
+                let mut data = Container::new();
+                data.add_item("from_port", Data::Text(edge.from_port));
+                data.add_item("to", Data::Text(edge.to.to_string()));
+                data.add_item("to_port", Data::Text(edge.to_port));
+                Ok(HttpGateway::command(command_type, Some(network), Some(edge.from), Some(data)))
+            }),
+            ("GET", ["networks", network, "runs", run]) => HttpGateway::parse_uuid(network).and_then(|network| {
+                let mut data = Container::new();
+                data.add_item("run", Data::Text(HttpGateway::parse_uuid(run)?.to_string()));
+                Ok(HttpGateway::command(AdaCommandType::QueryRun, Some(network), None, Some(data)))
+            }),
+            ("POST", ["networks", network, "runs", run, "cancel"]) => HttpGateway::parse_uuid(network).and_then(|network| {
+                let mut data = Container::new();
+                data.add_item("run", Data::Text(HttpGateway::parse_uuid(run)?.to_string()));
+                Ok(HttpGateway::command(AdaCommandType::CancelRun, Some(network), None, Some(data)))
+            }),
+            ("POST", ["networks", network, "nodes", node, action]) => {
+                let command_type = match *action {
+                    "execute" => AdaCommandType::ExecuteNode,
+                    "propagate" => AdaCommandType::PropagateNode,
+                    _ => return HttpResponse::error(404, "unknown route"),
+                };
+                HttpGateway::parse_uuid(network).and_then(|network| {
+                    let node = HttpGateway::parse_uuid(node)?;
+                    let data = HttpGateway::parse_body::<Container>(&request.body)?;
+                    Ok(HttpGateway::command(command_type, Some(network), Some(node), Some(data)))
+                })
+            }
+            _ => return HttpResponse::error(404, "unknown route"),
+        };
+
+        match command {
+            Ok(command) => HttpGateway::submit(context, identity, command),
+            Err(e) => HttpResponse::error(400, &e),
+        }
+    }
+
+    fn submit(context: &ConnectionContext, identity: Option<String>, command: AdaCommand) -> HttpResponse {
+        let (sender, receiver) = mpsc::channel();
+        let origin = CommandOrigin::new(sender).with_identity(identity);
+        let response = match transport::accept_command(context, command, origin) {
+            Some(reply) => Ok(reply),
+            None => receiver.recv_timeout(RESPONSE_TIMEOUT),
+        };
+
+        match response {
+            Ok(response) => {
+                let status = match response.header.command_type {
+                    AdaCommandType::Error => 400,
+                    AdaCommandType::PermissionDenied => 403,
+                    _ => 200,
+                };
+                match serde_json::to_string(&response) {
+                    Ok(body) => HttpResponse { status, body },
+                    Err(e) => HttpResponse::error(500, &e.to_string()),
+                }
+            }
+            Err(_) => HttpResponse::error(504, "no response from scheduler"),
+        }
+    }
+
+    fn command(command_type: AdaCommandType, network: Option<Uuid>, node: Option<Uuid>, data: Option<Container>) -> AdaCommand {
+        let mut command = AdaCommand::new(command_type);
+        command.header.network = network;
+        command.header.node = node;
+        command.data = data;
+        command
+    }
+
+    // An empty body is treated like `{}`
+    fn parse_body<T: serde::de::DeserializeOwned + Default>(body: &[u8]) -> Result<T, String> {
+        if body.iter().all(u8::is_ascii_whitespace) {
+            return Ok(T::default());
+        }
+        serde_json::from_slice(body).map_err(|e| e.to_string())
+    }
+
+    fn parse_uuid(value: &str) -> Result<Uuid, String> {
+        Uuid::parse_str(value).map_err(|e| format!("invalid uuid {}: {}", value, e))
+    }
+}
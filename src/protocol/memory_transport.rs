@@ -0,0 +1,143 @@
+use std::io::{ErrorKind, Read, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::protocol::transport::{Connection, Transport};
+
+static NEXT_PIPE: AtomicUsize = AtomicUsize::new(0);
+
+/// One end of an in-memory, socket-free connection created by `pipe`.
+pub struct MemoryConnection {
+    name: String,
+    outgoing: Sender<Vec<u8>>,
+    incoming: Receiver<Vec<u8>>,
+    // Part of a received chunk that did not fit into the caller's buffer
+    leftover: Vec<u8>,
+    read_timeout: Mutex<Option<Duration>>,
+    closed: Arc<AtomicBool>,
+}
+
+/// Creates two connected `MemoryConnection`s, bytes written to one can be read from the other.
+pub fn pipe() -> (MemoryConnection, MemoryConnection) {
+    let id = NEXT_PIPE.fetch_add(1, Ordering::Relaxed);
+    let (a_sender, b_receiver) = mpsc::channel();
+    let (b_sender, a_receiver) = mpsc::channel();
+    let closed = Arc::new(AtomicBool::new(false));
+
+    let a = MemoryConnection {
+        name: format!("memory:{}a", id),
+        outgoing: a_sender,
+        incoming: a_receiver,
+        leftover: Vec::new(),
+        read_timeout: Mutex::new(None),
+        closed: Arc::clone(&closed),
+    };
+    let b = MemoryConnection {
+        name: format!("memory:{}b", id),
+        outgoing: b_sender,
+        incoming: b_receiver,
+        leftover: Vec::new(),
+        read_timeout: Mutex::new(None),
+        closed,
+    };
+    (a, b)
+}
+
+impl Read for MemoryConnection {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.leftover.is_empty() {
+            if self.closed.load(Ordering::SeqCst) {
+                return Ok(0);
+            }
+            let timeout = *self.read_timeout.lock().unwrap();
+            let received = match timeout {
+                Some(timeout) => self.incoming.recv_timeout(timeout).map_err(|e| match e {
+                    RecvTimeoutError::Timeout => ErrorKind::WouldBlock,
+                    RecvTimeoutError::Disconnected => ErrorKind::UnexpectedEof,
+                }),
+                None => self.incoming.recv().map_err(|_| ErrorKind::UnexpectedEof),
+            };
+            match received {
+                Ok(bytes) => self.leftover = bytes,
+                // The other end is gone, report end of stream like a socket would
+                Err(ErrorKind::UnexpectedEof) => return Ok(0),
+                Err(kind) => return Err(kind.into()),
+            }
+        }
+
+        let size = buf.len().min(self.leftover.len());
+        buf[..size].copy_from_slice(&self.leftover[..size]);
+        self.leftover.drain(..size);
+        Ok(size)
+    }
+}
+
+impl Write for MemoryConnection {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(ErrorKind::BrokenPipe.into());
+        }
+        self.outgoing.send(buf.to_vec()).map_err(|_| ErrorKind::BrokenPipe)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Connection for MemoryConnection {
+    fn peer_identity(&self) -> String {
+        self.name.clone()
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        *self.read_timeout.lock().unwrap() = timeout;
+        Ok(())
+    }
+
+    fn shutdown(&self) -> std::io::Result<()> {
+        self.closed.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// In-memory listener, `connect` hands the server end of a new pipe to `accept`.
+pub struct MemoryTransport {
+    connector: Sender<MemoryConnection>,
+    pending: Mutex<Receiver<MemoryConnection>>,
+}
+
+impl Default for MemoryTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryTransport {
+    pub fn new() -> Self {
+        let (connector, pending) = mpsc::channel();
+        MemoryTransport {
+            connector,
+            pending: Mutex::new(pending),
+        }
+    }
+
+    /// Opens a connection and returns the client end.
+    pub fn connect(&self) -> MemoryConnection {
+        let (client, server) = pipe();
+        // The receiver lives as long as `self`, so this cannot fail
+        let _ = self.connector.send(server);
+        client
+    }
+}
+
+impl Transport for MemoryTransport {
+    type Connection = MemoryConnection;
+
+    fn accept(&self) -> std::io::Result<MemoryConnection> {
+        self.pending.lock().unwrap().recv().map_err(|_| ErrorKind::NotConnected.into())
+    }
+}
@@ -1,3 +1,16 @@
 pub mod protocol_parser;
 pub mod tcp_server;
-pub mod commands;
\ No newline at end of file
+pub mod commands;
+pub mod auth;
+pub mod capture;
+pub mod transport;
+pub mod memory_transport;
+pub mod wal;
+#[cfg(feature = "http")]
+pub mod http_gateway;
+#[cfg(feature = "websocket")]
+pub mod websocket_server;
+#[cfg(unix)]
+pub mod unix_server;
+#[cfg(feature = "tls")]
+pub mod tls;
\ No newline at end of file
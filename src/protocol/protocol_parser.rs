@@ -1,7 +1,7 @@
-use std::fmt::{Display, Formatter};
+use std::io::Read;
 use uuid::Uuid;
 use crate::network::data_container::{Container, Data};
-use crate::protocol::commands::{AdaCommandHeader, AdaCommandType, COMMON_HEADER_SIZE};
+use crate::protocol::commands::{AdaCommand, AdaCommandHeader, AdaCommandType, COMMON_HEADER_SIZE, MAX_FRAME_SIZE};
 
 
 pub fn parse_header(buffer: &[u8]) -> Option<AdaCommandHeader> {
@@ -28,12 +28,54 @@ pub fn parse_header(buffer: &[u8]) -> Option<AdaCommandHeader> {
         version,
         content_length,
         command_type,
-        node: Some(network_id),
-        network: Some(node_id),
+        network: Some(network_id),
+        node: Some(node_id),
     })
 }
 
+/// Parses a complete frame (header followed by its content) as produced by `AdaCommand::to_bytes`.
+pub fn parse_command(buffer: &[u8]) -> Option<AdaCommand> {
+    if buffer.len() < COMMON_HEADER_SIZE as usize {
+        return None;
+    }
+    let header = parse_header(&buffer[..COMMON_HEADER_SIZE as usize])?;
+    if header.content_length as usize != buffer.len() {
+        log::error!("Frame length {} does not match header length {}", buffer.len(), header.content_length);
+        return None;
+    }
+
+    let data = if header.content_length > COMMON_HEADER_SIZE {
+        parse_data_container(buffer[COMMON_HEADER_SIZE as usize..].to_vec(), 0)
+    } else {
+        None
+    };
+
+    Some(AdaCommand {
+        header,
+        data,
+        origin: None,
+    })
+}
+
+/// Blocks until one complete frame has been read from `reader`.
+pub fn read_command<R: Read>(reader: &mut R) -> std::io::Result<AdaCommand> {
+    let mut frame = vec![0; COMMON_HEADER_SIZE as usize];
+    reader.read_exact(&mut frame)?;
+    let content_length = u32::from_be_bytes([frame[1], frame[2], frame[3], frame[4]]);
+    if content_length > MAX_FRAME_SIZE {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("frame of {} bytes exceeds the maximum size", content_length)));
+    }
+    if content_length > COMMON_HEADER_SIZE {
+        frame.resize(content_length as usize, 0);
+        reader.read_exact(&mut frame[COMMON_HEADER_SIZE as usize..])?;
+    }
+    parse_command(&frame).ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "malformed frame"))
+}
+
 pub fn parse_data_container(content_buffer: Vec<u8>, data_offset: usize) -> Option<Container> {
+    if data_offset + 4 > content_buffer.len() {
+        return None;
+    }
     let data_size: usize = u32::from_be_bytes([
         content_buffer[data_offset],
         content_buffer[data_offset + 1],
@@ -53,6 +95,9 @@ pub fn parse_data_container(content_buffer: Vec<u8>, data_offset: usize) -> Opti
     let mut offset = data_offset + 4;
     while offset < data_offset + data_size {
         // Read key length
+        if offset + 2 > content_buffer.len() {
+            return None;
+        }
         let key_len = u16::from_be_bytes([content_buffer[offset], content_buffer[offset + 1]]);
         offset += 2;
 
@@ -99,18 +144,13 @@ pub fn parse_data_container(content_buffer: Vec<u8>, data_offset: usize) -> Opti
                 Some(Data::Float(value))
             }
             Data::Text(_) => {
-                let slice = &content_buffer[offset..];
-                let text = match slice.iter().position(|&b| b == 0) {
-                    Some(null_terminator) => Some(String::from_utf8_lossy(&slice[..null_terminator]).to_string()),
-                    None => Some(String::from_utf8_lossy(slice).to_string()),
-                };
-                match text {
-                    Some(val) => {
-                        offset += val.len() + 1;
-                        Some(Data::Text(val.to_string()))
-                    }
-                    None => None
-                }
+                // The terminator is searched within the data field only, and the offset advances by the
+                // bytes read since invalid UTF-8 changes the length of the decoded text
+                let end = (data_offset + data_size).min(content_buffer.len());
+                let slice = content_buffer.get(offset..end).unwrap_or_default();
+                let text_len = slice.iter().position(|&b| b == 0).unwrap_or(slice.len());
+                offset += text_len + 1;
+                Some(Data::Text(String::from_utf8_lossy(&slice[..text_len]).to_string()))
             }
             Data::Boolean(_) => {
                 // Boolean (already handled by TryFrom)
@@ -121,10 +161,6 @@ pub fn parse_data_container(content_buffer: Vec<u8>, data_offset: usize) -> Opti
                 offset += 1;
                 Some(Data::Boolean(value))
             }
-            _ => {
-                log::warn!("Unknown data type encountered, skipping key-value pair");
-                None
-            }
         };
 
         log::debug!("Read data field {} with value {:?}", key, data);
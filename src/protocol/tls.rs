@@ -0,0 +1,169 @@
+use std::fs::File;
+use std::io::{BufReader, Error, ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{ClientConfig, ClientConnection, RootCertStore, ServerConfig, ServerConnection, StreamOwned};
+
+use crate::protocol::auth::to_hex;
+use crate::protocol::transport::{Connection, Transport};
+
+/// Server side TLS, built from PEM files.
+///
+/// When `client_ca_path` is given every client has to present a certificate signed by it (mutual
+/// TLS) and is identified by that certificate, see `certificate_identity`.
+pub fn server_config(cert_path: &Path, key_path: &Path, client_ca_path: Option<&Path>) -> std::io::Result<Arc<ServerConfig>> {
+    let provider = crypto_provider();
+    let builder = ServerConfig::builder_with_provider(Arc::clone(&provider))
+        .with_safe_default_protocol_versions()
+        .map_err(tls_error)?;
+
+    let builder = match client_ca_path {
+        Some(client_ca_path) => {
+            let roots = Arc::new(load_roots(client_ca_path)?);
+            let verifier = WebPkiClientVerifier::builder_with_provider(roots, provider)
+                .build()
+                .map_err(tls_error)?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let config = builder
+        .with_single_cert(load_certs(cert_path)?, load_key(key_path)?)
+        .map_err(tls_error)?;
+    Ok(Arc::new(config))
+}
+
+/// Client side TLS trusting the certificates in `ca_path`, optionally presenting a client certificate.
+pub fn client_config(ca_path: &Path, client_identity: Option<(&Path, &Path)>) -> std::io::Result<Arc<ClientConfig>> {
+    let builder = ClientConfig::builder_with_provider(crypto_provider())
+        .with_safe_default_protocol_versions()
+        .map_err(tls_error)?
+        .with_root_certificates(load_roots(ca_path)?);
+
+    let config = match client_identity {
+        Some((cert_path, key_path)) => builder
+            .with_client_auth_cert(load_certs(cert_path)?, load_key(key_path)?)
+            .map_err(tls_error)?,
+        None => builder.with_no_client_auth(),
+    };
+    Ok(Arc::new(config))
+}
+
+/// Wraps an established TCP connection in a client TLS session for `server_name`.
+pub fn connect(stream: TcpStream, server_name: &str, config: Arc<ClientConfig>) -> std::io::Result<StreamOwned<ClientConnection, TcpStream>> {
+    let server_name = ServerName::try_from(server_name.to_string())
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+    let connection = ClientConnection::new(config, server_name).map_err(tls_error)?;
+    Ok(StreamOwned::new(connection, stream))
+}
+
+/// Identity of a peer certificate, the hex encoded SHA-256 fingerprint of its DER encoding.
+pub fn certificate_identity(certificate: &CertificateDer) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, certificate.as_ref());
+    format!("sha256:{}", to_hex(digest.as_ref()))
+}
+
+/// Transport accepting connections from `inner` and running the server side of TLS on them.
+///
+/// The handshake happens on the connection's own thread during its first reads.
+pub struct TlsTransport<T: Transport> {
+    inner: T,
+    config: Arc<ServerConfig>,
+}
+
+impl<T: Transport> TlsTransport<T> {
+    pub fn new(inner: T, config: Arc<ServerConfig>) -> Self {
+        TlsTransport { inner, config }
+    }
+}
+
+impl<T: Transport> Transport for TlsTransport<T> {
+    type Connection = TlsConnection<T::Connection>;
+
+    fn accept(&self) -> std::io::Result<Self::Connection> {
+        let stream = self.inner.accept()?;
+        let session = ServerConnection::new(Arc::clone(&self.config)).map_err(tls_error)?;
+        Ok(TlsConnection {
+            stream: StreamOwned::new(session, stream),
+        })
+    }
+}
+
+pub struct TlsConnection<C: Connection> {
+    stream: StreamOwned<ServerConnection, C>,
+}
+
+impl<C: Connection> Read for TlsConnection<C> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.stream.read(buf)
+    }
+}
+
+impl<C: Connection> Write for TlsConnection<C> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl<C: Connection> Connection for TlsConnection<C> {
+    fn peer_identity(&self) -> String {
+        format!("tls:{}", self.stream.get_ref().peer_identity())
+    }
+
+    fn authenticated_identity(&self) -> Option<String> {
+        self.stream.conn.peer_certificates()
+            .and_then(|certificates| certificates.first())
+            .map(certificate_identity)
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        self.stream.get_ref().set_read_timeout(timeout)
+    }
+
+    fn shutdown(&self) -> std::io::Result<()> {
+        self.stream.get_ref().shutdown()
+    }
+}
+
+fn load_certs(path: &Path) -> std::io::Result<Vec<CertificateDer<'static>>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let certs = rustls_pemfile::certs(&mut reader).collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidData, format!("no certificate found in {}", path.display())));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &Path) -> std::io::Result<PrivateKeyDer<'static>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    rustls_pemfile::private_key(&mut reader)?
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("no private key found in {}", path.display())))
+}
+
+fn load_roots(path: &Path) -> std::io::Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(path)? {
+        roots.add(cert).map_err(tls_error)?;
+    }
+    Ok(roots)
+}
+
+fn tls_error<E: std::error::Error + Send + Sync + 'static>(error: E) -> Error {
+    Error::new(ErrorKind::InvalidData, error)
+}
+
+// Server and client share one provider so both ends offer the same cipher suites
+fn crypto_provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
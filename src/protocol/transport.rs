@@ -0,0 +1,366 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::protocol::auth::{AuthOutcome, Authenticator, KeyStore};
+use crate::protocol::capture::Capture;
+use crate::protocol::commands::{AdaCommand, AdaCommandType, CommandOrigin, COMMON_HEADER_SIZE, MAX_FRAME_SIZE};
+use crate::protocol::protocol_parser::{parse_command, parse_header};
+use crate::protocol::wal::WriteAheadLog;
+
+// How long a read may block before queued outgoing frames are flushed
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+const READ_CHUNK_SIZE: usize = 8 * 1024;
+
+/// A bidirectional byte stream carrying `AdaCommand` frames.
+pub trait Connection: Read + Write + Send {
+    /// Human readable identity of the remote end, used for logging.
+    fn peer_identity(&self) -> String;
+
+    /// Identity the transport itself has verified, e.g. a TLS client certificate.
+    fn authenticated_identity(&self) -> Option<String> {
+        None
+    }
+
+    /// Bounds how long `read` blocks, a timed out read fails with `WouldBlock` or `TimedOut`.
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()>;
+
+    fn shutdown(&self) -> std::io::Result<()>;
+}
+
+/// Server-wide state every connection handler needs.
+#[derive(Clone)]
+pub struct ConnectionContext {
+    pub queue: Arc<Mutex<Vec<AdaCommand>>>,
+    /// When set, a connection has to authenticate before any of its commands is queued
+    pub key_store: Option<Arc<KeyStore>>,
+    /// When set, every command is logged before it is queued
+    pub wal: Option<Arc<WriteAheadLog>>,
+    /// When set, every command accepted is recorded
+    pub capture: Option<Arc<Capture>>,
+}
+
+impl ConnectionContext {
+    pub fn new(queue: Arc<Mutex<Vec<AdaCommand>>>) -> Self {
+        ConnectionContext {
+            queue,
+            key_store: None,
+            wal: None,
+            capture: None,
+        }
+    }
+}
+
+/// A listener handing out connections.
+pub trait Transport {
+    type Connection: Connection + 'static;
+
+    fn accept(&self) -> std::io::Result<Self::Connection>;
+}
+
+/// Accepts connections from `transport` forever, each one is served on its own thread.
+pub fn serve<T: Transport>(transport: &T, context: ConnectionContext) {
+    loop {
+        match transport.accept() {
+            Ok(connection) => {
+                log::debug!("New connection: {}", connection.peer_identity());
+                let context_clone = context.clone();
+                thread::spawn(move || {
+                    handle_connection(context_clone, connection)
+                });
+            }
+            Err(e) => {
+                log::error!("Error: {}", e);
+            }
+        }
+    }
+}
+
+/// Reads frames from `connection` into the queue and writes every response back on the same connection.
+///
+/// With a key store configured, frames are only queued once the connection authenticated, either
+/// through the `Auth` handshake or through an identity the transport verified itself.
+pub fn handle_connection<C: Connection>(context: ConnectionContext, mut connection: C) {
+    let peer = connection.peer_identity();
+    if let Err(e) = connection.set_read_timeout(Some(POLL_INTERVAL)) {
+        log::error!("Unable to configure connection with {}: {}", peer, e);
+        return;
+    }
+    let mut session = Session::new(&context, peer);
+    exchange(&mut session, &mut connection);
+    session.close();
+}
+
+/// Queues the `CloseConnection` telling the dispatcher that connection `id` is gone.
+pub fn connection_closed(queue: &Mutex<Vec<AdaCommand>>, id: Uuid, reply: Sender<AdaCommand>) {
+    let mut command = AdaCommand::new(AdaCommandType::CloseConnection);
+    command.origin = Some(CommandOrigin::new(reply).with_connection(id));
+    queue.lock().unwrap().push(command);
+}
+
+/// Logs `command` to the context's write-ahead log and capture, if any, then queues it with `origin`.
+///
+/// Every front end accepts commands through here, once they are parsed and authenticated, so
+/// captures only hold commands the executor went on to dispatch. A command that could not be
+/// logged is not queued, the error reply for its sender is returned instead.
+pub fn accept_command(context: &ConnectionContext, mut command: AdaCommand, mut origin: CommandOrigin) -> Option<AdaCommand> {
+    if let Some(wal) = &context.wal {
+        match wal.append(&command, origin.identity.as_deref()) {
+            Ok(sequence) => origin.wal_sequence = Some(sequence),
+            Err(e) => {
+                log::error!("Unable to log {}: {}", command, e);
+                return Some(command.error("unable to log command"));
+            }
+        }
+    }
+    if let Some(capture) = &context.capture {
+        capture_command(capture, &command, &origin);
+    }
+    command.origin = Some(origin);
+    let mut collection = context.queue.lock().unwrap();
+    log::debug!("Adding {} to queue", command);
+    collection.push(command);
+    None
+}
+
+// Commands without a connection, e.g. from the HTTP gateway, are captured as a connection of their own
+fn capture_command(capture: &Capture, command: &AdaCommand, origin: &CommandOrigin) {
+    let connection = origin.connection.unwrap_or_else(Uuid::new_v4);
+    let mut result = capture.frame(connection, origin.identity.as_deref(), &command.to_bytes());
+    if origin.connection.is_none() {
+        result = result.and_then(|_| capture.closed(connection));
+    }
+    if let Err(e) = result {
+        log::warn!("Unable to capture {}: {}", command, e);
+    }
+}
+
+/// What a transport has to do after passing a received frame to its `Session`.
+pub enum FrameOutcome {
+    /// The frame was queued or dropped, nothing to send
+    Handled,
+    /// Send the reply and keep reading
+    Reply(AdaCommand),
+    /// Send the reply and close the connection
+    Close(AdaCommand),
+}
+
+/// Per-connection state every transport shares, from authentication to queueing of received frames.
+///
+/// Transports only move frames: each complete frame goes through `receive`, and everything
+/// `outgoing` yields is written back to the peer.
+pub struct Session<'a> {
+    context: &'a ConnectionContext,
+    id: Uuid,
+    peer: String,
+    sender: Sender<AdaCommand>,
+    receiver: Receiver<AdaCommand>,
+    authenticator: Option<Authenticator<'a>>,
+    identity: Option<String>,
+}
+
+impl<'a> Session<'a> {
+    pub fn new(context: &'a ConnectionContext, peer: String) -> Self {
+        let (sender, receiver) = mpsc::channel::<AdaCommand>();
+        Session {
+            context,
+            id: Uuid::new_v4(),
+            peer,
+            sender,
+            receiver,
+            authenticator: context.key_store.as_deref().map(Authenticator::new),
+            identity: None,
+        }
+    }
+
+    /// Frames the scheduler produced for this connection since the last call.
+    pub fn outgoing(&self) -> impl Iterator<Item = AdaCommand> + '_ {
+        self.receiver.try_iter()
+    }
+
+    /// Authenticates, logs and queues one complete frame received from the peer.
+    pub fn receive(&mut self, frame: &[u8]) -> FrameOutcome {
+        let command = match parse_command(frame) {
+            Some(command) => command,
+            None => {
+                log::warn!("Dropping malformed frame of {} bytes from {}", frame.len(), self.peer);
+                return FrameOutcome::Handled;
+            }
+        };
+
+        if let (None, Some(authenticator)) = (&self.identity, self.authenticator.as_mut()) {
+            return match authenticator.handle(&command) {
+                AuthOutcome::Authenticated(name, reply) => {
+                    self.identity = Some(name);
+                    FrameOutcome::Reply(reply)
+                }
+                AuthOutcome::Continue(reply) => FrameOutcome::Reply(reply),
+                AuthOutcome::Rejected(reply) => {
+                    log::warn!("Closing unauthenticated connection with {}", self.peer);
+                    FrameOutcome::Close(reply)
+                }
+            };
+        }
+
+        let origin = CommandOrigin::new(self.sender.clone())
+            .with_identity(self.identity.clone())
+            .with_connection(self.id);
+        match accept_command(self.context, command, origin) {
+            Some(reply) => FrameOutcome::Reply(reply),
+            None => FrameOutcome::Handled,
+        }
+    }
+
+    /// Tells the dispatcher the connection is gone, call once the peer closed or failed.
+    pub fn close(self) {
+        if let Some(capture) = &self.context.capture {
+            if let Err(e) = capture.closed(self.id) {
+                log::warn!("Unable to capture the end of connection {}: {}", self.id, e);
+            }
+        }
+        connection_closed(&self.context.queue, self.id, self.sender);
+    }
+}
+
+// Returns once the connection closed or failed
+fn exchange<C: Connection>(session: &mut Session, connection: &mut C) {
+    // Bytes received but not yet forming a complete frame
+    let mut pending: Vec<u8> = Vec::new();
+    let mut chunk = [0; READ_CHUNK_SIZE];
+
+    loop {
+        for outgoing in session.outgoing() {
+            if let Err(e) = send(connection, &outgoing) {
+                log::error!("An error occurred, terminating connection with {}: {}", session.peer, e);
+                let _ = connection.shutdown();
+                return;
+            }
+        }
+
+        match connection.read(&mut chunk) {
+            Ok(0) => {
+                log::debug!("Connection closed by peer: {}", session.peer);
+                return;
+            }
+            Ok(size) => {
+                pending.extend_from_slice(&chunk[..size]);
+                while let Some(frame_length) = declared_frame_length(&pending) {
+                    // Refused before buffering, so a peer cannot make the connection hold more than one frame
+                    if frame_length > MAX_FRAME_SIZE as usize {
+                        log::warn!("Closing connection with {}: frame of {} bytes exceeds {} bytes", session.peer, frame_length, MAX_FRAME_SIZE);
+                        let _ = send(connection, &frame_too_large(&pending));
+                        let _ = connection.shutdown();
+                        return;
+                    }
+                    if pending.len() < frame_length {
+                        break;
+                    }
+                    let frame: Vec<u8> = pending.drain(..frame_length).collect();
+                    // The TLS handshake completes during the first reads, so check on every frame
+                    if session.identity.is_none() {
+                        session.identity = connection.authenticated_identity();
+                    }
+                    let (reply, close) = match session.receive(&frame) {
+                        FrameOutcome::Handled => continue,
+                        FrameOutcome::Reply(reply) => (reply, false),
+                        FrameOutcome::Close(reply) => (reply, true),
+                    };
+                    if send(connection, &reply).is_err() || close {
+                        let _ = connection.shutdown();
+                        return;
+                    }
+                }
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) => {
+                log::error!("An error occurred, terminating connection with {}: {}", session.peer, e);
+                let _ = connection.shutdown();
+                return;
+            }
+        }
+    }
+}
+
+fn send<C: Connection>(connection: &mut C, command: &AdaCommand) -> std::io::Result<()> {
+    connection.write_all(&command.to_bytes())?;
+    connection.flush()
+}
+
+// Length the first frame in `buffer` declares, once its header has arrived
+fn declared_frame_length(buffer: &[u8]) -> Option<usize> {
+    if buffer.len() < COMMON_HEADER_SIZE as usize {
+        return None;
+    }
+    let content_length = u32::from_be_bytes([buffer[1], buffer[2], buffer[3], buffer[4]]) as usize;
+    // A frame can never be shorter than its header, treat it as a header-only frame so it gets rejected
+    Some(content_length.max(COMMON_HEADER_SIZE as usize))
+}
+
+// Error reply to the frame whose header starts `buffer`
+fn frame_too_large(buffer: &[u8]) -> AdaCommand {
+    let command = match parse_header(&buffer[..COMMON_HEADER_SIZE as usize]) {
+        Some(header) => AdaCommand { header, data: None, origin: None },
+        None => AdaCommand::new(AdaCommandType::Error),
+    };
+    command.error("frame too large")
+}
+
+impl Connection for TcpStream {
+    fn peer_identity(&self) -> String {
+        self.peer_addr().map(|addr| addr.to_string()).unwrap_or_else(|_| "unknown".to_string())
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+
+    fn shutdown(&self) -> std::io::Result<()> {
+        TcpStream::shutdown(self, Shutdown::Both)
+    }
+}
+
+impl Transport for TcpListener {
+    type Connection = TcpStream;
+
+    fn accept(&self) -> std::io::Result<TcpStream> {
+        TcpListener::accept(self).map(|(stream, _)| stream)
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::net::Shutdown;
+    use std::os::fd::AsRawFd;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::time::Duration;
+
+    use super::{Connection, Transport};
+
+    impl Connection for UnixStream {
+        // Unix peers are unnamed, the descriptor tells open connections apart
+        fn peer_identity(&self) -> String {
+            format!("unix:fd{}", self.as_raw_fd())
+        }
+
+        fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+            UnixStream::set_read_timeout(self, timeout)
+        }
+
+        fn shutdown(&self) -> std::io::Result<()> {
+            UnixStream::shutdown(self, Shutdown::Both)
+        }
+    }
+
+    impl Transport for UnixListener {
+        type Connection = UnixStream;
+
+        fn accept(&self) -> std::io::Result<UnixStream> {
+            UnixListener::accept(self).map(|(stream, _)| stream)
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::fs::{self, DirBuilder};
+use std::io::{Error, ErrorKind};
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::os::unix::net::UnixListener;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use uuid::Uuid;
+
+use crate::protocol::auth::KeyStore;
+use crate::protocol::capture::Capture;
+use crate::protocol::commands::AdaCommand;
+use crate::protocol::transport::{self, ConnectionContext};
+use crate::protocol::wal::WriteAheadLog;
+
+/// Mode applied to the socket file unless configured otherwise: owner and group may connect.
+pub const DEFAULT_SOCKET_MODE: u32 = 0o660;
+
+/// Unix domain socket listener for clients on the same host, speaking the same frames as `TcpServer`.
+///
+/// Access is controlled through the permissions of the socket file, connecting requires write
+/// permission on it.
+pub struct UnixServer {
+    path: PathBuf,
+    mode: u32,
+    context: ConnectionContext,
+}
+
+impl UnixServer {
+    pub fn new(path: &str, mode: u32, queue: Arc<Mutex<Vec<AdaCommand>>>) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            mode,
+            context: ConnectionContext::new(queue),
+        }
+    }
+
+    /// Requires every connection to authenticate against `key_store` before its commands are queued.
+    pub fn with_key_store(mut self, key_store: Arc<KeyStore>) -> Self {
+        self.context.key_store = Some(key_store);
+        self
+    }
+
+    /// Logs every command to `wal` before it is queued.
+    pub fn with_wal(mut self, wal: Arc<WriteAheadLog>) -> Self {
+        self.context.wal = Some(wal);
+        self
+    }
+
+    /// Records every command accepted to `capture`.
+    pub fn with_capture(mut self, capture: Arc<Capture>) -> Self {
+        self.context.capture = Some(capture);
+        self
+    }
+
+    pub fn run(&self) -> std::io::Result<()> {
+        let listener = self.bind()?;
+        log::info!("Server listening on {} (mode {:o})", self.path.display(), self.mode);
+
+        transport::serve(&listener, self.context.clone());
+
+        Ok(())
+    }
+
+    /// Binds the socket, which only appears at the configured path once it has its final mode.
+    ///
+    /// A socket left behind by a previous run is replaced, any other file at the path is an error.
+    pub fn bind(&self) -> std::io::Result<UnixListener> {
+        match fs::symlink_metadata(&self.path) {
+            Ok(metadata) if metadata.file_type().is_socket() => {
+                fs::remove_file(&self.path)?;
+                log::debug!("Removed stale socket {}", self.path.display());
+            }
+            Ok(_) => return Err(Error::new(ErrorKind::AlreadyExists, format!("{} exists and is not a socket", self.path.display()))),
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        // Bound inside a directory only the owner can enter, so nobody connects before the chmod
+        let parent = self.path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        let staging = parent.join(format!(".ada-socket-{}", Uuid::new_v4()));
+        DirBuilder::new().mode(0o700).create(&staging)?;
+        let staged = staging.join("socket");
+        let listener = UnixListener::bind(&staged).and_then(|listener| {
+            fs::set_permissions(&staged, fs::Permissions::from_mode(self.mode))?;
+            fs::rename(&staged, &self.path)?;
+            Ok(listener)
+        });
+        let _ = fs::remove_file(&staged);
+        let _ = fs::remove_dir(&staging);
+        listener
+    }
+}
@@ -0,0 +1,276 @@
+//! Write-ahead log of the commands accepted from clients.
+//!
+//! Every command is appended in its `to_bytes` form and synced to disk before it is queued, and
+//! marked done once the scheduler handled it. Commands never marked done are replayed at startup,
+//! so a command may run twice if the executor stopped right after handling it.
+//!
+//! The log is a directory of numbered `<segment>.wal` files, only the newest one is appended to.
+//! Once it outgrows the segment size a new segment is started with the commands still pending and
+//! the older segments are deleted, which keeps the log about one segment plus the pending commands.
+//!
+//! A segment is a sequence of records, all integers big endian:
+//! - accepted: `1`, sequence `u64`, identity length `u16` (`u16::MAX` without identity), identity,
+//!   frame length `u32`, frame
+//! - done: `2`, sequence `u64`
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+
+use crate::protocol::commands::{AdaCommand, CommandOrigin};
+use crate::protocol::protocol_parser::parse_command;
+
+/// Segment size after which the log rotates unless set with `WriteAheadLog::with_segment_size`.
+pub const DEFAULT_SEGMENT_SIZE: u64 = 4 * 1024 * 1024;
+
+const ACCEPTED: u8 = 1;
+const DONE: u8 = 2;
+const NO_IDENTITY: u16 = u16::MAX;
+const EXTENSION: &str = "wal";
+
+pub struct WriteAheadLog {
+    dir: PathBuf,
+    segment_size: u64,
+    state: Mutex<LogState>,
+}
+
+struct LogState {
+    segment: File,
+    segment_number: u64,
+    segment_length: u64,
+    // Bytes of pending records the segment was started with, they do not count towards rotation
+    carried_length: u64,
+    next_sequence: u64,
+    // Accepted records not marked done yet, by sequence
+    pending: BTreeMap<u64, Vec<u8>>,
+}
+
+impl WriteAheadLog {
+    /// Opens the log in `dir`, creating it if needed. Returns the log together with the commands
+    /// accepted but never marked done, oldest first, which still have to be queued.
+    pub fn open(dir: &Path) -> std::io::Result<(WriteAheadLog, Vec<AdaCommand>)> {
+        fs::create_dir_all(dir)?;
+        let segments = segments(dir)?;
+        let mut pending = BTreeMap::new();
+        let mut next_sequence = 1;
+        for (_, path) in &segments {
+            next_sequence = next_sequence.max(read_segment(path, &mut pending)?);
+        }
+
+        let mut replayed = Vec::new();
+        for (sequence, record) in &pending {
+            match decode_accepted(record) {
+                Some(command) => replayed.push(command),
+                None => log::warn!("Dropping unreadable command {} from the write-ahead log", sequence),
+            }
+        }
+        pending.retain(|sequence, _| replayed.iter().any(|command| wal_sequence(command) == Some(*sequence)));
+
+        // Starting a fresh segment with the pending commands compacts whatever the previous process left
+        let segment_number = segments.last().map(|(number, _)| number + 1).unwrap_or(1);
+        let log = WriteAheadLog {
+            dir: dir.to_path_buf(),
+            segment_size: DEFAULT_SEGMENT_SIZE,
+            state: Mutex::new(LogState {
+                segment: create_segment(dir, segment_number)?,
+                segment_number,
+                segment_length: 0,
+                carried_length: 0,
+                next_sequence,
+                pending,
+            }),
+        };
+        log.write_pending(&mut log.state.lock().unwrap())?;
+        remove_segments_before(dir, segment_number)?;
+        if !replayed.is_empty() {
+            log::info!("Replaying {} commands from the write-ahead log in {}", replayed.len(), dir.display());
+        }
+        Ok((log, replayed))
+    }
+
+    /// Size in bytes after which the current segment is rotated.
+    pub fn with_segment_size(mut self, segment_size: u64) -> Self {
+        self.segment_size = segment_size;
+        self
+    }
+
+    /// Appends `command`, sent by `identity`, and syncs it to disk. Returns its sequence number.
+    ///
+    /// On an error nothing of the command is left in the log. Once it is synced the command counts
+    /// as accepted, failing to rotate afterwards is only logged.
+    pub fn append(&self, command: &AdaCommand, identity: Option<&str>) -> std::io::Result<u64> {
+        let mut state = self.state.lock().unwrap();
+        let sequence = state.next_sequence;
+        let record = encode_accepted(sequence, command, identity)?;
+        if let Err(e) = state.segment.write_all(&record).and_then(|_| state.segment.sync_data()) {
+            // Otherwise a command the client was told failed would be replayed at the next start
+            let _ = state.segment.set_len(state.segment_length);
+            return Err(e);
+        }
+        state.segment_length += record.len() as u64;
+        state.next_sequence += 1;
+        state.pending.insert(sequence, record);
+        if let Err(e) = self.rotate_if_full(&mut state) {
+            log::error!("Failed to rotate the write-ahead log in {}: {}", self.dir.display(), e);
+        }
+        Ok(sequence)
+    }
+
+    /// Marks the command with the given sequence number as handled, it is not replayed any more.
+    pub fn mark_done(&self, sequence: u64) -> std::io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.pending.remove(&sequence).is_none() {
+            return Ok(());
+        }
+        let mut record = vec![DONE];
+        record.extend_from_slice(&sequence.to_be_bytes());
+        // Losing a done record only replays a command, so it is not synced
+        state.segment.write_all(&record)?;
+        state.segment_length += record.len() as u64;
+        self.rotate_if_full(&mut state)
+    }
+
+    /// Number of commands accepted but not marked done.
+    pub fn pending(&self) -> usize {
+        self.state.lock().unwrap().pending.len()
+    }
+
+    fn rotate_if_full(&self, state: &mut LogState) -> std::io::Result<()> {
+        // Pending records alone may exceed the segment size, rotating again would not shrink the log
+        if state.segment_length - state.carried_length < self.segment_size {
+            return Ok(());
+        }
+        state.segment_number += 1;
+        state.segment = create_segment(&self.dir, state.segment_number)?;
+        state.segment_length = 0;
+        self.write_pending(state)?;
+        log::debug!("Rotated write-ahead log to segment {} with {} pending commands", state.segment_number, state.pending.len());
+        remove_segments_before(&self.dir, state.segment_number)
+    }
+
+    // Copies every pending record into the current segment, older segments can go once it returns
+    fn write_pending(&self, state: &mut LogState) -> std::io::Result<()> {
+        let mut written = 0;
+        for record in state.pending.values() {
+            state.segment.write_all(record)?;
+            written += record.len() as u64;
+        }
+        state.segment.sync_all()?;
+        state.segment_length += written;
+        state.carried_length = written;
+        Ok(())
+    }
+}
+
+fn wal_sequence(command: &AdaCommand) -> Option<u64> {
+    command.origin.as_ref().and_then(|origin| origin.wal_sequence)
+}
+
+fn encode_accepted(sequence: u64, command: &AdaCommand, identity: Option<&str>) -> std::io::Result<Vec<u8>> {
+    let frame = command.to_bytes();
+    let mut record = vec![ACCEPTED];
+    record.extend_from_slice(&sequence.to_be_bytes());
+    match identity {
+        Some(identity) if identity.len() < NO_IDENTITY as usize => {
+            record.extend_from_slice(&(identity.len() as u16).to_be_bytes());
+            record.extend_from_slice(identity.as_bytes());
+        }
+        Some(_) => return Err(Error::new(ErrorKind::InvalidInput, "identity too long")),
+        None => record.extend_from_slice(&NO_IDENTITY.to_be_bytes()),
+    }
+    record.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+    record.extend_from_slice(&frame);
+    Ok(record)
+}
+
+// The command of an accepted record, with an origin whose responses go nowhere
+fn decode_accepted(record: &[u8]) -> Option<AdaCommand> {
+    let sequence = u64::from_be_bytes(record.get(1..9)?.try_into().ok()?);
+    let identity_length = u16::from_be_bytes(record.get(9..11)?.try_into().ok()?);
+    let (identity, rest) = match identity_length {
+        NO_IDENTITY => (None, record.get(11..)?),
+        length => {
+            let identity = String::from_utf8(record.get(11..11 + length as usize)?.to_vec()).ok()?;
+            (Some(identity), record.get(11 + length as usize..)?)
+        }
+    };
+    let mut command = parse_command(rest.get(4..)?)?;
+    let (reply, _) = mpsc::channel();
+    command.origin = Some(CommandOrigin::new(reply).with_identity(identity).with_wal_sequence(sequence));
+    Some(command)
+}
+
+// Collects the accepted records of the segment not marked done into `pending`, a torn record at
+// the end is ignored. Returns the sequence number following the highest one seen.
+fn read_segment(path: &Path, pending: &mut BTreeMap<u64, Vec<u8>>) -> std::io::Result<u64> {
+    let bytes = fs::read(path)?;
+    let mut next_sequence = 1;
+    let mut offset = 0;
+    while let Some((length, sequence)) = record_at(&bytes[offset..]) {
+        let record = &bytes[offset..offset + length];
+        if record[0] == ACCEPTED {
+            pending.insert(sequence, record.to_vec());
+        } else {
+            pending.remove(&sequence);
+        }
+        next_sequence = next_sequence.max(sequence + 1);
+        offset += length;
+    }
+    if offset < bytes.len() {
+        log::warn!("Ignoring {} bytes of an incomplete record at the end of {}", bytes.len() - offset, path.display());
+    }
+    Ok(next_sequence)
+}
+
+// Length and sequence number of the complete record at the start of `bytes`
+fn record_at(bytes: &[u8]) -> Option<(usize, u64)> {
+    let sequence = u64::from_be_bytes(bytes.get(1..9)?.try_into().ok()?);
+    match *bytes.first()? {
+        DONE => Some((9, sequence)),
+        ACCEPTED => {
+            let identity_length = match u16::from_be_bytes(bytes.get(9..11)?.try_into().ok()?) {
+                NO_IDENTITY => 0,
+                length => length as usize,
+            };
+            let frame_start = 11 + identity_length + 4;
+            let frame_length = u32::from_be_bytes(bytes.get(frame_start - 4..frame_start)?.try_into().ok()?) as usize;
+            let length = frame_start + frame_length;
+            (bytes.len() >= length).then_some((length, sequence))
+        }
+        _ => None,
+    }
+}
+
+// Segment files in `dir` by number, oldest first
+fn segments(dir: &Path) -> std::io::Result<Vec<(u64, PathBuf)>> {
+    let mut segments = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == EXTENSION) {
+            if let Some(number) = path.file_stem().and_then(|stem| stem.to_str()).and_then(|stem| stem.parse().ok()) {
+                segments.push((number, path));
+            }
+        }
+    }
+    segments.sort();
+    Ok(segments)
+}
+
+// The directory is synced as well, so the new segment survives a crash once older ones are removed
+fn create_segment(dir: &Path, number: u64) -> std::io::Result<File> {
+    let segment = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(format!("{:020}.{}", number, EXTENSION)))?;
+    File::open(dir)?.sync_all()?;
+    Ok(segment)
+}
+
+fn remove_segments_before(dir: &Path, number: u64) -> std::io::Result<()> {
+    for (_, path) in segments(dir)?.into_iter().filter(|(segment, _)| *segment < number) {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use tungstenite::protocol::WebSocketConfig;
+use tungstenite::{Message, WebSocket};
+
+use crate::protocol::auth::KeyStore;
+use crate::protocol::capture::Capture;
+use crate::protocol::commands::{AdaCommand, MAX_FRAME_SIZE};
+use crate::protocol::transport::{ConnectionContext, FrameOutcome, Session};
+use crate::protocol::wal::WriteAheadLog;
+
+// How long a read may block before queued outgoing frames are flushed
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// WebSocket transport for browser clients.
+///
+/// Every binary message carries exactly one `AdaCommand` frame in the same encoding `TcpServer`
+/// reads. Responses and other frames produced for the connection are pushed back as binary
+/// messages as soon as the scheduler emits them.
+pub struct WebSocketServer {
+    address: String,
+    context: ConnectionContext,
+}
+
+impl WebSocketServer {
+    pub fn new(address: &str, queue: Arc<Mutex<Vec<AdaCommand>>>) -> Self {
+        Self {
+            address: address.to_string(),
+            context: ConnectionContext::new(queue),
+        }
+    }
+
+    /// Requires every connection to complete the `Auth` handshake before its commands are queued.
+    pub fn with_key_store(mut self, key_store: Arc<KeyStore>) -> Self {
+        self.context.key_store = Some(key_store);
+        self
+    }
+
+    /// Logs every command to `wal` before it is queued.
+    pub fn with_wal(mut self, wal: Arc<WriteAheadLog>) -> Self {
+        self.context.wal = Some(wal);
+        self
+    }
+
+    /// Records every command accepted to `capture`.
+    pub fn with_capture(mut self, capture: Arc<Capture>) -> Self {
+        self.context.capture = Some(capture);
+        self
+    }
+
+    pub fn run(&self) -> std::io::Result<()> {
+        let listener = TcpListener::bind(&self.address)?;
+        log::info!("WebSocket server listening on {}", self.address);
+        self.serve(listener)
+    }
+
+    /// Accepts WebSocket clients on `listener`, which may be bound to another address than configured.
+    pub fn serve(&self, listener: TcpListener) -> std::io::Result<()> {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    log::debug!("New WebSocket connection: {:?}", stream.peer_addr());
+                    let context_clone = self.context.clone();
+                    thread::spawn(move || {
+                        // Messages carry a single frame, so they are limited like frames on the other transports
+                        let config = WebSocketConfig {
+                            max_message_size: Some(MAX_FRAME_SIZE as usize),
+                            max_frame_size: Some(MAX_FRAME_SIZE as usize),
+                            ..WebSocketConfig::default()
+                        };
+                        match tungstenite::accept_with_config(stream, Some(config)) {
+                            Ok(socket) => WebSocketServer::handle_client(context_clone, socket),
+                            Err(e) => log::error!("WebSocket handshake failed: {}", e),
+                        }
+                    });
+                }
+                Err(e) => {
+                    log::error!("Error: {}", e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_client(context: ConnectionContext, mut socket: WebSocket<TcpStream>) {
+        if let Err(e) = socket.get_ref().set_read_timeout(Some(POLL_INTERVAL)) {
+            log::error!("Unable to configure WebSocket connection: {}", e);
+            return;
+        }
+        let peer = socket.get_ref().peer_addr().map(|addr| format!("ws:{}", addr)).unwrap_or_else(|_| "ws:unknown".to_string());
+        let mut session = Session::new(&context, peer);
+        Self::exchange(&mut session, &mut socket);
+        session.close();
+    }
+
+    // Returns once the connection closed or failed
+    fn exchange(session: &mut Session, socket: &mut WebSocket<TcpStream>) {
+        loop {
+            for outgoing in session.outgoing() {
+                if let Err(e) = socket.send(Message::Binary(outgoing.to_bytes())) {
+                    log::error!("Failed to push frame to WebSocket client: {}", e);
+                    return;
+                }
+            }
+
+            match socket.read() {
+                Ok(Message::Binary(frame)) => {
+                    let (reply, close) = match session.receive(&frame) {
+                        FrameOutcome::Handled => continue,
+                        FrameOutcome::Reply(reply) => (reply, false),
+                        FrameOutcome::Close(reply) => (reply, true),
+                    };
+                    if socket.send(Message::Binary(reply.to_bytes())).is_err() || close {
+                        let _ = socket.close(None);
+                        return;
+                    }
+                }
+                Ok(Message::Close(_)) => {
+                    log::debug!("WebSocket closed by peer");
+                    return;
+                }
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(e)) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(tungstenite::Error::ConnectionClosed) | Err(tungstenite::Error::AlreadyClosed) => return,
+                Err(e) => {
+                    log::error!("An error occurred, terminating WebSocket connection: {}", e);
+                    return;
+                }
+            }
+        }
+    }
+}
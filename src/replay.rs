@@ -0,0 +1,126 @@
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use log::LevelFilter;
+use simple_logger::SimpleLogger;
+use uuid::Uuid;
+
+use ada_executor::command_processor::access_control::AccessControl;
+use ada_executor::command_processor::command_scheduler::{self, SchedulerOptions};
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::network::registry::NodeRegistry;
+use ada_executor::protocol::capture::{self, Pace};
+use ada_executor::protocol::commands::AdaCommand;
+
+/// Feeds a capture written with `ADA_CAPTURE` into a fresh executor and prints every frame it sends back.
+///
+/// `replay <capture> [--real-time] [--limit <records>]` replays as fast as possible unless
+/// `--real-time` keeps the captured gaps, `--limit` stops after the first records, for bisecting.
+/// `ADA_ACL`, `ADA_GRAPH_DIR`, `ADA_ALLOW_PROCESS_EXEC` and the plugin directories set up the executor like `ada-executor`.
+pub fn main() -> io::Result<()> {
+    SimpleLogger::new()
+        .with_level(LevelFilter::Warn)
+        .init().unwrap();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let usage = || io::Error::new(io::ErrorKind::InvalidInput, "usage: replay <capture> [--real-time] [--limit <records>]");
+    let path = args.first().filter(|arg| !arg.starts_with("--")).ok_or_else(usage)?;
+    let pace = match args.iter().any(|arg| arg == "--real-time") {
+        true => Pace::RealTime,
+        false => Pace::AsFastAsPossible,
+    };
+    let limit = match args.iter().position(|arg| arg == "--limit") {
+        Some(index) => args.get(index + 1).and_then(|limit| limit.parse().ok()).ok_or_else(usage)?,
+        None => usize::MAX,
+    };
+
+    let mut records = capture::read(path.as_ref())?;
+    records.truncate(limit);
+
+    let registry = NodeRegistry::with_builtins();
+    #[cfg(feature = "wasm")]
+    let registry = match std::env::var("ADA_PLUGIN_DIR") {
+        Ok(plugin_dir) => {
+            let mut registry = registry;
+            ada_executor::network::plugins::wasm::load_dir(&mut registry, plugin_dir.as_ref(), wasm_limits()).expect("Invalid WebAssembly plugin");
+            registry
+        }
+        Err(_) => registry,
+    };
+    #[cfg(feature = "plugins")]
+    let registry = match std::env::var("ADA_NATIVE_PLUGIN_DIR") {
+        Ok(plugin_dir) => {
+            let mut registry = registry;
+            ada_executor::network::plugins::native::load_dir(&mut registry, plugin_dir.as_ref()).expect("Invalid native plugin");
+            registry
+        }
+        Err(_) => registry,
+    };
+    #[cfg(feature = "process")]
+    let registry = match std::env::var("ADA_ALLOW_PROCESS_EXEC") {
+        Ok(commands) => {
+            let mut registry = registry;
+            let allowed = commands.split(',').map(str::trim).filter(|command| !command.is_empty()).map(String::from).collect();
+            ada_executor::network::nodes::process::register(&mut registry, allowed);
+            registry
+        }
+        Err(_) => registry,
+    };
+    let dispatcher = match std::env::var("ADA_ACL") {
+        Ok(path) => Dispatcher::new().with_access_control(AccessControl::load(path.as_ref()).expect("Invalid access control list")),
+        Err(_) => Dispatcher::new(),
+    }.with_registry(registry);
+    #[cfg(feature = "serde")]
+    let dispatcher = match std::env::var("ADA_GRAPH_DIR") {
+        Ok(graph_dir) => {
+            let mut dispatcher = dispatcher;
+            dispatcher.load_networks(graph_dir.as_ref()).expect("Invalid graph file");
+            dispatcher
+        }
+        Err(_) => dispatcher,
+    };
+
+    let queue = Arc::new(Mutex::new(Vec::new()));
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let options = SchedulerOptions { shutdown: Some(Arc::clone(&shutdown)), ..SchedulerOptions::default() };
+    let scheduler = command_scheduler::start(Arc::clone(&queue), dispatcher, options);
+
+    let (responses, received) = mpsc::channel::<(Uuid, AdaCommand)>();
+    let printer = thread::spawn(move || {
+        for (connection, response) in received {
+            println!("{} {} {:?}", connection, response, response.data);
+        }
+    });
+    let queued = capture::replay(&records, &queue, pace, &responses);
+    drop(responses);
+
+    // The scheduler only stops between batches, so everything queued has been handled once it sees the flag
+    loop {
+        let queue = queue.lock().unwrap();
+        if queue.is_empty() {
+            shutdown.store(true, Ordering::SeqCst);
+            break;
+        }
+        drop(queue);
+        thread::sleep(Duration::from_millis(10));
+    }
+    let _ = scheduler.join();
+    // Runs still in progress keep their connection's reply channel until they finish
+    let _ = printer.join();
+    eprintln!("Replayed {} commands from {} records", queued, records.len());
+    Ok(())
+}
+
+// Most fuel and memory a WebAssembly node may ask for, `ADA_WASM_MAX_FUEL` and `ADA_WASM_MAX_MEMORY_BYTES`
+#[cfg(feature = "wasm")]
+fn wasm_limits() -> ada_executor::network::plugins::wasm::Limits {
+    let defaults = ada_executor::network::plugins::wasm::Limits::default();
+    ada_executor::network::plugins::wasm::Limits {
+        fuel: std::env::var("ADA_WASM_MAX_FUEL").ok().and_then(|fuel| fuel.parse().ok()).unwrap_or(defaults.fuel),
+        max_memory_bytes: std::env::var("ADA_WASM_MAX_MEMORY_BYTES").ok().and_then(|bytes| bytes.parse().ok()).unwrap_or(defaults.max_memory_bytes),
+    }
+}
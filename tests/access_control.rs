@@ -0,0 +1,62 @@
+use std::sync::mpsc;
+
+use uuid::Uuid;
+
+use ada_executor::command_processor::access_control::{AccessControl, NetworkScope, Permission};
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::protocol::commands::{AdaCommand, AdaCommandType, CommandOrigin};
+
+fn command_from(identity: &str, command_type: AdaCommandType, network: Uuid) -> AdaCommand {
+    let (reply, _) = mpsc::channel();
+    let mut command = AdaCommand::new(command_type);
+    command.header.network = Some(network);
+    command.origin = Some(CommandOrigin::new(reply).with_identity(identity.to_string()));
+    command
+}
+
+#[test]
+fn enforces_grants_per_network() {
+    let team_a = Uuid::new_v4();
+    let team_b = Uuid::new_v4();
+    let mut access_control = AccessControl::new();
+    access_control.grant("team-a", NetworkScope::Network(team_a), Permission::Admin);
+    access_control.grant("team-b", NetworkScope::Network(team_b), Permission::Admin);
+    access_control.grant("team-b", NetworkScope::Network(team_a), Permission::Read);
+    let mut dispatcher = Dispatcher::new().with_access_control(access_control);
+
+    let response = dispatcher.dispatch(&command_from("team-a", AdaCommandType::CreateNetwork, team_a));
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+
+    let response = dispatcher.dispatch(&command_from("team-b", AdaCommandType::ExecuteNode, team_a));
+    assert!(matches!(response.header.command_type, AdaCommandType::PermissionDenied));
+    let response = dispatcher.dispatch(&command_from("team-b", AdaCommandType::DeleteNetwork, team_a));
+    assert!(matches!(response.header.command_type, AdaCommandType::PermissionDenied));
+
+    let response = dispatcher.dispatch(&command_from("team-a", AdaCommandType::DeleteNetwork, team_a));
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+}
+
+#[test]
+fn wildcard_scope_covers_every_network() {
+    let mut access_control = AccessControl::new();
+    access_control.grant("operator", NetworkScope::Any, Permission::Execute);
+
+    assert!(access_control.is_allowed("operator", Some(Uuid::new_v4()), Permission::Execute));
+    assert!(!access_control.is_allowed("operator", Some(Uuid::new_v4()), Permission::Modify));
+    assert!(!access_control.is_allowed("someone-else", Some(Uuid::new_v4()), Permission::Read));
+}
+
+#[test]
+fn snapshots_need_admin_on_every_network() {
+    let network = Uuid::new_v4();
+    let mut access_control = AccessControl::new();
+    access_control.grant("team-a", NetworkScope::Network(network), Permission::Admin);
+    access_control.grant("operator", NetworkScope::Any, Permission::Admin);
+    let mut dispatcher = Dispatcher::new().with_access_control(access_control);
+
+    let response = dispatcher.dispatch(&command_from("team-a", AdaCommandType::Snapshot, network));
+    assert!(matches!(response.header.command_type, AdaCommandType::PermissionDenied));
+
+    let response = dispatcher.dispatch(&command_from("operator", AdaCommandType::Snapshot, network));
+    assert!(!matches!(response.header.command_type, AdaCommandType::PermissionDenied));
+}
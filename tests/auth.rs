@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use uuid::Uuid;
+
+use ada_executor::command_processor::command_scheduler;
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::protocol::auth::{self, KeyStore};
+use ada_executor::protocol::commands::{AdaCommand, AdaCommandType};
+use ada_executor::protocol::memory_transport::{MemoryConnection, MemoryTransport};
+use ada_executor::protocol::protocol_parser::read_command;
+use ada_executor::protocol::transport::{self, ConnectionContext};
+
+fn start_server() -> Arc<MemoryTransport> {
+    let queue = Arc::new(Mutex::new(Vec::new()));
+    command_scheduler::listen(Arc::clone(&queue), Dispatcher::new());
+
+    let keys = HashMap::from([("team-a".to_string(), "secret-a".to_string())]);
+    let mut context = ConnectionContext::new(queue);
+    context.key_store = Some(Arc::new(KeyStore::new(keys)));
+
+    let memory_transport = Arc::new(MemoryTransport::new());
+    let server_transport = Arc::clone(&memory_transport);
+    thread::spawn(move || transport::serve(&*server_transport, context));
+    memory_transport
+}
+
+fn create_network(client: &mut MemoryConnection) -> AdaCommand {
+    let mut command = AdaCommand::new(AdaCommandType::CreateNetwork);
+    command.header.network = Some(Uuid::new_v4());
+    client.write_all(&command.to_bytes()).unwrap();
+    read_command(client).unwrap()
+}
+
+#[test]
+fn rejects_commands_before_authentication() {
+    let server = start_server();
+    let mut client = server.connect();
+
+    let response = create_network(&mut client);
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+    assert!(read_command(&mut client).is_err());
+}
+
+#[test]
+fn accepts_commands_after_challenge_response() {
+    let server = start_server();
+    let mut client = server.connect();
+
+    auth::authenticate(&mut client, "team-a", "secret-a").unwrap();
+    let response = create_network(&mut client);
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+}
+
+fn send_token(client: &mut MemoryConnection, token: &str) -> AdaCommand {
+    let mut data = Container::new();
+    data.add_item("identity", Data::Text("team-a".to_string()));
+    data.add_item("token", Data::Text(token.to_string()));
+    let mut command = AdaCommand::new(AdaCommandType::Auth);
+    command.data = Some(data);
+    client.write_all(&command.to_bytes()).unwrap();
+    read_command(client).unwrap()
+}
+
+#[test]
+fn rejects_wrong_token() {
+    let server = start_server();
+    let mut client = server.connect();
+
+    let response = send_token(&mut client, "secret-b");
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+    assert!(auth::authenticate(&mut client, "team-a", "secret-a").is_err());
+}
+
+#[test]
+fn tokens_must_match_in_full() {
+    let server = start_server();
+    for token in ["secret", "secret-a-and-more", ""] {
+        let mut client = server.connect();
+        let response = send_token(&mut client, token);
+        assert!(matches!(response.header.command_type, AdaCommandType::Error), "{:?} was accepted", token);
+    }
+
+    let mut client = server.connect();
+    let response = send_token(&mut client, "secret-a");
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+    assert!(matches!(create_network(&mut client).header.command_type, AdaCommandType::Response));
+}
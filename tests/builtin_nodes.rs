@@ -0,0 +1,118 @@
+use uuid::Uuid;
+
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::network::registry::NodeRegistry;
+
+fn container(items: &[(&str, Data)]) -> Container {
+    let mut container = Container::new();
+    for (key, value) in items {
+        container.add_item(key, value.clone());
+    }
+    container
+}
+
+fn run(type_name: &str, config: Container, input: &[(&str, Data)]) -> Container {
+    let node = NodeRegistry::with_builtins().create_node(type_name, Uuid::new_v4(), config)
+        .expect("built-in node type");
+    node.execute(container(input))
+}
+
+fn result(type_name: &str, input: &[(&str, Data)]) -> Option<Data> {
+    run(type_name, Container::new(), input).get_item("result").cloned()
+}
+
+fn text(value: &str) -> Data {
+    Data::Text(value.to_string())
+}
+
+#[test]
+fn arithmetic_keeps_integers_and_promotes_mixed_input() {
+    assert_eq!(result("math.add", &[("a", Data::Integer(2)), ("b", Data::Integer(3))]), Some(Data::Integer(5)));
+    assert_eq!(result("math.subtract", &[("a", Data::Integer(2)), ("b", Data::Float(0.5))]), Some(Data::Float(1.5)));
+    assert_eq!(result("math.multiply", &[("a", Data::Float(1.5)), ("b", Data::Integer(4))]), Some(Data::Float(6.0)));
+    assert_eq!(result("math.divide", &[("a", Data::Integer(7)), ("b", Data::Integer(2))]), Some(Data::Integer(3)));
+    assert_eq!(result("math.modulo", &[("a", Data::Integer(7)), ("b", Data::Integer(4))]), Some(Data::Integer(3)));
+    assert_eq!(result("math.max", &[("a", Data::Integer(7)), ("b", Data::Integer(4))]), Some(Data::Integer(7)));
+}
+
+#[test]
+fn arithmetic_without_result_yields_empty_output() {
+    assert_eq!(result("math.divide", &[("a", Data::Integer(1)), ("b", Data::Integer(0))]), None);
+    assert_eq!(result("math.add", &[("a", Data::Integer(i32::MAX)), ("b", Data::Integer(1))]), None);
+    assert_eq!(result("math.add", &[("a", text("1")), ("b", Data::Integer(1))]), None);
+    assert_eq!(result("math.add", &[("a", Data::Integer(1))]), None);
+}
+
+#[test]
+fn comparisons_mix_numbers_and_order_text() {
+    assert_eq!(result("logic.equal", &[("a", Data::Integer(2)), ("b", Data::Float(2.0))]), Some(Data::Boolean(true)));
+    assert_eq!(result("logic.not_equal", &[("a", text("x")), ("b", text("x"))]), Some(Data::Boolean(false)));
+    assert_eq!(result("logic.less", &[("a", text("abc")), ("b", text("abd"))]), Some(Data::Boolean(true)));
+    assert_eq!(result("logic.greater_equal", &[("a", Data::Integer(1)), ("b", Data::Float(1.5))]), Some(Data::Boolean(false)));
+    assert_eq!(result("logic.less", &[("a", text("1")), ("b", Data::Integer(2))]), None);
+}
+
+#[test]
+fn boolean_logic() {
+    assert_eq!(result("logic.and", &[("a", Data::Boolean(true)), ("b", Data::Boolean(false))]), Some(Data::Boolean(false)));
+    assert_eq!(result("logic.or", &[("a", Data::Boolean(true)), ("b", Data::Boolean(false))]), Some(Data::Boolean(true)));
+    assert_eq!(result("logic.xor", &[("a", Data::Boolean(true)), ("b", Data::Boolean(true))]), Some(Data::Boolean(false)));
+    assert_eq!(result("logic.not", &[("value", Data::Boolean(false))]), Some(Data::Boolean(true)));
+}
+
+#[test]
+fn text_nodes() {
+    let config = container(&[("separator", text(" "))]);
+    let output = run("text.concat", config, &[("a", text("hello")), ("b", Data::Integer(3))]);
+    assert_eq!(output.get_item("result"), Some(&text("hello 3")));
+
+    let config = container(&[("template", text("{name} is {age}{missing}"))]);
+    let output = run("text.format", config, &[("name", text("ada")), ("age", Data::Integer(36))]);
+    assert_eq!(output.get_item("result"), Some(&text("ada is 36{missing}")));
+
+    let output = run("text.split", Container::new(), &[("value", text("a,b,c"))]);
+    assert_eq!(output, container(&[("0", text("a")), ("1", text("b")), ("2", text("c")), ("count", Data::Integer(3))]));
+}
+
+#[test]
+fn conversions() {
+    assert_eq!(result("convert.to_integer", &[("value", text(" 42 "))]), Some(Data::Integer(42)));
+    assert_eq!(result("convert.to_integer", &[("value", Data::Float(2.9))]), Some(Data::Integer(2)));
+    assert_eq!(result("convert.to_float", &[("value", Data::Integer(2))]), Some(Data::Float(2.0)));
+    assert_eq!(result("convert.to_text", &[("value", Data::Boolean(true))]), Some(text("true")));
+    assert_eq!(result("convert.to_boolean", &[("value", Data::Integer(0))]), Some(Data::Boolean(false)));
+    assert_eq!(result("convert.to_integer", &[("value", text("four"))]), None);
+}
+
+#[test]
+fn constant_and_pass_through() {
+    let config = container(&[("value", Data::Integer(1)), ("unit", text("m"))]);
+    let output = run("flow.constant", config, &[("value", Data::Integer(2))]);
+    assert_eq!(output, container(&[("value", Data::Integer(2)), ("unit", text("m"))]));
+
+    let input = [("a", Data::Integer(1)), ("b", text("x"))];
+    assert_eq!(run("flow.pass", Container::new(), &input), container(&input));
+    assert_eq!(run("flow.merge", Container::new(), &input), container(&input));
+}
+
+#[test]
+fn gate_and_filter() {
+    let open = run("flow.gate", Container::new(), &[("value", Data::Integer(1)), ("open", Data::Boolean(true))]);
+    assert_eq!(open, container(&[("value", Data::Integer(1))]));
+    let closed = run("flow.gate", Container::new(), &[("value", Data::Integer(1)), ("open", Data::Boolean(false))]);
+    assert!(closed.is_empty());
+
+    let config = container(&[("key", text("kind")), ("equals", text("alert"))]);
+    let input = [("kind", text("alert")), ("value", Data::Integer(1))];
+    assert_eq!(run("flow.filter", config.clone(), &input), container(&input));
+    assert!(run("flow.filter", config, &[("kind", text("info"))]).is_empty());
+    assert!(NodeRegistry::with_builtins().create_node("flow.filter", Uuid::new_v4(), Container::new()).is_err());
+}
+
+#[test]
+fn log_sink_outputs_nothing() {
+    let config = container(&[("label", text("test")), ("level", text("debug"))]);
+    assert!(run("sink.log", config, &[("value", Data::Integer(1))]).is_empty());
+    let invalid = container(&[("level", text("loud"))]);
+    assert!(NodeRegistry::with_builtins().create_node("sink.log", Uuid::new_v4(), invalid).is_err());
+}
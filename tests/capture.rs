@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use uuid::Uuid;
+
+use ada_executor::command_processor::access_control::AccessControl;
+use ada_executor::command_processor::command_scheduler;
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::protocol::auth::{self, KeyStore};
+use ada_executor::protocol::capture::{self, Capture, CaptureRecord, Pace};
+use ada_executor::protocol::commands::{AdaCommand, AdaCommandType};
+use ada_executor::protocol::memory_transport::MemoryTransport;
+use ada_executor::protocol::protocol_parser::read_command;
+use ada_executor::protocol::transport::{self, ConnectionContext};
+
+mod common;
+
+use common::TempDir;
+
+fn create_network() -> AdaCommand {
+    let mut command = AdaCommand::new(AdaCommandType::CreateNetwork);
+    command.header.network = Some(Uuid::new_v4());
+    command
+}
+
+// Replays `records` into an executor using `dispatcher`, returns what it sent back
+fn replay(records: &[CaptureRecord], dispatcher: Dispatcher, pace: Pace) -> Receiver<(Uuid, AdaCommand)> {
+    let queue = Arc::new(Mutex::new(Vec::new()));
+    command_scheduler::listen(Arc::clone(&queue), dispatcher);
+    let (responses, received) = mpsc::channel();
+    capture::replay(records, &queue, pace, &responses);
+    received
+}
+
+// The connection's thread records its end once it noticed the client is gone
+fn wait_for_records(path: &Path, count: usize) -> Vec<CaptureRecord> {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let records = capture::read(path).unwrap();
+        if records.len() == count || Instant::now() > deadline {
+            break records;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn records_survive_a_round_trip() {
+    let dir = TempDir::new("capture");
+    let path = dir.join("capture.bin");
+    let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+    let frame = create_network().to_bytes();
+    {
+        let capture = Capture::open(&path).unwrap();
+        capture.frame(first, None, &frame).unwrap();
+        capture.frame(second, Some("alice"), &frame).unwrap();
+    }
+    // Reopening appends to the same capture
+    Capture::open(&path).unwrap().closed(first).unwrap();
+    #[cfg(unix)]
+    assert_eq!(std::os::unix::fs::PermissionsExt::mode(&fs::metadata(&path).unwrap().permissions()) & 0o777, 0o600);
+
+    let records = capture::read(&path).unwrap();
+    assert_eq!(records.len(), 3);
+    assert!(matches!(&records[0], CaptureRecord::Frame { connection, identity: None, frame: bytes, .. } if *connection == first && *bytes == frame));
+    assert!(matches!(&records[1], CaptureRecord::Frame { identity: Some(identity), .. } if identity == "alice"));
+    assert!(matches!(&records[2], CaptureRecord::Closed { connection, .. } if *connection == first));
+    assert!(records[0].at() <= records[2].at());
+}
+
+#[test]
+fn a_torn_record_at_the_end_is_ignored() {
+    let dir = TempDir::new("capture");
+    let path = dir.join("capture.bin");
+    Capture::open(&path).unwrap().frame(Uuid::new_v4(), None, &create_network().to_bytes()).unwrap();
+    let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(&[1, 0, 0, 0]).unwrap();
+    assert_eq!(capture::read(&path).unwrap().len(), 1);
+
+    let other = dir.join("other.bin");
+    fs::write(&other, b"not a capture").unwrap();
+    assert!(capture::read(&other).is_err());
+    assert!(Capture::open(&other).is_err());
+}
+
+#[test]
+fn transport_captures_frames_and_closed_connections() {
+    let dir = TempDir::new("capture");
+    let path = dir.join("capture.bin");
+    let queue = Arc::new(Mutex::new(Vec::new()));
+    command_scheduler::listen(Arc::clone(&queue), Dispatcher::new());
+    let mut context = ConnectionContext::new(queue);
+    context.capture = Some(Arc::new(Capture::open(&path).unwrap()));
+    let memory_transport = Arc::new(MemoryTransport::new());
+    let server_transport = Arc::clone(&memory_transport);
+    thread::spawn(move || transport::serve(&*server_transport, context));
+
+    let mut client = memory_transport.connect();
+    let frame = create_network().to_bytes();
+    client.write_all(&frame).unwrap();
+    read_command(&mut client).unwrap();
+    drop(client);
+
+    let records = wait_for_records(&path, 2);
+    let connection = match &records[0] {
+        CaptureRecord::Frame { connection, frame: bytes, .. } if *bytes == frame => *connection,
+        other => panic!("expected the frame, got {:?}", other),
+    };
+    assert!(matches!(&records[1], CaptureRecord::Closed { connection: closed, .. } if *closed == connection));
+}
+
+#[test]
+fn only_accepted_commands_are_captured_and_replay_keeps_the_identity() {
+    let dir = TempDir::new("capture");
+    let path = dir.join("capture.bin");
+    let queue = Arc::new(Mutex::new(Vec::new()));
+    command_scheduler::listen(Arc::clone(&queue), Dispatcher::new());
+    let mut context = ConnectionContext::new(queue);
+    context.key_store = Some(Arc::new(KeyStore::new(HashMap::from([("team-a".to_string(), "secret-a".to_string())]))));
+    context.capture = Some(Arc::new(Capture::open(&path).unwrap()));
+    let memory_transport = Arc::new(MemoryTransport::new());
+    let server_transport = Arc::clone(&memory_transport);
+    thread::spawn(move || transport::serve(&*server_transport, context));
+
+    // Refused before authenticating, so neither frame is recorded
+    let mut refused = memory_transport.connect();
+    refused.write_all(&[0; 38]).unwrap();
+    refused.write_all(&create_network().to_bytes()).unwrap();
+    assert!(matches!(read_command(&mut refused).unwrap().header.command_type, AdaCommandType::Error));
+
+    let mut client = memory_transport.connect();
+    auth::authenticate(&mut client, "team-a", "secret-a").unwrap();
+    client.write_all(&create_network().to_bytes()).unwrap();
+    let response = read_command(&mut client).unwrap();
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+    drop(client);
+
+    // The handshake carries the secret, only the command is recorded, along with the closing of both connections
+    let records = wait_for_records(&path, 3);
+    assert_eq!(records.len(), 3);
+    let records: Vec<_> = records.into_iter().filter(|record| matches!(record, CaptureRecord::Frame { .. })).collect();
+    assert_eq!(records.len(), 1);
+    assert!(matches!(&records[0], CaptureRecord::Frame { identity: Some(identity), .. } if identity == "team-a"));
+
+    // Only team-a may create networks in the executor the capture is replayed into
+    let acl_path = dir.join("acl");
+    fs::write(&acl_path, "team-a * admin\n").unwrap();
+    let dispatcher = Dispatcher::new().with_access_control(AccessControl::load(&acl_path).unwrap());
+    let received = replay(&records, dispatcher, Pace::AsFastAsPossible);
+    let (connection, response) = received.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+    assert!(matches!(&records[0], CaptureRecord::Frame { connection: captured, .. } if *captured == connection));
+    assert!(received.recv_timeout(Duration::from_millis(200)).is_err());
+}
+
+#[test]
+fn real_time_replay_keeps_the_gaps_between_frames() {
+    let connection = Uuid::new_v4();
+    let start = SystemTime::now();
+    let frame = |at: SystemTime| CaptureRecord::Frame { at, connection, identity: None, frame: create_network().to_bytes() };
+    let records = vec![frame(start), frame(start + Duration::from_millis(300)), CaptureRecord::Closed { at: start + Duration::from_millis(300), connection }];
+
+    let started = Instant::now();
+    let received = replay(&records, Dispatcher::new(), Pace::RealTime);
+    assert!(started.elapsed() >= Duration::from_millis(300));
+    for _ in 0..2 {
+        let (from, response) = received.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(from, connection);
+        assert!(matches!(response.header.command_type, AdaCommandType::Response));
+    }
+
+    let started = Instant::now();
+    let received = replay(&records, Dispatcher::new(), Pace::AsFastAsPossible);
+    assert!(started.elapsed() < Duration::from_millis(300));
+    assert!(received.recv_timeout(Duration::from_secs(5)).is_ok());
+}
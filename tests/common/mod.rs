@@ -0,0 +1,50 @@
+// Helpers shared by the integration tests, not every test uses all of them
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use uuid::Uuid;
+
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::protocol::commands::{AdaCommand, AdaCommandType};
+
+/// A command for `network` and `node` carrying `items` as its data.
+pub fn command(command_type: AdaCommandType, network: Uuid, node: Option<Uuid>, items: &[(&str, Data)]) -> AdaCommand {
+    let mut data = Container::new();
+    for (key, value) in items {
+        data.add_item(key, value.clone());
+    }
+    let mut command = AdaCommand::new(command_type);
+    command.header.network = Some(network);
+    command.header.node = node;
+    command.data = Some(data);
+    command
+}
+
+/// A fresh directory under the system temp directory, removed with everything in it once dropped.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new(prefix: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("ada-{}-{}", prefix, Uuid::new_v4()));
+        fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn join(&self, name: impl AsRef<Path>) -> PathBuf {
+        self.path.join(name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,53 @@
+use uuid::Uuid;
+
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::network::data_container::Data;
+use ada_executor::protocol::commands::AdaCommandType;
+
+mod common;
+
+use common::command;
+
+fn export(dispatcher: &mut Dispatcher, network: Uuid, status: bool) -> String {
+    let response = dispatcher.dispatch(&command(AdaCommandType::ExportGraph, network, None, &[("status", Data::Boolean(status))]));
+    match response.data.unwrap().get_item("dot") {
+        Some(Data::Text(dot)) => dot.clone(),
+        other => panic!("expected DOT source, got {:?}", other),
+    }
+}
+
+#[test]
+fn exports_nodes_edges_and_last_status() {
+    let mut dispatcher = Dispatcher::new();
+    let network = Uuid::new_v4();
+    dispatcher.dispatch(&command(AdaCommandType::CreateNetwork, network, None, &[]));
+
+    let (add, gate) = (Uuid::new_v4(), Uuid::new_v4());
+    dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(add), &[("type", Data::Text("math.add".to_string()))]));
+    dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(gate), &[("type", Data::Text("flow.gate".to_string()))]));
+    dispatcher.dispatch(&command(AdaCommandType::ConnectNodes, network, Some(add), &[
+        ("from_port", Data::Text("result".to_string())),
+        ("to", Data::Text(gate.to_string())),
+        ("to_port", Data::Text("value".to_string())),
+    ]));
+
+    let dot = export(&mut dispatcher, network, true);
+    assert!(dot.starts_with(&format!("digraph \"{}\" {{", network)));
+    assert!(dot.contains(&format!("\"{}\" [label=\"math.add\\n{}\"];", add, add)));
+    assert!(dot.contains(&format!("\"{}\" -> \"{}\" [label=\"result -> value\"];", add, gate)));
+
+    // The gate stays closed, so it runs without output
+    dispatcher.dispatch(&command(AdaCommandType::PropagateNode, network, Some(add), &[("a", Data::Integer(1)), ("b", Data::Integer(2))]));
+    let dot = export(&mut dispatcher, network, true);
+    assert!(dot.contains(&format!("\"{}\" [label=\"math.add\\n{}\", style=filled, fillcolor=palegreen];", add, add)));
+    assert!(dot.contains(&format!("\"{}\" [label=\"flow.gate\\n{}\", style=filled, fillcolor=khaki];", gate, gate)));
+
+    assert!(!export(&mut dispatcher, network, false).contains("fillcolor"));
+}
+
+#[test]
+fn unknown_network_is_an_error() {
+    let mut dispatcher = Dispatcher::new();
+    let response = dispatcher.dispatch(&command(AdaCommandType::ExportGraph, Uuid::new_v4(), None, &[]));
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+}
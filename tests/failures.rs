@@ -0,0 +1,168 @@
+use uuid::Uuid;
+
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::network::graph::{Edge, FailurePolicy, Graph, NodeStatus};
+use ada_executor::network::node::{Node, NodeContext, NodeError, NodeErrorKind, NodeFunction};
+use ada_executor::network::registry::{NodeDescriptor, NodeRegistry};
+use ada_executor::network::run::{RunOptions, RunStatus};
+use ada_executor::protocol::commands::AdaCommandType;
+
+mod common;
+
+use common::command;
+
+fn edge(from: Uuid, from_port: &str, to: Uuid) -> Edge {
+    Edge { from, from_port: from_port.to_string(), to, to_port: "value".to_string() }
+}
+
+fn value() -> Container {
+    let mut container = Container::new();
+    container.add_item("value", Data::Integer(1));
+    container
+}
+
+fn pass(id: Uuid) -> Node {
+    Node::new(id, |input| input)
+}
+
+fn failing(id: Uuid) -> Node {
+    Node::try_new(id, |_| Err(NodeError::transient("service unavailable")))
+}
+
+// start feeds `fail` and `sibling`, `fail` feeds `child` through `value` and `handler` through `error`,
+// `child` feeds `grandchild`
+struct Pipeline {
+    graph: Graph,
+    start: Uuid,
+    fail: Uuid,
+    sibling: Uuid,
+    child: Uuid,
+    grandchild: Uuid,
+    handler: Uuid,
+}
+
+fn pipeline(failure_policy: FailurePolicy) -> Pipeline {
+    let [start, fail, sibling, child, grandchild, handler] = [(); 6].map(|_| Uuid::new_v4());
+    let mut graph = Graph::new().with_failure_policy(failure_policy);
+    for id in [start, sibling, child, grandchild, handler] {
+        graph.add_node(pass(id));
+    }
+    graph.add_node(failing(fail));
+    for edge in [
+        edge(start, "value", fail),
+        edge(start, "value", sibling),
+        edge(fail, "value", child),
+        edge(fail, "error", handler),
+        edge(child, "value", grandchild),
+    ] {
+        graph.connect(edge).unwrap();
+    }
+    Pipeline { graph, start, fail, sibling, child, grandchild, handler }
+}
+
+#[test]
+fn panics_become_node_errors() {
+    let id = Uuid::new_v4();
+    let node = Node::new(id, |_| panic!("boom"));
+    assert_eq!(node.try_execute(Container::new()).unwrap_err().message, "panicked: boom");
+    assert_eq!(node.execute(Container::new()).get_item("error"), Some(&Data::Text("panicked: boom".to_string())));
+
+    let mut graph = Graph::new();
+    graph.add_node(node);
+    let report = graph.run(&id, Container::new(), false, &RunOptions::new()).unwrap();
+    assert_eq!(report.status, RunStatus::Completed);
+    assert_eq!(report.statuses[&id], NodeStatus::Failed);
+    assert_eq!(report.errors[&id].kind, NodeErrorKind::Internal);
+}
+
+#[test]
+fn dependents_of_a_failed_node_are_skipped() {
+    let pipeline = pipeline(FailurePolicy::SkipDependents);
+    let report = pipeline.graph.run(&pipeline.start, value(), true, &RunOptions::new()).unwrap();
+
+    assert_eq!(report.status, RunStatus::Completed);
+    assert_eq!(report.statuses[&pipeline.fail], NodeStatus::Failed);
+    assert_eq!(report.statuses[&pipeline.sibling], NodeStatus::Completed);
+    for skipped in [pipeline.child, pipeline.grandchild, pipeline.handler] {
+        assert_eq!(report.statuses[&skipped], NodeStatus::Skipped);
+    }
+    assert_eq!(report.failed(), vec![pipeline.fail]);
+    assert_eq!(report.errors[&pipeline.fail].message, "service unavailable");
+}
+
+#[test]
+fn errors_are_routed_to_the_error_port() {
+    let pipeline = pipeline(FailurePolicy::ErrorPort("error".to_string()));
+    let report = pipeline.graph.run(&pipeline.start, value(), true, &RunOptions::new()).unwrap();
+
+    assert_eq!(report.status, RunStatus::Completed);
+    assert_eq!(report.statuses[&pipeline.handler], NodeStatus::Completed);
+    assert_eq!(report.outputs[&pipeline.handler].get_item("value"), Some(&Data::Text("service unavailable".to_string())));
+    assert_eq!(report.statuses[&pipeline.child], NodeStatus::Skipped);
+    assert_eq!(report.statuses[&pipeline.grandchild], NodeStatus::Skipped);
+    assert_eq!(report.statuses[&pipeline.sibling], NodeStatus::Completed);
+}
+
+#[test]
+fn abort_stops_the_run() {
+    let pipeline = pipeline(FailurePolicy::Abort);
+    let report = pipeline.graph.run(&pipeline.start, value(), true, &RunOptions::new()).unwrap();
+
+    assert_eq!(report.status, RunStatus::Failed);
+    assert_eq!(report.statuses[&pipeline.fail], NodeStatus::Failed);
+    for id in [pipeline.child, pipeline.grandchild, pipeline.handler] {
+        assert_eq!(report.statuses[&id], NodeStatus::Skipped);
+    }
+    assert_eq!(report.failed(), vec![pipeline.fail]);
+}
+
+#[test]
+fn failure_policies_parse_their_display_form() {
+    for policy in [FailurePolicy::SkipDependents, FailurePolicy::ErrorPort("failed".to_string()), FailurePolicy::Abort] {
+        assert_eq!(policy.to_string().parse::<FailurePolicy>().unwrap(), policy);
+    }
+    assert_eq!("error_port".parse::<FailurePolicy>().unwrap(), FailurePolicy::ErrorPort("error".to_string()));
+    assert!("retry".parse::<FailurePolicy>().is_err());
+    assert!("error_port:".parse::<FailurePolicy>().is_err());
+}
+
+// `test.panic` panics on every execution
+fn registry() -> NodeRegistry {
+    let mut registry = NodeRegistry::with_builtins();
+    registry.register_fn(NodeDescriptor::new("test.panic", "Panics", &[], &[]), |_| {
+        let execute_fn: NodeFunction = Box::new(|_: Container, _: &NodeContext| panic!("no divisor"));
+        Ok(execute_fn)
+    });
+    registry
+}
+
+#[test]
+fn run_response_lists_failed_nodes() {
+    let mut dispatcher = Dispatcher::new().with_registry(registry());
+    let network = Uuid::new_v4();
+    let response = dispatcher.dispatch(&command(AdaCommandType::CreateNetwork, network, None, &[("failure_policy", Data::Text("abort".to_string()))]));
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+
+    let (start, divide) = (Uuid::new_v4(), Uuid::new_v4());
+    dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(start), &[("type", Data::Text("flow.pass".to_string()))]));
+    dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(divide), &[("type", Data::Text("test.panic".to_string()))]));
+    dispatcher.dispatch(&command(AdaCommandType::ConnectNodes, network, Some(start), &[
+        ("from_port", Data::Text("value".to_string())),
+        ("to", Data::Text(divide.to_string())),
+        ("to_port", Data::Text("value".to_string())),
+    ]));
+
+    // The panic is contained, the dispatcher keeps answering
+    for _ in 0..2 {
+        let response = dispatcher.dispatch(&command(AdaCommandType::PropagateNode, network, Some(start), &[("value", Data::Integer(1))]));
+        let data = response.data.unwrap();
+        assert_eq!(data.get_item("run.status"), Some(&Data::Text("failed".to_string())));
+        assert_eq!(data.get_item("run.failed"), Some(&Data::Text(divide.to_string())));
+        assert_eq!(data.get_item(&format!("run.error.{}", divide)), Some(&Data::Text("internal: panicked: no divisor".to_string())));
+        assert_eq!(data.get_item(&format!("{}.value", start)), Some(&Data::Integer(1)));
+    }
+
+    let response = dispatcher.dispatch(&command(AdaCommandType::CreateNetwork, Uuid::new_v4(), None, &[("failure_policy", Data::Text("ignore".to_string()))]));
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+}
@@ -0,0 +1,168 @@
+#![cfg(feature = "serde")]
+
+use std::fs;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::network::graph::{Edge, FailurePolicy, Graph};
+use ada_executor::network::node::{Backoff, ExecutionPolicy, Node, NodeErrorKind, RetryPolicy};
+use ada_executor::network::registry::NodeRegistry;
+use ada_executor::protocol::commands::{AdaCommand, AdaCommandType};
+
+mod common;
+
+use common::TempDir;
+
+// `add` sums its `a` and `b` inputs and feeds `format`, which renders the sum
+fn graph(registry: &NodeRegistry) -> (Graph, Uuid, Uuid) {
+    let (add, format) = (Uuid::new_v4(), Uuid::new_v4());
+    let mut config = Container::new();
+    config.add_item("template", Data::Text("sum={value}".to_string()));
+
+    let mut graph = Graph::new();
+    graph.add_node(registry.create_node("math.add", add, Container::new()).unwrap());
+    graph.add_node(registry.create_node("text.format", format, config).unwrap());
+    graph.connect(Edge { from: add, from_port: "result".to_string(), to: format, to_port: "value".to_string() }).unwrap();
+    (graph, add, format)
+}
+
+fn arguments() -> Container {
+    let mut arguments = Container::new();
+    arguments.add_item("a", Data::Integer(2));
+    arguments.add_item("b", Data::Integer(3));
+    arguments
+}
+
+#[test]
+fn saved_graph_loads_with_same_shape_and_behaviour() {
+    let registry = NodeRegistry::with_builtins();
+    let (graph, add, format) = graph(&registry);
+    let dir = TempDir::new("graphs");
+    let path = dir.join("pipeline.json");
+    graph.save(&path).unwrap();
+
+    let loaded = Graph::load(&path, &registry).unwrap();
+    assert_eq!(loaded.id(), graph.id());
+    assert_eq!(loaded.edges(), graph.edges());
+    assert_eq!(loaded.get_node(&format).unwrap().node_type(), "text.format");
+    assert_eq!(loaded.get_node(&format).unwrap().config(), graph.get_node(&format).unwrap().config());
+
+    let outputs = loaded.propagate(&add, arguments()).unwrap();
+    assert_eq!(outputs[&format].get_item("result"), Some(&Data::Text("sum=5".to_string())));
+
+    // Saving again reproduces the file byte for byte
+    let copy = path.with_file_name("copy.json");
+    loaded.save(&copy).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), fs::read_to_string(&copy).unwrap());
+}
+
+#[test]
+fn execution_policies_survive_a_round_trip() {
+    let registry = NodeRegistry::with_builtins();
+    let (graph, add, format) = graph(&registry);
+    let mut graph = graph.with_failure_policy(FailurePolicy::ErrorPort("failed".to_string()));
+    let policy = ExecutionPolicy {
+        timeout: Some(Duration::from_millis(500)),
+        retry: RetryPolicy {
+            max_attempts: 4,
+            backoff: Backoff::Exponential { initial: Duration::from_millis(20), max: Duration::from_millis(200) },
+            retry_on: vec![NodeErrorKind::Transient, NodeErrorKind::Io],
+        },
+    };
+    let node = graph.remove_node(&add).unwrap();
+    graph.add_node(registry.create_node(node.node_type(), add, node.config().clone()).unwrap().with_policy(policy.clone()));
+    let dir = TempDir::new("graphs");
+    let path = dir.join("policies.json");
+    graph.save(&path).unwrap();
+
+    let loaded = Graph::load(&path, &registry).unwrap();
+    assert_eq!(loaded.failure_policy(), &FailurePolicy::ErrorPort("failed".to_string()));
+    assert_eq!(loaded.get_node(&add).unwrap().policy(), &policy);
+    assert_eq!(loaded.get_node(&format).unwrap().policy(), &ExecutionPolicy::default());
+}
+
+#[test]
+fn retry_settings_left_out_take_the_add_node_defaults() {
+    let dir = TempDir::new("graphs");
+    let path = dir.join("retry.json");
+    let (network, node) = (Uuid::new_v4(), Uuid::new_v4());
+    fs::write(&path, format!(
+        r#"{{"id": "{}", "nodes": [{{"id": "{}", "type": "flow.pass", "retry": {{"max_attempts": 3}}}}]}}"#,
+        network, node,
+    )).unwrap();
+
+    let loaded = Graph::load(&path, &NodeRegistry::with_builtins()).unwrap();
+    let mut config = Container::new();
+    config.add_item("node.retry.max_attempts", Data::Integer(3));
+    assert_eq!(loaded.get_node(&node).unwrap().policy(), &ExecutionPolicy::take_from(&mut config).unwrap());
+}
+
+#[test]
+fn node_state_survives_a_round_trip() {
+    let registry = NodeRegistry::with_builtins();
+    let counter = Uuid::new_v4();
+    let mut graph = Graph::new();
+    graph.add_node(registry.create_node("state.counter", counter, Container::new()).unwrap());
+    for _ in 0..3 {
+        graph.execute(&counter, Container::new()).unwrap();
+    }
+    let dir = TempDir::new("graphs");
+    let path = dir.join("state.json");
+    graph.save(&path).unwrap();
+
+    let loaded = Graph::load(&path, &registry).unwrap();
+    assert_eq!(loaded.state().get(&counter), graph.state().get(&counter));
+    let output = loaded.execute(&counter, Container::new()).unwrap();
+    assert_eq!(output.get_item("count"), Some(&Data::Integer(4)));
+}
+
+#[test]
+fn closure_nodes_cannot_be_saved() {
+    let mut graph = Graph::new();
+    graph.add_node(Node::new(Uuid::new_v4(), |input| input));
+    assert!(graph.save(&TempDir::new("graphs").join("closure.json")).is_err());
+}
+
+#[test]
+fn invalid_files_are_rejected() {
+    let dir = TempDir::new("graphs");
+    let registry = NodeRegistry::with_builtins();
+    let network = Uuid::new_v4();
+    let node = Uuid::new_v4();
+
+    let unknown_type = dir.join("unknown_type.json");
+    fs::write(&unknown_type, format!(r#"{{"id": "{}", "nodes": [{{"id": "{}", "type": "no.such"}}]}}"#, network, node)).unwrap();
+    assert!(Graph::load(&unknown_type, &registry).is_err());
+
+    let dangling_edge = dir.join("dangling_edge.json");
+    fs::write(&dangling_edge, format!(
+        r#"{{"id": "{}", "nodes": [{{"id": "{}", "type": "flow.pass"}}], "edges": [{{"from": "{}", "from_port": "a", "to": "{}", "to_port": "b"}}]}}"#,
+        network, node, node, Uuid::new_v4(),
+    )).unwrap();
+    assert!(Graph::load(&dangling_edge, &registry).is_err());
+}
+
+#[test]
+fn dispatcher_preloads_graph_directory() {
+    let dir = TempDir::new("graphs");
+    let (graph, add, format) = graph(&NodeRegistry::with_builtins());
+    graph.save(&dir.join("pipeline.json")).unwrap();
+    fs::write(dir.join("README.txt"), "not a graph").unwrap();
+
+    let mut dispatcher = Dispatcher::new();
+    assert_eq!(dispatcher.load_networks(dir.path()).unwrap(), 1);
+
+    let mut command = AdaCommand::new(AdaCommandType::PropagateNode);
+    command.header.network = Some(graph.id());
+    command.header.node = Some(add);
+    command.data = Some(arguments());
+    let response = dispatcher.dispatch(&command);
+    let data = response.data.unwrap();
+    assert_eq!(data.get_item(&format!("{}.result", format)), Some(&Data::Text("sum=5".to_string())));
+
+    // The same network cannot be loaded twice
+    assert!(dispatcher.load_networks(dir.path()).is_err());
+}
@@ -0,0 +1,198 @@
+#![cfg(feature = "http")]
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use ada_executor::command_processor::access_control::AccessControl;
+use ada_executor::command_processor::command_scheduler;
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::protocol::auth::KeyStore;
+use ada_executor::protocol::capture::{self, Capture, CaptureRecord};
+use ada_executor::protocol::http_gateway::HttpGateway;
+use ada_executor::protocol::wal::WriteAheadLog;
+
+mod common;
+
+fn start_gateway(dispatcher: Dispatcher, key_store: Option<KeyStore>) -> SocketAddr {
+    start_gateway_with(dispatcher, |gateway| match key_store {
+        Some(key_store) => gateway.with_key_store(Arc::new(key_store)),
+        None => gateway,
+    })
+}
+
+fn start_gateway_with(dispatcher: Dispatcher, configure: impl FnOnce(HttpGateway) -> HttpGateway) -> SocketAddr {
+    let queue = Arc::new(Mutex::new(Vec::new()));
+    command_scheduler::listen(Arc::clone(&queue), dispatcher);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let gateway = configure(HttpGateway::new(&address.to_string(), queue));
+    thread::spawn(move || gateway.serve(listener));
+    address
+}
+
+// Sends one request and returns the status with the parsed JSON body
+fn request(address: SocketAddr, method: &str, path: &str, authorization: Option<&str>, body: &str) -> (u16, Value) {
+    let authorization = authorization.map(|value| format!("Authorization: {}\r\n", value)).unwrap_or_default();
+    send(address, &format!("{} {} HTTP/1.1\r\nHost: test\r\n{}Content-Length: {}\r\n\r\n{}", method, path, authorization, body.len(), body))
+}
+
+// Writes `request` as is and returns the status with the parsed JSON body
+fn send(address: SocketAddr, request: &str) -> (u16, Value) {
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let status = response.split_whitespace().nth(1).unwrap().parse().unwrap();
+    let body = response.split_once("\r\n\r\n").unwrap().1;
+    (status, serde_json::from_str(body).unwrap())
+}
+
+#[test]
+fn routes_build_and_run_a_network() {
+    let address = start_gateway(Dispatcher::new(), None);
+    let network = Uuid::new_v4();
+    let (status, body) = request(address, "POST", "/networks", None, &json!({ "network": network }).to_string());
+    assert_eq!(status, 200);
+    assert_eq!(body["header"]["command_type"], "Response");
+    assert_eq!(body["data"]["network"], json!({ "Text": network.to_string() }));
+
+    let (status, body) = request(address, "GET", "/node-types", None, "");
+    assert_eq!(status, 200);
+    assert!(body["data"].as_object().unwrap().contains_key("math.add.description"));
+
+    let node = Uuid::new_v4();
+    let (status, _) = request(address, "POST", &format!("/networks/{}/nodes/{}", network, node), None, r#"{ "type": { "Text": "math.add" } }"#);
+    assert_eq!(status, 200);
+    // Containers map to JSON objects of key to typed value and back
+    let (status, body) = request(address, "POST", &format!("/networks/{}/nodes/{}/execute", network, node), None, r#"{ "a": { "Integer": 2 }, "b": { "Integer": 3 } }"#);
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(body["data"]["result"], json!({ "Integer": 5 }));
+
+    let (status, body) = request(address, "GET", &format!("/networks/{}/dot", network), None, "");
+    assert_eq!(status, 200);
+    assert!(body["data"]["dot"]["Text"].as_str().unwrap().contains(&node.to_string()));
+
+    let (status, _) = request(address, "DELETE", &format!("/networks/{}/nodes/{}", network, node), None, "");
+    assert_eq!(status, 200);
+    let (status, _) = request(address, "DELETE", &format!("/networks/{}", network), None, "");
+    assert_eq!(status, 200);
+    let (status, body) = request(address, "DELETE", &format!("/networks/{}", network), None, "");
+    assert_eq!(status, 400);
+    assert_eq!(body["data"]["error"], json!({ "Text": "unknown network" }));
+}
+
+#[test]
+fn serialized_commands_are_accepted() {
+    let address = start_gateway(Dispatcher::new(), None);
+    let network = Uuid::new_v4();
+    let command = json!({ "header": { "version": 0, "command_type": "CreateNetwork", "network": network } });
+    let (status, body) = request(address, "POST", "/commands", None, &command.to_string());
+    assert_eq!(status, 200);
+    assert_eq!(body["header"]["network"], json!(network));
+}
+
+#[test]
+fn malformed_requests_are_rejected() {
+    let address = start_gateway(Dispatcher::new(), None);
+    let (status, _) = request(address, "GET", "/no/such/route", None, "");
+    assert_eq!(status, 404);
+    let (status, body) = request(address, "DELETE", "/networks/not-a-uuid", None, "");
+    assert_eq!(status, 400);
+    assert!(body["error"].as_str().unwrap().contains("invalid uuid"));
+    let (status, _) = request(address, "POST", "/commands", None, "{ not json");
+    assert_eq!(status, 400);
+    let (status, _) = request(address, "POST", &format!("/networks/{}/nodes", Uuid::new_v4()), None, r#"{ "type": "untyped" }"#);
+    assert_eq!(status, 400);
+}
+
+#[test]
+fn oversized_headers_are_refused() {
+    let address = start_gateway(Dispatcher::new(), None);
+    let long_line = format!("GET /node-types HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "x".repeat(10 * 1024));
+    assert_eq!(send(address, &long_line).0, 431);
+    let many_headers = format!("GET /node-types HTTP/1.1\r\n{}\r\n", "X-Padding: x\r\n".repeat(101));
+    assert_eq!(send(address, &many_headers).0, 431);
+    let long_request_line = format!("GET /{} HTTP/1.1\r\n\r\n", "x".repeat(10 * 1024));
+    assert_eq!(send(address, &long_request_line).0, 400);
+    // Right at the limits is fine
+    let headers = format!("GET /node-types HTTP/1.1\r\n{}\r\n", "X-Padding: x\r\n".repeat(100));
+    assert_eq!(send(address, &headers).0, 200);
+}
+
+#[test]
+fn idle_clients_time_out() {
+    let address = start_gateway_with(Dispatcher::new(), |gateway| gateway.with_read_timeout(Duration::from_millis(200)));
+    let started = Instant::now();
+    let (status, body) = send(address, "GET /node-types HTTP/1.1\r\nHost: te");
+    assert_eq!(status, 408);
+    assert!(body["error"].as_str().unwrap().contains("timed out"));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn bearer_tokens_authenticate_requests() {
+    let dir = common::TempDir::new("http");
+    let acl_path = dir.join("acl");
+    fs::write(&acl_path, "team-a * admin\n").unwrap();
+    let access_control = AccessControl::load(&acl_path).unwrap();
+    let keys = HashMap::from([("team-a".to_string(), "secret-a".to_string()), ("team-b".to_string(), "secret-b".to_string())]);
+    let address = start_gateway(Dispatcher::new().with_access_control(access_control), Some(KeyStore::new(keys)));
+
+    let (status, _) = request(address, "POST", "/networks", None, "");
+    assert_eq!(status, 401);
+    let (status, _) = request(address, "POST", "/networks", Some("Bearer team-a:wrong"), "");
+    assert_eq!(status, 401);
+    let (status, _) = request(address, "POST", "/networks", Some("Basic team-a:secret-a"), "");
+    assert_eq!(status, 401);
+    let (status, _) = request(address, "POST", "/networks", Some("Bearer team-a:secret-a"), "");
+    assert_eq!(status, 200);
+    // Authenticated, but not granted anything by the access control list
+    let (status, body) = request(address, "POST", "/networks", Some("Bearer team-b:secret-b"), "");
+    assert_eq!(status, 403);
+    assert_eq!(body["header"]["command_type"], "PermissionDenied");
+}
+
+#[test]
+fn requests_are_logged_before_they_are_queued() {
+    let dir = common::TempDir::new("http-wal");
+    let (wal, _) = WriteAheadLog::open(dir.path()).unwrap();
+    let wal = Arc::new(wal);
+    // Nothing marks the commands done without a scheduler owning the log
+    let address = start_gateway_with(Dispatcher::new(), |gateway| gateway.with_wal(Arc::clone(&wal)));
+
+    let (status, _) = request(address, "POST", "/networks", None, "");
+    assert_eq!(status, 200);
+    assert_eq!(wal.pending(), 1);
+}
+
+#[test]
+fn accepted_requests_are_captured() {
+    let dir = common::TempDir::new("http-capture");
+    let path = dir.join("capture.bin");
+    let keys = HashMap::from([("team-a".to_string(), "secret-a".to_string())]);
+    let address = start_gateway_with(Dispatcher::new(), |gateway| {
+        gateway.with_key_store(Arc::new(KeyStore::new(keys))).with_capture(Arc::new(Capture::open(&path).unwrap()))
+    });
+
+    let (status, _) = request(address, "POST", "/networks", Some("Bearer team-a:wrong"), "");
+    assert_eq!(status, 401);
+    let (status, _) = request(address, "POST", "/networks", Some("Bearer team-a:secret-a"), "");
+    assert_eq!(status, 200);
+
+    // Only the authenticated request, as a connection closed right after it
+    let records = capture::read(&path).unwrap();
+    assert_eq!(records.len(), 2);
+    let connection = match &records[0] {
+        CaptureRecord::Frame { connection, identity: Some(identity), .. } if identity == "team-a" => *connection,
+        other => panic!("expected the request, got {:?}", other),
+    };
+    assert!(matches!(&records[1], CaptureRecord::Closed { connection: closed, .. } if *closed == connection));
+}
@@ -0,0 +1,102 @@
+#![cfg(all(unix, feature = "process"))]
+
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::network::nodes::process;
+use ada_executor::network::registry::NodeRegistry;
+
+fn registry() -> NodeRegistry {
+    let mut registry = NodeRegistry::with_builtins();
+    process::register(&mut registry, vec!["sh".to_string()]);
+    registry
+}
+
+fn text(value: &str) -> Data {
+    Data::Text(value.to_string())
+}
+
+// `sh -c <script>` with the extra config items
+fn run(script: &str, config: &[(&str, Data)], input: &[(&str, Data)]) -> Container {
+    let mut node_config = Container::new();
+    node_config.add_item("command", text("sh"));
+    node_config.add_item("arg0", text("-c"));
+    node_config.add_item("arg1", text(script));
+    for (key, value) in config {
+        node_config.add_item(key, value.clone());
+    }
+    let node = registry().create_node("process.exec", Uuid::new_v4(), node_config).unwrap();
+    let mut arguments = Container::new();
+    for (key, value) in input {
+        arguments.add_item(key, value.clone());
+    }
+    node.execute(arguments)
+}
+
+#[test]
+fn stdin_json_in_and_stdout_json_out() {
+    // Echoes the input object back with an extra field
+    let output = run(r#"sed 's/}$/,"done":true}/'"#, &[], &[("count", Data::Integer(3)), ("ratio", Data::Float(0.5)), ("name", text("ada"))]);
+    assert_eq!(output.get_item("count"), Some(&Data::Integer(3)));
+    assert_eq!(output.get_item("ratio"), Some(&Data::Float(0.5)));
+    assert_eq!(output.get_item("name"), Some(&text("ada")));
+    assert_eq!(output.get_item("done"), Some(&Data::Boolean(true)));
+    assert_eq!(output.get_item("exit_code"), Some(&Data::Integer(0)));
+}
+
+#[test]
+fn inputs_as_arguments_and_environment() {
+    let config = [("arg2", text("sh")), ("arg3", text("{name}")), ("env", Data::Boolean(true)), ("stdin", Data::Boolean(false))];
+    let output = run(r#"echo "$1 $ADA_INPUT_MY_COUNT""#, &config, &[("name", text("ada")), ("my-count", Data::Integer(2))]);
+    assert_eq!(output.get_item("stdout"), Some(&text("ada 2")));
+}
+
+#[test]
+fn stderr_is_captured() {
+    let output = run("echo warning >&2", &[("stdin", Data::Boolean(false))], &[]);
+    assert_eq!(output.get_item("exit_code"), Some(&Data::Integer(0)));
+    assert_eq!(output.get_item("stderr"), Some(&text("warning")));
+}
+
+#[test]
+fn non_zero_exit_fails_with_code_and_stderr() {
+    let output = run("echo broken >&2; exit 3", &[("stdin", Data::Boolean(false))], &[]);
+    assert!(matches!(output.get_item("error"), Some(Data::Text(error)) if error.contains("code 3") && error.contains("broken")), "{:?}", output);
+    assert_eq!(output.get_item("exit_code"), None);
+}
+
+#[test]
+fn captured_output_is_limited() {
+    // 2 MiB, twice what is kept
+    let flood = "head -c 2097152 /dev/zero | tr '\\0' x";
+    let output = run(flood, &[("stdin", Data::Boolean(false))], &[]);
+    assert!(matches!(output.get_item("error"), Some(Data::Text(error)) if error.contains("stdout exceeds")), "{:?}", output);
+
+    let output = run(&format!("{} >&2", flood), &[("stdin", Data::Boolean(false))], &[]);
+    assert!(matches!(output.get_item("stderr"), Some(Data::Text(stderr)) if stderr.len() == 1024 * 1024));
+}
+
+#[test]
+fn slow_process_is_killed_after_timeout() {
+    let started = Instant::now();
+    let output = run("echo starting >&2; sleep 5", &[("timeout_ms", Data::Integer(300)), ("stdin", Data::Boolean(false))], &[]);
+    assert!(started.elapsed() < Duration::from_secs(4));
+    assert!(matches!(output.get_item("error"), Some(Data::Text(error)) if error.contains("timed out") && error.contains("starting")), "{:?}", output);
+    assert_eq!(output.get_item("exit_code"), None);
+}
+
+#[test]
+fn missing_command_is_rejected() {
+    assert!(registry().create_node("process.exec", Uuid::new_v4(), Container::new()).is_err());
+}
+
+#[test]
+fn only_allowed_commands_run() {
+    let mut config = Container::new();
+    config.add_item("command", text("rm"));
+    assert!(registry().create_node("process.exec", Uuid::new_v4(), config).is_err());
+    // Clients cannot start processes unless the operator registered the node type
+    assert!(NodeRegistry::with_builtins().descriptor("process.exec").is_none());
+}
@@ -0,0 +1,43 @@
+use ada_executor::network::data_container::Data;
+use ada_executor::protocol::protocol_parser::parse_data_container;
+
+const INTEGER: u8 = 1;
+const TEXT: u8 = 3;
+
+// Data field with `text` holding the given bytes and `number` set to 7 after it
+fn data_field(text: &[u8]) -> Vec<u8> {
+    let mut field = Vec::new();
+    field.extend_from_slice(&4u16.to_be_bytes());
+    field.extend_from_slice(b"text");
+    field.push(TEXT);
+    field.extend_from_slice(text);
+    field.extend_from_slice(&6u16.to_be_bytes());
+    field.extend_from_slice(b"number");
+    field.push(INTEGER);
+    field.extend_from_slice(&7i32.to_be_bytes());
+
+    let mut buffer = ((field.len() + 4) as u32).to_be_bytes().to_vec();
+    buffer.extend_from_slice(&field);
+    buffer
+}
+
+#[test]
+fn invalid_utf8_text_does_not_shift_the_following_items() {
+    let container = parse_data_container(data_field(&[b'a', 0xff, 0xfe, b'b', 0]), 0).unwrap();
+    assert_eq!(container.get_item("text"), Some(&Data::Text("a\u{fffd}\u{fffd}b".to_string())));
+    assert_eq!(container.get_item("number"), Some(&Data::Integer(7)));
+}
+
+#[test]
+fn unterminated_text_ends_with_the_data_field() {
+    let mut buffer = 10u32.to_be_bytes().to_vec();
+    buffer.extend_from_slice(&1u16.to_be_bytes());
+    buffer.push(b't');
+    buffer.push(TEXT);
+    buffer.extend_from_slice(b"ab");
+    // Bytes after the data field are not part of the text
+    buffer.extend_from_slice(b"cd\0");
+
+    let container = parse_data_container(buffer, 0).unwrap();
+    assert_eq!(container.get_item("t"), Some(&Data::Text("ab".to_string())));
+}
@@ -0,0 +1,96 @@
+use uuid::Uuid;
+
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::network::node::{NodeContext, NodeFunction};
+use ada_executor::network::registry::{NodeDescriptor, NodeRegistry};
+use ada_executor::protocol::commands::AdaCommandType;
+
+mod common;
+
+use common::command;
+
+// `test.scale` multiplies its `value` input by the configured `factor`
+fn registry() -> NodeRegistry {
+    let mut registry = NodeRegistry::new();
+    registry.register_fn(NodeDescriptor::new("test.scale", "Scales value by factor", &["value"], &["value"]), |config| {
+        let factor = match config.get_item("factor") {
+            Some(Data::Integer(factor)) => *factor,
+            _ => return Err("factor must be an integer".to_string()),
+        };
+        let scale: NodeFunction = Box::new(move |input: Container, _: &NodeContext| {
+            let mut output = Container::new();
+            if let Some(Data::Integer(value)) = input.get_item("value") {
+                output.add_item("value", Data::Integer(value * factor));
+            }
+            Ok(output)
+        });
+        Ok(scale)
+    });
+    registry
+}
+
+fn add_scale_node(dispatcher: &mut Dispatcher, network: Uuid, factor: i32) -> Uuid {
+    let node = Uuid::new_v4();
+    let response = dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(node), &[
+        ("type", Data::Text("test.scale".to_string())),
+        ("factor", Data::Integer(factor)),
+    ]));
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+    node
+}
+
+#[test]
+fn builds_and_propagates_declared_graph() {
+    let mut dispatcher = Dispatcher::new().with_registry(registry());
+    let network = Uuid::new_v4();
+    dispatcher.dispatch(&command(AdaCommandType::CreateNetwork, network, None, &[]));
+
+    let double = add_scale_node(&mut dispatcher, network, 2);
+    let triple = add_scale_node(&mut dispatcher, network, 3);
+    let response = dispatcher.dispatch(&command(AdaCommandType::ConnectNodes, network, Some(double), &[
+        ("from_port", Data::Text("value".to_string())),
+        ("to", Data::Text(triple.to_string())),
+        ("to_port", Data::Text("value".to_string())),
+    ]));
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+
+    let response = dispatcher.dispatch(&command(AdaCommandType::PropagateNode, network, Some(double), &[("value", Data::Integer(5))]));
+    let output = response.data.unwrap();
+    assert!(matches!(output.get_item(&format!("{}.value", double)), Some(Data::Integer(10))));
+    assert!(matches!(output.get_item(&format!("{}.value", triple)), Some(Data::Integer(30))));
+}
+
+#[test]
+fn rejects_unknown_type_and_invalid_config() {
+    let mut dispatcher = Dispatcher::new().with_registry(registry());
+    let network = Uuid::new_v4();
+    dispatcher.dispatch(&command(AdaCommandType::CreateNetwork, network, None, &[]));
+
+    let response = dispatcher.dispatch(&command(AdaCommandType::AddNode, network, None, &[("type", Data::Text("test.missing".to_string()))]));
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+
+    let response = dispatcher.dispatch(&command(AdaCommandType::AddNode, network, None, &[
+        ("type", Data::Text("test.scale".to_string())),
+        ("factor", Data::Text("two".to_string())),
+    ]));
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+}
+
+#[test]
+fn rejects_node_ids_already_in_the_network() {
+    let mut dispatcher = Dispatcher::new().with_registry(registry());
+    let network = Uuid::new_v4();
+    dispatcher.dispatch(&command(AdaCommandType::CreateNetwork, network, None, &[]));
+    let double = add_scale_node(&mut dispatcher, network, 2);
+
+    let response = dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(double), &[
+        ("type", Data::Text("test.scale".to_string())),
+        ("factor", Data::Integer(3)),
+    ]));
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+
+    // The original node is still in place
+    let response = dispatcher.dispatch(&command(AdaCommandType::ExecuteNode, network, Some(double), &[("value", Data::Integer(5))]));
+    assert!(matches!(response.data.unwrap().get_item("value"), Some(Data::Integer(10))));
+}
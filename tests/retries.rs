@@ -0,0 +1,181 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::network::graph::{Edge, Graph, NodeStatus};
+use ada_executor::network::node::{Backoff, ExecutionPolicy, Node, NodeContext, NodeError, NodeErrorKind, NodeFunction, RetryPolicy};
+use ada_executor::network::registry::{NodeDescriptor, NodeRegistry};
+use ada_executor::network::run::{RunOptions, RunStatus};
+use ada_executor::protocol::commands::AdaCommandType;
+
+mod common;
+
+use common::command;
+
+// Fails with `kind` until it was called `failures` times, then passes its input through
+fn flaky(id: Uuid, failures: u32, kind: NodeErrorKind) -> (Node, Arc<AtomicU32>) {
+    let calls = Arc::new(AtomicU32::new(0));
+    let counter = Arc::clone(&calls);
+    let node = Node::try_new(id, move |input| {
+        if counter.fetch_add(1, Ordering::SeqCst) < failures {
+            return Err(NodeError::new(kind, "service unavailable"));
+        }
+        Ok(input)
+    });
+    (node, calls)
+}
+
+fn retry(max_attempts: u32) -> ExecutionPolicy {
+    ExecutionPolicy {
+        retry: RetryPolicy { max_attempts, backoff: Backoff::Fixed(Duration::from_millis(5)), ..RetryPolicy::default() },
+        ..ExecutionPolicy::default()
+    }
+}
+
+fn value() -> Container {
+    let mut container = Container::new();
+    container.add_item("value", Data::Integer(1));
+    container
+}
+
+#[test]
+fn transient_failures_are_retried_and_traced() {
+    let id = Uuid::new_v4();
+    let (node, calls) = flaky(id, 2, NodeErrorKind::Transient);
+    let mut graph = Graph::new();
+    graph.add_node(node.with_policy(retry(3)));
+
+    let report = graph.run(&id, value(), false, &RunOptions::new()).unwrap();
+    assert_eq!(report.status, RunStatus::Completed);
+    assert_eq!(report.statuses[&id], NodeStatus::Completed);
+    assert_eq!(report.outputs[&id], value());
+    assert!(report.errors.is_empty());
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    let attempts: Vec<_> = report.trace.iter().map(|entry| (entry.node, entry.attempt, entry.status)).collect();
+    assert_eq!(attempts, vec![(id, 1, NodeStatus::Failed), (id, 2, NodeStatus::Failed), (id, 3, NodeStatus::Completed)]);
+    assert_eq!(report.trace[0].error.as_ref().unwrap().kind, NodeErrorKind::Transient);
+    assert!(report.trace[2].error.is_none());
+}
+
+#[test]
+fn retries_stop_after_max_attempts() {
+    let (id, next) = (Uuid::new_v4(), Uuid::new_v4());
+    let (node, calls) = flaky(id, 5, NodeErrorKind::Transient);
+    let mut graph = Graph::new();
+    graph.add_node(node.with_policy(retry(2)));
+    graph.add_node(Node::new(next, |input| input));
+    graph.connect(Edge { from: id, from_port: "value".to_string(), to: next, to_port: "value".to_string() }).unwrap();
+
+    let report = graph.run(&id, value(), true, &RunOptions::new()).unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(report.statuses[&id], NodeStatus::Failed);
+    assert_eq!(report.errors[&id].message, "service unavailable");
+    assert_eq!(report.statuses[&next], NodeStatus::Skipped);
+    assert_eq!(report.trace.len(), 2);
+    assert_eq!(report.incomplete(), vec![next]);
+    assert_eq!(report.failed(), vec![id]);
+}
+
+#[test]
+fn only_selected_error_kinds_are_retried() {
+    let id = Uuid::new_v4();
+    let (node, calls) = flaky(id, 1, NodeErrorKind::InvalidInput);
+    let mut graph = Graph::new();
+    graph.add_node(node.with_policy(retry(3)));
+
+    let report = graph.run(&id, value(), false, &RunOptions::new()).unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(report.statuses[&id], NodeStatus::Failed);
+    assert_eq!(graph.execute(&id, value()).unwrap(), value());
+}
+
+#[test]
+fn timed_out_attempts_are_retried() {
+    let id = Uuid::new_v4();
+    let calls = Arc::new(AtomicU32::new(0));
+    let counter = Arc::clone(&calls);
+    let node = Node::new(id, move |input| {
+        if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+            thread::sleep(Duration::from_secs(5));
+        }
+        input
+    });
+    let mut graph = Graph::new();
+    graph.add_node(node.with_policy(ExecutionPolicy { timeout: Some(Duration::from_millis(50)), ..retry(2) }));
+
+    let report = graph.run(&id, value(), false, &RunOptions::new()).unwrap();
+    assert_eq!(report.statuses[&id], NodeStatus::Completed);
+    assert_eq!(report.trace[0].status, NodeStatus::TimedOut);
+    assert_eq!(report.trace[0].error.as_ref().unwrap().kind, NodeErrorKind::Timeout);
+    assert_eq!(report.trace[1].status, NodeStatus::Completed);
+}
+
+#[test]
+fn exponential_backoff_doubles_up_to_its_cap() {
+    let backoff = Backoff::Exponential { initial: Duration::from_millis(10), max: Duration::from_millis(25) };
+    let delays: Vec<_> = (1..=4).map(|retry| backoff.delay(retry).as_millis()).collect();
+    assert_eq!(delays, vec![10, 20, 25, 25]);
+    assert_eq!(Backoff::Fixed(Duration::from_millis(7)).delay(3), Duration::from_millis(7));
+}
+
+// `test.flaky` fails transiently as often as its `failures` config says, counted across all its nodes
+fn registry() -> NodeRegistry {
+    let mut registry = NodeRegistry::with_builtins();
+    registry.register_fn(NodeDescriptor::new("test.flaky", "Fails `failures` times", &[], &[]), |config| {
+        let failures = match config.get_item("failures") {
+            Some(Data::Integer(failures)) => *failures as u32,
+            _ => 0,
+        };
+        let calls = AtomicU32::new(0);
+        let execute_fn: NodeFunction = Box::new(move |input: Container, _: &NodeContext| {
+            if calls.fetch_add(1, Ordering::SeqCst) < failures {
+                return Err(NodeError::transient("try again"));
+            }
+            Ok(input)
+        });
+        Ok(execute_fn)
+    });
+    registry
+}
+
+#[test]
+fn add_node_configures_retries() {
+    let mut dispatcher = Dispatcher::new().with_registry(registry());
+    let network = Uuid::new_v4();
+    dispatcher.dispatch(&command(AdaCommandType::CreateNetwork, network, None, &[]));
+
+    let (patient, impatient) = (Uuid::new_v4(), Uuid::new_v4());
+    for (node, attempts) in [(patient, 3), (impatient, 1)] {
+        let response = dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(node), &[
+            ("type", Data::Text("test.flaky".to_string())),
+            ("failures", Data::Integer(2)),
+            ("node.retry.max_attempts", Data::Integer(attempts)),
+            ("node.retry.backoff", Data::Text("exponential".to_string())),
+            ("node.retry.delay_ms", Data::Integer(1)),
+            ("node.retry.on", Data::Text("transient, io".to_string())),
+        ]));
+        assert!(matches!(response.header.command_type, AdaCommandType::Response));
+    }
+
+    let response = dispatcher.dispatch(&command(AdaCommandType::ExecuteNode, network, Some(patient), &[("value", Data::Integer(4))]));
+    let data = response.data.unwrap();
+    assert_eq!(data.get_item("value"), Some(&Data::Integer(4)));
+    assert_eq!(data.get_item("run.status"), Some(&Data::Text("completed".to_string())));
+
+    let response = dispatcher.dispatch(&command(AdaCommandType::ExecuteNode, network, Some(impatient), &[("value", Data::Integer(4))]));
+    let data = response.data.unwrap();
+    assert_eq!(data.get_item("error"), Some(&Data::Text("try again".to_string())));
+    assert_eq!(data.get_item("value"), None);
+
+    let response = dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(Uuid::new_v4()), &[
+        ("type", Data::Text("flow.pass".to_string())),
+        ("node.retry.on", Data::Text("sometimes".to_string())),
+    ]));
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+}
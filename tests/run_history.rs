@@ -0,0 +1,162 @@
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::network::graph::{Edge, Graph, NodeStatus};
+use ada_executor::network::node::{Node, NodeContext, NodeFunction};
+use ada_executor::network::registry::{NodeDescriptor, NodeRegistry};
+use ada_executor::network::run::RunOptions;
+use ada_executor::protocol::commands::{AdaCommand, AdaCommandType, CommandOrigin};
+
+mod common;
+
+use common::command;
+
+#[test]
+fn trace_records_inputs_and_outputs() {
+    let (double, sink) = (Uuid::new_v4(), Uuid::new_v4());
+    let mut graph = Graph::new();
+    graph.add_node(Node::new(double, |input| {
+        let mut output = Container::new();
+        if let Some(Data::Integer(value)) = input.get_item("value") {
+            output.add_item("value", Data::Integer(value * 2));
+        }
+        output
+    }));
+    graph.add_node(Node::new(sink, |_| Container::new()));
+    graph.connect(Edge { from: double, from_port: "value".to_string(), to: sink, to_port: "in".to_string() }).unwrap();
+
+    let mut arguments = Container::new();
+    arguments.add_item("value", Data::Integer(21));
+    let report = graph.run(&double, arguments.clone(), true, &RunOptions::new()).unwrap();
+    assert_eq!(report.network, graph.id());
+    assert_eq!(report.node, double);
+    assert_eq!(report.trace.len(), 2);
+
+    let first = &report.trace[0];
+    assert_eq!((first.node, first.status), (double, NodeStatus::Completed));
+    assert_eq!(first.input, arguments);
+    assert_eq!(first.output.as_ref().unwrap().get_item("value"), Some(&Data::Integer(42)));
+    let second = &report.trace[1];
+    assert_eq!((second.node, second.status), (sink, NodeStatus::NoOutput));
+    assert_eq!(second.input.get_item("in"), Some(&Data::Integer(42)));
+    assert!(first.started_at <= second.started_at);
+}
+
+// `test.sleep` waits for its `ms` config before passing its input on
+fn registry() -> NodeRegistry {
+    let mut registry = NodeRegistry::with_builtins();
+    registry.register_fn(NodeDescriptor::new("test.sleep", "Sleeps for `ms`", &[], &[]), |config| {
+        let duration = match config.get_item("ms") {
+            Some(Data::Integer(ms)) => Duration::from_millis(*ms as u64),
+            _ => Duration::ZERO,
+        };
+        let execute_fn: NodeFunction = Box::new(move |input: Container, _: &NodeContext| {
+            thread::sleep(duration);
+            Ok(input)
+        });
+        Ok(execute_fn)
+    });
+    registry
+}
+
+fn query(dispatcher: &mut Dispatcher, network: Uuid, run: &str) -> AdaCommand {
+    dispatcher.dispatch(&command(AdaCommandType::QueryRun, network, None, &[("run", Data::Text(run.to_string()))]))
+}
+
+fn text(data: &Container, key: &str) -> String {
+    match data.get_item(key) {
+        Some(Data::Text(value)) => value.clone(),
+        other => panic!("expected text under {}, got {:?}", key, other),
+    }
+}
+
+// Network with a `flow.pass` node feeding a `test.sleep` node
+fn setup(dispatcher: &mut Dispatcher, sleep_ms: i32) -> (Uuid, Uuid, Uuid) {
+    let network = Uuid::new_v4();
+    dispatcher.dispatch(&command(AdaCommandType::CreateNetwork, network, None, &[]));
+    let (pass, sleep) = (Uuid::new_v4(), Uuid::new_v4());
+    dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(pass), &[("type", Data::Text("flow.pass".to_string()))]));
+    dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(sleep), &[
+        ("type", Data::Text("test.sleep".to_string())),
+        ("ms", Data::Integer(sleep_ms)),
+    ]));
+    dispatcher.dispatch(&command(AdaCommandType::ConnectNodes, network, Some(pass), &[
+        ("from_port", Data::Text("value".to_string())),
+        ("to", Data::Text(sleep.to_string())),
+        ("to_port", Data::Text("value".to_string())),
+    ]));
+    (network, pass, sleep)
+}
+
+#[test]
+fn query_run_returns_result_and_trace() {
+    let mut dispatcher = Dispatcher::new().with_registry(registry());
+    let (network, pass, sleep) = setup(&mut dispatcher, 0);
+
+    let response = dispatcher.dispatch(&command(AdaCommandType::PropagateNode, network, Some(pass), &[("value", Data::Integer(7))]));
+    let run = text(&response.data.unwrap(), "run.id");
+
+    let response = query(&mut dispatcher, network, &run);
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+    let data = response.data.unwrap();
+    assert_eq!(text(&data, "run.status"), "completed");
+    assert_eq!(text(&data, "run.node"), pass.to_string());
+    assert_eq!(data.get_item(&format!("{}.value", sleep)), Some(&Data::Integer(7)));
+    assert!(matches!(data.get_item("run.started_at"), Some(Data::Float(seconds)) if *seconds > 0.0));
+    assert_eq!(data.get_item("trace.count"), Some(&Data::Integer(2)));
+    assert_eq!(text(&data, "trace.0.node"), pass.to_string());
+    assert_eq!(text(&data, "trace.0.status"), "completed");
+    assert_eq!(data.get_item("trace.0.attempt"), Some(&Data::Integer(1)));
+    assert_eq!(data.get_item("trace.0.input.value"), Some(&Data::Integer(7)));
+    assert_eq!(data.get_item("trace.1.output.value"), Some(&Data::Integer(7)));
+
+    // Runs belong to their network
+    let response = query(&mut dispatcher, Uuid::new_v4(), &run);
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+    let response = query(&mut dispatcher, network, "not-a-run");
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+}
+
+#[test]
+fn running_runs_report_their_status() {
+    let mut dispatcher = Dispatcher::new().with_registry(registry());
+    let (network, pass, _) = setup(&mut dispatcher, 200);
+
+    let run = Uuid::new_v4().to_string();
+    let (sender, receiver) = mpsc::channel();
+    let mut propagate = command(AdaCommandType::PropagateNode, network, Some(pass), &[("run.id", Data::Text(run.clone()))]);
+    propagate.origin = Some(CommandOrigin::new(sender));
+    dispatcher.handle(propagate);
+
+    let response = query(&mut dispatcher, network, &run);
+    assert_eq!(text(&response.data.unwrap(), "run.status"), "running");
+
+    receiver.recv_timeout(Duration::from_secs(2)).unwrap();
+    let response = query(&mut dispatcher, network, &run);
+    assert_eq!(text(&response.data.unwrap(), "run.status"), "completed");
+}
+
+#[test]
+fn history_is_bounded_per_network() {
+    let mut dispatcher = Dispatcher::new().with_registry(registry()).with_history_limit(2);
+    let (network, pass, _) = setup(&mut dispatcher, 0);
+
+    let runs: Vec<String> = (0..3)
+        .map(|_| {
+            let response = dispatcher.dispatch(&command(AdaCommandType::ExecuteNode, network, Some(pass), &[]));
+            text(&response.data.unwrap(), "run.id")
+        })
+        .collect();
+    assert!(matches!(query(&mut dispatcher, network, &runs[0]).header.command_type, AdaCommandType::Error));
+    for run in &runs[1..] {
+        assert!(matches!(query(&mut dispatcher, network, run).header.command_type, AdaCommandType::Response));
+    }
+
+    dispatcher.dispatch(&command(AdaCommandType::DeleteNetwork, network, None, &[]));
+    assert!(matches!(query(&mut dispatcher, network, &runs[2]).header.command_type, AdaCommandType::Error));
+}
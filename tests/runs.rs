@@ -0,0 +1,193 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::network::graph::{Edge, Graph, NodeStatus};
+use ada_executor::network::node::{ExecutionPolicy, Node, NodeContext, NodeFunction};
+use ada_executor::network::registry::{NodeDescriptor, NodeRegistry};
+use ada_executor::network::run::{RunOptions, RunStatus};
+use ada_executor::protocol::commands::{AdaCommandType, CommandOrigin};
+
+mod common;
+
+use common::command;
+
+fn sleeping(id: Uuid, duration: Duration) -> Node {
+    Node::new(id, move |input| {
+        thread::sleep(duration);
+        input
+    })
+}
+
+fn edge(from: Uuid, to: Uuid) -> Edge {
+    Edge { from, from_port: "value".to_string(), to, to_port: "value".to_string() }
+}
+
+fn value() -> Container {
+    let mut container = Container::new();
+    container.add_item("value", Data::Integer(1));
+    container
+}
+
+#[test]
+fn node_timeout_does_not_stop_the_run() {
+    let (slow, next) = (Uuid::new_v4(), Uuid::new_v4());
+    let mut graph = Graph::new();
+    graph.add_node(sleeping(slow, Duration::from_secs(5)).with_policy(ExecutionPolicy { timeout: Some(Duration::from_millis(50)), ..ExecutionPolicy::default() }));
+    graph.add_node(Node::new(next, |input| input));
+    graph.connect(edge(slow, next)).unwrap();
+
+    let started = Instant::now();
+    let report = graph.run(&slow, value(), true, &RunOptions::new()).unwrap();
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(report.status, RunStatus::Completed);
+    assert_eq!(report.statuses[&slow], NodeStatus::TimedOut);
+    assert_eq!(report.statuses[&next], NodeStatus::Skipped);
+    let mut incomplete = vec![slow, next];
+    incomplete.sort();
+    assert_eq!(report.incomplete(), incomplete);
+}
+
+#[test]
+fn run_timeout_stops_remaining_nodes() {
+    let (first, second, third) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let mut graph = Graph::new();
+    for id in [first, second, third] {
+        graph.add_node(sleeping(id, Duration::from_millis(100)));
+    }
+    graph.connect(edge(first, second)).unwrap();
+    graph.connect(edge(second, third)).unwrap();
+
+    let options = RunOptions { timeout: Some(Duration::from_millis(150)), ..RunOptions::new() };
+    let report = graph.run(&first, value(), true, &options).unwrap();
+    assert_eq!(report.status, RunStatus::TimedOut);
+    assert_eq!(report.statuses[&first], NodeStatus::Completed);
+    assert_eq!(report.statuses[&second], NodeStatus::TimedOut);
+    assert_eq!(report.statuses[&third], NodeStatus::TimedOut);
+    assert!(report.outputs.contains_key(&first));
+    assert!(!report.outputs.contains_key(&third));
+}
+
+#[test]
+fn cancellation_reaches_the_running_node() {
+    let observed = Arc::new(AtomicBool::new(false));
+    let node = Uuid::new_v4();
+    let observed_by_node = Arc::clone(&observed);
+    let execute_fn: NodeFunction = Box::new(move |input: Container, context: &NodeContext| {
+        while !context.is_cancelled() {
+            thread::sleep(Duration::from_millis(5));
+        }
+        observed_by_node.store(true, Ordering::SeqCst);
+        Ok(input)
+    });
+    let mut graph = Graph::new();
+    graph.add_node(Node::with_type(node, "test.wait", Container::new(), execute_fn));
+
+    let options = RunOptions::new();
+    let token = options.token.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        token.cancel();
+    });
+    let report = graph.run(&node, Container::new(), false, &options).unwrap();
+    assert_eq!(report.status, RunStatus::Cancelled);
+    assert_eq!(report.incomplete(), vec![node]);
+
+    thread::sleep(Duration::from_millis(50));
+    assert!(observed.load(Ordering::SeqCst));
+}
+
+// `test.wait` returns its input once cancelled
+fn registry() -> NodeRegistry {
+    let mut registry = NodeRegistry::with_builtins();
+    registry.register_fn(NodeDescriptor::new("test.wait", "Waits for cancellation", &[], &[]), |_| {
+        let execute_fn: NodeFunction = Box::new(|input: Container, context: &NodeContext| {
+            while !context.is_cancelled() {
+                thread::sleep(Duration::from_millis(5));
+            }
+            Ok(input)
+        });
+        Ok(execute_fn)
+    });
+    registry
+}
+
+fn text(data: &Container, key: &str) -> String {
+    match data.get_item(key) {
+        Some(Data::Text(value)) => value.clone(),
+        other => panic!("expected text under {}, got {:?}", key, other),
+    }
+}
+
+#[test]
+fn cancel_run_command_reports_incomplete_nodes() {
+    let mut dispatcher = Dispatcher::new().with_registry(registry());
+    let network = Uuid::new_v4();
+    dispatcher.dispatch(&command(AdaCommandType::CreateNetwork, network, None, &[]));
+    let (pass, wait) = (Uuid::new_v4(), Uuid::new_v4());
+    dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(pass), &[("type", Data::Text("flow.pass".to_string()))]));
+    dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(wait), &[("type", Data::Text("test.wait".to_string()))]));
+    dispatcher.dispatch(&command(AdaCommandType::ConnectNodes, network, Some(pass), &[
+        ("from_port", Data::Text("value".to_string())),
+        ("to", Data::Text(wait.to_string())),
+        ("to_port", Data::Text("value".to_string())),
+    ]));
+
+    let run = Uuid::new_v4();
+    let (run_sender, run_receiver) = mpsc::channel();
+    let mut propagate = command(AdaCommandType::PropagateNode, network, Some(pass), &[("run.id", Data::Text(run.to_string())), ("value", Data::Integer(1))]);
+    propagate.origin = Some(CommandOrigin::new(run_sender));
+    dispatcher.handle(propagate);
+    thread::sleep(Duration::from_millis(50));
+    assert!(run_receiver.try_recv().is_err(), "run answered before it finished");
+
+    let (cancel_sender, cancel_receiver) = mpsc::channel();
+    let mut cancel = command(AdaCommandType::CancelRun, network, None, &[("run", Data::Text(run.to_string()))]);
+    cancel.origin = Some(CommandOrigin::new(cancel_sender));
+    dispatcher.handle(cancel);
+
+    let cancelled = cancel_receiver.recv_timeout(Duration::from_secs(2)).unwrap().data.unwrap();
+    assert_eq!(text(&cancelled, "run.id"), run.to_string());
+    assert_eq!(text(&cancelled, "run.status"), "cancelled");
+    assert_eq!(text(&cancelled, "run.incomplete"), wait.to_string());
+
+    let response = run_receiver.recv_timeout(Duration::from_secs(2)).unwrap().data.unwrap();
+    assert_eq!(text(&response, "run.status"), "cancelled");
+    assert_eq!(response.get_item(&format!("{}.value", pass)), Some(&Data::Integer(1)));
+
+    // The run is gone once it stopped
+    let response = dispatcher.dispatch(&command(AdaCommandType::CancelRun, network, None, &[("run", Data::Text(run.to_string()))]));
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+}
+
+#[test]
+fn node_and_run_timeouts_from_commands() {
+    let mut dispatcher = Dispatcher::new().with_registry(registry());
+    let network = Uuid::new_v4();
+    dispatcher.dispatch(&command(AdaCommandType::CreateNetwork, network, None, &[]));
+    let wait = Uuid::new_v4();
+    dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(wait), &[
+        ("type", Data::Text("test.wait".to_string())),
+        ("node.timeout_ms", Data::Integer(50)),
+    ]));
+
+    let response = dispatcher.dispatch(&command(AdaCommandType::ExecuteNode, network, Some(wait), &[]));
+    let data = response.data.unwrap();
+    assert_eq!(text(&data, "run.status"), "completed");
+    assert_eq!(text(&data, "run.incomplete"), wait.to_string());
+
+    let response = dispatcher.dispatch(&command(AdaCommandType::ExecuteNode, network, Some(wait), &[("run.timeout_ms", Data::Integer(20))]));
+    assert_eq!(text(&response.data.unwrap(), "run.status"), "timed_out");
+
+    let response = dispatcher.dispatch(&command(AdaCommandType::AddNode, network, None, &[
+        ("type", Data::Text("flow.pass".to_string())),
+        ("node.timeout_ms", Data::Text("soon".to_string())),
+    ]));
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+}
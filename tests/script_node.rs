@@ -0,0 +1,61 @@
+#![cfg(feature = "scripting")]
+
+use uuid::Uuid;
+
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::network::node::Node;
+use ada_executor::network::registry::NodeRegistry;
+
+fn script_node(script: &str, config: &[(&str, Data)]) -> Result<Node, String> {
+    let mut node_config = Container::new();
+    node_config.add_item("script", Data::Text(script.to_string()));
+    for (key, value) in config {
+        node_config.add_item(key, value.clone());
+    }
+    NodeRegistry::with_builtins().create_node("script.rhai", Uuid::new_v4(), node_config)
+}
+
+#[test]
+fn returned_map_becomes_output() {
+    let node = script_node(r#"#{ total: input.a + input.b, label: `sum of ${input.name}`, big: input.a > 1 }"#, &[]).unwrap();
+    let mut input = Container::new();
+    input.add_item("a", Data::Integer(2));
+    input.add_item("b", Data::Integer(3));
+    input.add_item("name", Data::Text("ada".to_string()));
+
+    let output = node.execute(input);
+    assert_eq!(output.get_item("total"), Some(&Data::Integer(5)));
+    assert_eq!(output.get_item("label"), Some(&Data::Text("sum of ada".to_string())));
+    assert_eq!(output.get_item("big"), Some(&Data::Boolean(true)));
+}
+
+#[test]
+fn other_values_become_result() {
+    let node = script_node("input.x * 1.5", &[]).unwrap();
+    let mut input = Container::new();
+    input.add_item("x", Data::Float(2.0));
+    assert_eq!(node.execute(input).get_item("result"), Some(&Data::Float(3.0)));
+    assert!(script_node("let unused = 1;", &[]).unwrap().execute(Container::new()).is_empty());
+}
+
+#[test]
+fn runaway_script_hits_operation_limit() {
+    let node = script_node("loop { }", &[("max_operations", Data::Integer(1_000))]).unwrap();
+    let output = node.execute(Container::new());
+    assert!(matches!(output.get_item("error"), Some(Data::Text(error)) if error.contains("operations")));
+}
+
+#[test]
+fn growing_data_hits_size_limits() {
+    let output = script_node(r#"let text = "x"; loop { text += text; }"#, &[]).unwrap().execute(Container::new());
+    assert!(matches!(output.get_item("error"), Some(Data::Text(error)) if error.contains("too large")), "{:?}", output);
+
+    let output = script_node("let items = [0]; loop { items += items; }", &[]).unwrap().execute(Container::new());
+    assert!(matches!(output.get_item("error"), Some(Data::Text(error)) if error.contains("too large")), "{:?}", output);
+}
+
+#[test]
+fn syntax_errors_are_reported_on_creation() {
+    assert!(script_node("let = ;", &[]).is_err());
+    assert!(script_node("1", &[("max_operations", Data::Integer(0))]).is_err());
+}
@@ -0,0 +1,59 @@
+#![cfg(feature = "serde")]
+
+use uuid::Uuid;
+
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::protocol::commands::{AdaCommand, AdaCommandType};
+
+fn container() -> Container {
+    let mut container = Container::new();
+    container.add_item("int", Data::Integer(-7));
+    container.add_item("float", Data::Float(2.5));
+    container.add_item("text", Data::Text("höhe".to_string()));
+    container.add_item("flag", Data::Boolean(true));
+    container
+}
+
+#[test]
+fn data_and_containers_survive_a_round_trip() {
+    for data in [Data::Integer(1), Data::Float(-0.25), Data::Text(String::new()), Data::Boolean(false)] {
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(serde_json::from_str::<Data>(&json).unwrap(), data);
+    }
+
+    let json = serde_json::to_value(container()).unwrap();
+    // Containers are plain maps of key to value
+    assert_eq!(json["int"], serde_json::json!({ "Integer": -7 }));
+    assert_eq!(serde_json::from_value::<Container>(json).unwrap(), container());
+}
+
+#[test]
+fn commands_survive_a_round_trip() {
+    let mut command = AdaCommand::new(AdaCommandType::PropagateNode);
+    command.header.network = Some(Uuid::new_v4());
+    command.header.node = Some(Uuid::new_v4());
+    command.data = Some(container());
+
+    let json = serde_json::to_string(&command).unwrap();
+    let parsed: AdaCommand = serde_json::from_str(&json).unwrap();
+    assert!(matches!(parsed.header.command_type, AdaCommandType::PropagateNode));
+    assert_eq!(parsed.header.network, command.header.network);
+    assert_eq!(parsed.header.node, command.header.node);
+    assert_eq!(parsed.data, command.data);
+    assert_eq!(parsed.to_bytes().len(), command.to_bytes().len());
+}
+
+#[test]
+fn command_types_serialize_by_name() {
+    for command_type in [AdaCommandType::CloseConnection, AdaCommandType::ExecuteNode, AdaCommandType::Unknown] {
+        let json = serde_json::to_string(&command_type).unwrap();
+        let parsed: AdaCommandType = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed as u8, command_type as u8);
+    }
+    assert_eq!(serde_json::to_string(&AdaCommandType::ExecuteNode).unwrap(), r#""ExecuteNode""#);
+
+    // Hand-written commands may leave out everything but the version and type
+    let parsed: AdaCommand = serde_json::from_str(r#"{ "header": { "version": 0, "command_type": "CreateNetwork" } }"#).unwrap();
+    assert!(matches!(parsed.header.command_type, AdaCommandType::CreateNetwork));
+    assert!(parsed.header.network.is_none() && parsed.data.is_none());
+}
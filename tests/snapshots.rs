@@ -0,0 +1,186 @@
+#![cfg(feature = "serde")]
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use ada_executor::command_processor::access_control::AccessControl;
+use ada_executor::command_processor::command_scheduler::{self, SchedulerOptions};
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::network::data_container::Data;
+use ada_executor::protocol::commands::{AdaCommand, AdaCommandType, CommandOrigin};
+
+mod common;
+
+use common::{command, TempDir};
+
+fn from(identity: &str, mut command: AdaCommand) -> AdaCommand {
+    let (reply, _) = mpsc::channel();
+    command.origin = Some(CommandOrigin::new(reply).with_identity(identity.to_string()));
+    command
+}
+
+// A network with a `state.counter` node, executed `count` times
+fn counter_network(dispatcher: &mut Dispatcher, count: usize) -> (Uuid, Uuid) {
+    let (network, counter) = (Uuid::new_v4(), Uuid::new_v4());
+    dispatcher.dispatch(&command(AdaCommandType::CreateNetwork, network, None, &[("failure_policy", Data::Text("abort".to_string()))]));
+    dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(counter), &[
+        ("type", Data::Text("state.counter".to_string())),
+        ("node.timeout_ms", Data::Integer(500)),
+    ]));
+    for _ in 0..count {
+        dispatcher.dispatch(&command(AdaCommandType::ExecuteNode, network, Some(counter), &[]));
+    }
+    (network, counter)
+}
+
+fn count(response: &AdaCommand) -> Option<&Data> {
+    response.data.as_ref().and_then(|data| data.get_item("count"))
+}
+
+#[test]
+fn networks_node_state_and_queue_survive_a_round_trip() {
+    let dir = TempDir::new("snapshots");
+    let path = dir.join("snapshot.json");
+    let mut dispatcher = Dispatcher::new();
+    let (network, counter) = counter_network(&mut dispatcher, 2);
+    let execute = command(AdaCommandType::ExecuteNode, network, Some(counter), &[]);
+    // Connections do not survive a restart, neither do commands tied to them
+    let close = from("alice", AdaCommand::new(AdaCommandType::CloseConnection));
+    // The write-ahead log replays its commands itself
+    let mut logged = from("alice", execute.clone());
+    logged.origin.as_mut().unwrap().wal_sequence = Some(1);
+    let pending = vec![from("alice", execute.clone()), execute.clone(), close, logged];
+    assert_eq!(dispatcher.snapshot(&path, &pending).unwrap(), 2);
+
+    let mut restored = Dispatcher::new();
+    let queued = restored.restore(&path).unwrap();
+    assert_eq!(queued.len(), 2);
+    assert_eq!(queued[0].origin.as_ref().unwrap().identity.as_deref(), Some("alice"));
+    assert!(queued[1].origin.is_none());
+    assert_eq!(queued[0].header.node, Some(counter));
+
+    // The counter continues where it stopped
+    assert_eq!(count(&restored.dispatch(&execute)), Some(&Data::Integer(3)));
+    let export = restored.dispatch(&command(AdaCommandType::ExportGraph, network, None, &[]));
+    assert!(matches!(export.header.command_type, AdaCommandType::Response));
+
+    // Snapshotting the restored executor reproduces the networks
+    let copy = path.with_file_name("copy.json");
+    let mut again = Dispatcher::new();
+    again.restore(&path).unwrap();
+    again.snapshot(&copy, &[]).unwrap();
+    let networks = |path: &PathBuf| {
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        json["networks"].clone()
+    };
+    assert_eq!(networks(&path), networks(&copy));
+}
+
+#[test]
+fn restored_commands_keep_their_identity() {
+    let dir = TempDir::new("snapshots");
+    let path = dir.join("snapshot.json");
+    let mut dispatcher = Dispatcher::new();
+    let (network, counter) = counter_network(&mut dispatcher, 0);
+    let execute = command(AdaCommandType::ExecuteNode, network, Some(counter), &[]);
+    dispatcher.snapshot(&path, &[from("mallory", execute)]).unwrap();
+
+    let acl_path = path.with_file_name("acl");
+    fs::write(&acl_path, format!("alice {} execute\n", network)).unwrap();
+    let mut restored = Dispatcher::new().with_access_control(AccessControl::load(&acl_path).unwrap());
+    let queued = restored.restore(&path).unwrap();
+    let response = restored.dispatch(&queued[0]);
+    assert!(matches!(response.header.command_type, AdaCommandType::PermissionDenied));
+}
+
+#[test]
+fn snapshot_command_writes_to_the_configured_path() {
+    let mut dispatcher = Dispatcher::new();
+    let network = Uuid::new_v4();
+    let snapshot = command(AdaCommandType::Snapshot, network, None, &[]);
+    let response = dispatcher.dispatch(&snapshot);
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+
+    let dir = TempDir::new("snapshots");
+    let path = dir.join("snapshot.json");
+    let mut dispatcher = Dispatcher::new().with_snapshot_path(path.clone());
+    counter_network(&mut dispatcher, 1);
+    let response = dispatcher.dispatch(&snapshot);
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+    assert_eq!(response.data.unwrap().get_item("networks"), Some(&Data::Integer(1)));
+    assert_eq!(Dispatcher::new().restore(&path).unwrap().len(), 0);
+}
+
+#[test]
+fn invalid_snapshots_are_rejected() {
+    let dir = TempDir::new("snapshots");
+    let path = dir.join("snapshot.json");
+    fs::write(&path, r#"{ "version": 99, "taken_at": 0 }"#).unwrap();
+    assert!(Dispatcher::new().restore(&path).is_err());
+    fs::write(&path, r#"{ "version": 1, "taken_at": 0, "networks": [{ "id": "5f0c6b5e-3a4e-4c43-9a55-3c1fd1ab7e1a", "nodes": [{ "id": "9b1d2c0e-6f0a-4a8e-8f57-1f1f8d5c2b10", "type": "no.such_type" }] }] }"#).unwrap();
+    assert!(Dispatcher::new().restore(&path).is_err());
+}
+
+#[test]
+fn scheduler_snapshots_commands_queued_behind_a_snapshot_command() {
+    let dir = TempDir::new("snapshots");
+    let path = dir.join("snapshot.json");
+    let mut dispatcher = Dispatcher::new().with_snapshot_path(path.clone());
+    let (network, counter) = counter_network(&mut dispatcher, 0);
+    let queue = Arc::new(Mutex::new(Vec::new()));
+    let (reply, responses) = mpsc::channel();
+    let mut snapshot = command(AdaCommandType::Snapshot, network, None, &[]);
+    snapshot.origin = Some(CommandOrigin::new(reply));
+    queue.lock().unwrap().extend([snapshot, command(AdaCommandType::ExecuteNode, network, Some(counter), &[])]);
+    command_scheduler::listen(Arc::clone(&queue), dispatcher);
+
+    let response = responses.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(response.data.unwrap().get_item("queued"), Some(&Data::Integer(1)));
+    let queued = Dispatcher::new().restore(&path).unwrap();
+    assert!(matches!(queued[0].header.command_type, AdaCommandType::ExecuteNode));
+}
+
+#[test]
+fn scheduler_snapshots_on_a_timer_and_on_shutdown() {
+    let dir = TempDir::new("snapshots");
+    let path = dir.join("snapshot.json");
+    let mut dispatcher = Dispatcher::new().with_snapshot_path(path.clone());
+    let (network, counter) = counter_network(&mut dispatcher, 0);
+    let queue = Arc::new(Mutex::new(Vec::new()));
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let options = SchedulerOptions {
+        snapshot_interval: Some(Duration::from_millis(50)),
+        shutdown: Some(Arc::clone(&shutdown)),
+        ..SchedulerOptions::default()
+    };
+    let scheduler = command_scheduler::start(Arc::clone(&queue), dispatcher, options);
+    thread::sleep(Duration::from_millis(200));
+    assert!(path.exists());
+
+    queue.lock().unwrap().push(command(AdaCommandType::ExecuteNode, network, Some(counter), &[]));
+    thread::sleep(Duration::from_millis(100));
+    // Stopping keeps what arrived after the scheduler's last look at the queue
+    let (reply, _) = mpsc::channel();
+    let mut late = command(AdaCommandType::ExecuteNode, network, Some(counter), &[]);
+    late.origin = Some(CommandOrigin::new(reply).with_identity("alice".to_string()));
+    {
+        let mut queue = queue.lock().unwrap();
+        queue.push(late);
+        shutdown.store(true, Ordering::SeqCst);
+    }
+    scheduler.join().unwrap();
+
+    let mut restored = Dispatcher::new();
+    let queued = restored.restore(&path).unwrap();
+    assert_eq!(queued.len(), 1);
+    assert_eq!(queued[0].origin.as_ref().unwrap().identity.as_deref(), Some("alice"));
+    let response = restored.dispatch(&command(AdaCommandType::ExecuteNode, network, Some(counter), &[]));
+    assert_eq!(count(&response), Some(&Data::Integer(2)));
+}
@@ -0,0 +1,147 @@
+use std::thread;
+
+use uuid::Uuid;
+
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::network::graph::Graph;
+use ada_executor::network::node::{Node, NodeContext, NodeFunction, CLOSURE_NODE_TYPE};
+use ada_executor::network::registry::NodeRegistry;
+use ada_executor::network::state::StateStore;
+use ada_executor::protocol::commands::AdaCommandType;
+
+mod common;
+
+use common::command;
+
+fn value(value: Data) -> Container {
+    let mut input = Container::new();
+    input.add_item("value", value);
+    input
+}
+
+#[test]
+fn state_is_scoped_to_node_and_network() {
+    let registry = NodeRegistry::with_builtins();
+    let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+    let mut graph = Graph::new();
+    graph.add_node(registry.create_node("state.counter", first, Container::new()).unwrap());
+    graph.add_node(registry.create_node("state.counter", second, Container::new()).unwrap());
+    // Another network with a node of the same id
+    let mut other = Graph::new();
+    other.add_node(registry.create_node("state.counter", first, Container::new()).unwrap());
+
+    for _ in 0..3 {
+        graph.execute(&first, Container::new()).unwrap();
+    }
+    graph.execute(&second, Container::new()).unwrap();
+    let output = other.execute(&first, Container::new()).unwrap();
+
+    assert_eq!(output.get_item("count"), Some(&Data::Integer(1)));
+    assert_eq!(graph.state().node(first).get::<i32>("count"), Some(3));
+    assert_eq!(graph.state().node(second).get::<i32>("count"), Some(1));
+    // A value of another type reads as unset
+    assert_eq!(graph.state().node(first).get::<String>("count"), None);
+
+    let mut reset = Container::new();
+    reset.add_item("reset", Data::Boolean(true));
+    assert_eq!(graph.execute(&first, reset).unwrap().get_item("count"), Some(&Data::Integer(0)));
+}
+
+#[test]
+fn concurrent_updates_are_not_lost() {
+    let store = StateStore::new();
+    let node = Uuid::new_v4();
+    let handles: Vec<_> = (0..8).map(|_| {
+        let state = store.node(node);
+        thread::spawn(move || {
+            for _ in 0..100 {
+                state.update("count", 0, |count: i32| count + 1);
+            }
+        })
+    }).collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(store.node(node).get::<i32>("count"), Some(800));
+}
+
+#[test]
+fn builtin_stateful_nodes() {
+    let registry = NodeRegistry::with_builtins();
+    let (accumulate, average, distinct) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let mut window = Container::new();
+    window.add_item("window", Data::Integer(2));
+    let mut graph = Graph::new();
+    graph.add_node(registry.create_node("state.accumulate", accumulate, Container::new()).unwrap());
+    graph.add_node(registry.create_node("state.moving_average", average, window).unwrap());
+    graph.add_node(registry.create_node("state.distinct", distinct, Container::new()).unwrap());
+
+    graph.execute(&accumulate, value(Data::Integer(2))).unwrap();
+    let sum = graph.execute(&accumulate, value(Data::Integer(3))).unwrap();
+    assert_eq!(sum.get_item("sum"), Some(&Data::Integer(5)));
+    let sum = graph.execute(&accumulate, value(Data::Float(0.5))).unwrap();
+    assert_eq!(sum.get_item("sum"), Some(&Data::Float(5.5)));
+    let error = graph.execute(&accumulate, value(Data::Text("x".to_string()))).unwrap();
+    assert!(error.get_item("error").is_some());
+
+    let averages: Vec<Option<Data>> = [1, 3, 8].iter()
+        .map(|number| graph.execute(&average, value(Data::Integer(*number))).unwrap().get_item("average").cloned())
+        .collect();
+    assert_eq!(averages, vec![Some(Data::Float(1.0)), Some(Data::Float(2.0)), Some(Data::Float(5.5))]);
+
+    let passed: Vec<bool> = [1, 1, 2, 1].iter()
+        .map(|number| !graph.execute(&distinct, value(Data::Integer(*number))).unwrap().is_empty())
+        .collect();
+    assert_eq!(passed, vec![true, false, true, true]);
+}
+
+#[test]
+fn custom_nodes_use_the_context_state() {
+    let node = Uuid::new_v4();
+    let execute_fn: NodeFunction = Box::new(|input: Container, context: &NodeContext| {
+        let mut seen = context.state().get::<String>("seen").unwrap_or_default();
+        if let Some(Data::Text(word)) = input.get_item("word") {
+            seen.push_str(word);
+        }
+        context.state().set("seen", seen.clone());
+        let mut output = Container::new();
+        output.add_item("seen", Data::Text(seen));
+        Ok(output)
+    });
+    let mut graph = Graph::new();
+    graph.add_node(Node::with_type(node, CLOSURE_NODE_TYPE, Container::new(), execute_fn));
+    for word in ["a", "b"] {
+        let mut input = Container::new();
+        input.add_item("word", Data::Text(word.to_string()));
+        graph.execute(&node, input).unwrap();
+    }
+    assert_eq!(graph.state().node(node).get::<String>("seen"), Some("ab".to_string()));
+}
+
+#[test]
+fn state_outlives_network_changes_but_not_its_node() {
+    let mut dispatcher = Dispatcher::new();
+    let (network, counter, sink) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    dispatcher.dispatch(&command(AdaCommandType::CreateNetwork, network, None, &[]));
+    dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(counter), &[("type", Data::Text("state.counter".to_string()))]));
+    let execute = command(AdaCommandType::ExecuteNode, network, Some(counter), &[]);
+    dispatcher.dispatch(&execute);
+
+    // Changing the network copies it, the copy keeps the counter's state
+    dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(sink), &[("type", Data::Text("sink.log".to_string()))]));
+    let edge = [
+        ("from_port", Data::Text("count".to_string())),
+        ("to", Data::Text(sink.to_string())),
+        ("to_port", Data::Text("count".to_string())),
+    ];
+    dispatcher.dispatch(&command(AdaCommandType::ConnectNodes, network, Some(counter), &edge));
+    let response = dispatcher.dispatch(&execute);
+    assert_eq!(response.data.unwrap().get_item("count"), Some(&Data::Integer(2)));
+
+    // A node added again under the same id starts over
+    dispatcher.dispatch(&command(AdaCommandType::RemoveNode, network, Some(counter), &[]));
+    dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(counter), &[("type", Data::Text("state.counter".to_string()))]));
+    let response = dispatcher.dispatch(&execute);
+    assert_eq!(response.data.unwrap().get_item("count"), Some(&Data::Integer(1)));
+}
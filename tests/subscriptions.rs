@@ -0,0 +1,193 @@
+use std::io::Write;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use ada_executor::command_processor::command_scheduler;
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::protocol::commands::{AdaCommand, AdaCommandType, CommandOrigin};
+use ada_executor::protocol::memory_transport::MemoryTransport;
+use ada_executor::protocol::protocol_parser::read_command;
+use ada_executor::protocol::transport::{self, ConnectionContext};
+
+mod common;
+
+use common::command;
+
+// A connection of its own, every frame for it arrives on the returned receiver
+fn connection() -> (CommandOrigin, Receiver<AdaCommand>) {
+    let (reply, receiver) = mpsc::channel();
+    (CommandOrigin::new(reply).with_connection(Uuid::new_v4()), receiver)
+}
+
+fn send(dispatcher: &mut Dispatcher, mut command: AdaCommand, origin: &CommandOrigin, receiver: &Receiver<AdaCommand>) -> AdaCommand {
+    command.origin = Some(origin.clone());
+    dispatcher.handle(command);
+    receiver.recv_timeout(Duration::from_secs(5)).unwrap()
+}
+
+fn text(data: &Container, key: &str) -> String {
+    match data.get_item(key) {
+        Some(Data::Text(value)) => value.clone(),
+        other => panic!("expected text under {}, got {:?}", key, other),
+    }
+}
+
+// A network with `math.add` feeding `flow.pass`, returns the network and both nodes
+fn network(dispatcher: &mut Dispatcher) -> (Uuid, Uuid, Uuid) {
+    let (network, add, pass) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    dispatcher.dispatch(&command(AdaCommandType::CreateNetwork, network, None, &[]));
+    dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(add), &[("type", Data::Text("math.add".to_string()))]));
+    dispatcher.dispatch(&command(AdaCommandType::AddNode, network, Some(pass), &[("type", Data::Text("flow.pass".to_string()))]));
+    let edge = [
+        ("from_port", Data::Text("result".to_string())),
+        ("to", Data::Text(pass.to_string())),
+        ("to_port", Data::Text("sum".to_string())),
+    ];
+    dispatcher.dispatch(&command(AdaCommandType::ConnectNodes, network, Some(add), &edge));
+    (network, add, pass)
+}
+
+fn propagate(network: Uuid, add: Uuid) -> AdaCommand {
+    command(AdaCommandType::PropagateNode, network, Some(add), &[("a", Data::Integer(2)), ("b", Data::Integer(3))])
+}
+
+// Frames the connection received before the run's response, which is returned last
+fn events_until_response(receiver: &Receiver<AdaCommand>) -> (Vec<AdaCommand>, AdaCommand) {
+    let mut events = Vec::new();
+    loop {
+        let frame = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        match frame.header.command_type {
+            AdaCommandType::Event => events.push(frame),
+            _ => return (events, frame),
+        }
+    }
+}
+
+#[test]
+fn network_subscription_receives_every_finished_node() {
+    let mut dispatcher = Dispatcher::new();
+    let (network, add, pass) = network(&mut dispatcher);
+    let (origin, receiver) = connection();
+
+    let response = send(&mut dispatcher, command(AdaCommandType::Subscribe, network, None, &[]), &origin, &receiver);
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+    let subscription = text(response.data.as_ref().unwrap(), "subscription");
+
+    let mut run = propagate(network, add);
+    run.origin = Some(origin.clone());
+    dispatcher.handle(run);
+    let (events, response) = events_until_response(&receiver);
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+    let run_id = text(response.data.as_ref().unwrap(), "run.id");
+
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].header.network, Some(network));
+    assert_eq!(events[0].header.node, Some(add));
+    assert_eq!(events[1].header.node, Some(pass));
+    let data = events[1].data.as_ref().unwrap();
+    assert_eq!(text(data, "subscription"), subscription);
+    assert_eq!(text(data, "run.id"), run_id);
+    assert_eq!(text(data, "status"), "completed");
+    assert_eq!(data.get_item("sum"), Some(&Data::Integer(5)));
+}
+
+#[test]
+fn node_and_port_subscriptions_filter_events() {
+    let mut dispatcher = Dispatcher::new();
+    let (network, add, pass) = network(&mut dispatcher);
+    let (origin, receiver) = connection();
+    send(&mut dispatcher, command(AdaCommandType::Subscribe, network, Some(pass), &[]), &origin, &receiver);
+    send(&mut dispatcher, command(AdaCommandType::Subscribe, network, Some(add), &[("port", Data::Text("missing".to_string()))]), &origin, &receiver);
+    send(&mut dispatcher, command(AdaCommandType::Subscribe, network, None, &[("port", Data::Text("result".to_string()))]), &origin, &receiver);
+
+    let mut run = propagate(network, add);
+    run.origin = Some(origin.clone());
+    dispatcher.handle(run);
+    let (events, _) = events_until_response(&receiver);
+
+    assert_eq!(events.len(), 2);
+    let port_event = events.iter().find(|event| event.header.node == Some(add)).unwrap();
+    let data = port_event.data.as_ref().unwrap();
+    assert_eq!(data.get_item("result"), Some(&Data::Integer(5)));
+    assert_eq!(data.get_item("sum"), None);
+    let node_event = events.iter().find(|event| event.header.node == Some(pass)).unwrap();
+    assert_eq!(node_event.data.as_ref().unwrap().get_item("sum"), Some(&Data::Integer(5)));
+}
+
+#[test]
+fn unsubscribe_and_closed_connections_stop_events() {
+    let mut dispatcher = Dispatcher::new();
+    let (network, add, _) = network(&mut dispatcher);
+    let (first, first_receiver) = connection();
+    let (second, second_receiver) = connection();
+    let response = send(&mut dispatcher, command(AdaCommandType::Subscribe, network, None, &[]), &first, &first_receiver);
+    let subscription = text(response.data.as_ref().unwrap(), "subscription");
+    send(&mut dispatcher, command(AdaCommandType::Subscribe, network, None, &[]), &second, &second_receiver);
+
+    // Only the subscribing connection may remove its subscription
+    let unsubscribe = command(AdaCommandType::Unsubscribe, network, None, &[("subscription", Data::Text(subscription))]);
+    let response = send(&mut dispatcher, unsubscribe.clone(), &second, &second_receiver);
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+    let response = send(&mut dispatcher, unsubscribe, &first, &first_receiver);
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+
+    let mut close = AdaCommand::new(AdaCommandType::CloseConnection);
+    close.origin = Some(second.clone());
+    dispatcher.handle(close);
+    second_receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+
+    let mut run = propagate(network, add);
+    run.origin = Some(first.clone());
+    dispatcher.handle(run);
+    let (events, _) = events_until_response(&first_receiver);
+    assert!(events.is_empty());
+    assert!(second_receiver.recv_timeout(Duration::from_millis(200)).is_err());
+}
+
+#[test]
+fn subscriptions_need_a_connection() {
+    let mut dispatcher = Dispatcher::new();
+    let (network, _, _) = network(&mut dispatcher);
+    let response = dispatcher.dispatch(&command(AdaCommandType::Subscribe, network, None, &[]));
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+    let response = dispatcher.dispatch(&command(AdaCommandType::Subscribe, network, Some(Uuid::new_v4()), &[]));
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+}
+
+#[test]
+fn events_are_pushed_over_the_transport() {
+    let queue = Arc::new(Mutex::new(Vec::new()));
+    let mut dispatcher = Dispatcher::new();
+    let (network, add, pass) = network(&mut dispatcher);
+    command_scheduler::listen(Arc::clone(&queue), dispatcher);
+
+    let memory_transport = Arc::new(MemoryTransport::new());
+    let server_transport = Arc::clone(&memory_transport);
+    thread::spawn(move || transport::serve(&*server_transport, ConnectionContext::new(queue)));
+
+    let mut watcher = memory_transport.connect();
+    watcher.write_all(&command(AdaCommandType::Subscribe, network, Some(pass), &[]).to_bytes()).unwrap();
+    let response = read_command(&mut watcher).unwrap();
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+
+    // Another connection starts the run, the watcher is told about it
+    let mut runner = memory_transport.connect();
+    runner.write_all(&propagate(network, add).to_bytes()).unwrap();
+    let response = read_command(&mut runner).unwrap();
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+    let event = read_command(&mut watcher).unwrap();
+    assert!(matches!(event.header.command_type, AdaCommandType::Event));
+    assert_eq!(event.data.as_ref().unwrap().get_item("sum"), Some(&Data::Integer(5)));
+
+    drop(watcher);
+    // Gives the server time to notice the disconnect before the next run
+    thread::sleep(Duration::from_millis(200));
+    runner.write_all(&propagate(network, add).to_bytes()).unwrap();
+    let response = read_command(&mut runner).unwrap();
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+}
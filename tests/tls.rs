@@ -0,0 +1,105 @@
+#![cfg(feature = "tls")]
+
+use std::fs;
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use rcgen::{BasicConstraints, Certificate, CertificateParams, IsCa, KeyPair};
+use uuid::Uuid;
+
+use ada_executor::command_processor::command_scheduler;
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::protocol::commands::{AdaCommand, AdaCommandType};
+use ada_executor::protocol::protocol_parser::read_command;
+use ada_executor::protocol::tls::{self, TlsTransport};
+use ada_executor::protocol::transport::{self, ConnectionContext};
+
+struct Pki {
+    dir: PathBuf,
+}
+
+impl Pki {
+    // A throwaway CA plus a server and a client certificate signed by it
+    fn generate() -> Self {
+        let dir = std::env::temp_dir().join(format!("ada-tls-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut ca_params = CertificateParams::new(Vec::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca_key = KeyPair::generate().unwrap();
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+        fs::write(dir.join("ca.pem"), ca.pem()).unwrap();
+
+        Pki::issue(&dir, "server", vec!["localhost".to_string()], &ca, &ca_key);
+        Pki::issue(&dir, "client", vec!["client.local".to_string()], &ca, &ca_key);
+        Pki { dir }
+    }
+
+    fn issue(dir: &Path, name: &str, names: Vec<String>, ca: &Certificate, ca_key: &KeyPair) {
+        let key = KeyPair::generate().unwrap();
+        let cert = CertificateParams::new(names).unwrap().signed_by(&key, ca, ca_key).unwrap();
+        fs::write(dir.join(format!("{}.pem", name)), cert.pem()).unwrap();
+        fs::write(dir.join(format!("{}.key", name)), key.serialize_pem()).unwrap();
+    }
+
+    fn path(&self, file: &str) -> PathBuf {
+        self.dir.join(file)
+    }
+}
+
+impl Drop for Pki {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn start_server(pki: &Pki, mutual: bool) -> String {
+    let client_ca = pki.path("ca.pem");
+    let config = tls::server_config(&pki.path("server.pem"), &pki.path("server.key"), mutual.then_some(client_ca.as_path())).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let queue = Arc::new(Mutex::new(Vec::new()));
+    command_scheduler::listen(Arc::clone(&queue), Dispatcher::new());
+    thread::spawn(move || transport::serve(&TlsTransport::new(listener, config), ConnectionContext::new(queue)));
+    address
+}
+
+fn create_network<S: std::io::Read + Write>(stream: &mut S) -> std::io::Result<AdaCommand> {
+    let mut command = AdaCommand::new(AdaCommandType::CreateNetwork);
+    command.header.network = Some(Uuid::new_v4());
+    stream.write_all(&command.to_bytes())?;
+    stream.flush()?;
+    read_command(stream)
+}
+
+#[test]
+fn serves_commands_over_tls() {
+    let pki = Pki::generate();
+    let address = start_server(&pki, false);
+
+    let config = tls::client_config(&pki.path("ca.pem"), None).unwrap();
+    let mut stream = tls::connect(TcpStream::connect(&address).unwrap(), "localhost", config).unwrap();
+    let response = create_network(&mut stream).unwrap();
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+}
+
+#[test]
+fn mutual_tls_requires_client_certificate() {
+    let pki = Pki::generate();
+    let address = start_server(&pki, true);
+
+    let anonymous = tls::client_config(&pki.path("ca.pem"), None).unwrap();
+    let mut stream = tls::connect(TcpStream::connect(&address).unwrap(), "localhost", anonymous).unwrap();
+    assert!(create_network(&mut stream).is_err());
+
+    let client_cert = pki.path("client.pem");
+    let client_key = pki.path("client.key");
+    let identified = tls::client_config(&pki.path("ca.pem"), Some((client_cert.as_path(), client_key.as_path()))).unwrap();
+    let mut stream = tls::connect(TcpStream::connect(&address).unwrap(), "localhost", identified).unwrap();
+    let response = create_network(&mut stream).unwrap();
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+}
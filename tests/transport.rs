@@ -0,0 +1,66 @@
+use std::io::{ErrorKind, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use uuid::Uuid;
+
+use ada_executor::command_processor::command_scheduler;
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::protocol::commands::{AdaCommand, AdaCommandType, MAX_FRAME_SIZE};
+use ada_executor::protocol::memory_transport::MemoryTransport;
+use ada_executor::protocol::protocol_parser::read_command;
+use ada_executor::protocol::transport::{self, ConnectionContext};
+
+#[test]
+fn serves_commands_over_in_memory_transport() {
+    let queue = Arc::new(Mutex::new(Vec::new()));
+    command_scheduler::listen(Arc::clone(&queue), Dispatcher::new());
+
+    let memory_transport = Arc::new(MemoryTransport::new());
+    let server_transport = Arc::clone(&memory_transport);
+    thread::spawn(move || transport::serve(&*server_transport, ConnectionContext::new(queue)));
+
+    let mut client = memory_transport.connect();
+    let network = Uuid::new_v4();
+
+    let mut create = AdaCommand::new(AdaCommandType::CreateNetwork);
+    create.header.network = Some(network);
+    client.write_all(&create.to_bytes()).unwrap();
+    let response = read_command(&mut client).unwrap();
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+    assert_eq!(response.header.network, Some(network));
+
+    // Frames split across writes are reassembled by the server
+    let mut execute = AdaCommand::new(AdaCommandType::ExecuteNode);
+    execute.header.network = Some(network);
+    execute.header.node = Some(Uuid::new_v4());
+    let bytes = execute.to_bytes();
+    client.write_all(&bytes[..10]).unwrap();
+    client.write_all(&bytes[10..]).unwrap();
+    let response = read_command(&mut client).unwrap();
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+}
+
+#[test]
+fn oversized_frames_are_refused_and_close_the_connection() {
+    let queue = Arc::new(Mutex::new(Vec::new()));
+    command_scheduler::listen(Arc::clone(&queue), Dispatcher::new());
+
+    let memory_transport = Arc::new(MemoryTransport::new());
+    let server_transport = Arc::clone(&memory_transport);
+    thread::spawn(move || transport::serve(&*server_transport, ConnectionContext::new(queue)));
+
+    let mut client = memory_transport.connect();
+    let mut header = AdaCommand::new(AdaCommandType::CreateNetwork).to_bytes();
+    header[1..5].copy_from_slice(&(MAX_FRAME_SIZE + 1).to_be_bytes());
+    client.write_all(&header).unwrap();
+
+    let response = read_command(&mut client).unwrap();
+    assert!(matches!(response.header.command_type, AdaCommandType::Error));
+    assert!(read_command(&mut client).is_err());
+
+    // The client side refuses such a frame as well, without reading its content
+    let mut frame = AdaCommand::new(AdaCommandType::Response).to_bytes();
+    frame[1..5].copy_from_slice(&u32::MAX.to_be_bytes());
+    assert!(matches!(read_command(&mut frame.as_slice()), Err(e) if e.kind() == ErrorKind::InvalidData));
+}
@@ -0,0 +1,79 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use ada_executor::command_processor::command_scheduler;
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::protocol::commands::{AdaCommand, AdaCommandType};
+use ada_executor::protocol::protocol_parser::read_command;
+use ada_executor::protocol::transport::{self, ConnectionContext};
+use ada_executor::protocol::unix_server::UnixServer;
+
+mod common;
+
+use common::TempDir;
+
+fn server(path: &Path, mode: u32) -> UnixServer {
+    let queue = Arc::new(Mutex::new(Vec::new()));
+    command_scheduler::listen(Arc::clone(&queue), Dispatcher::new());
+    UnixServer::new(path.to_str().unwrap(), mode, queue)
+}
+
+fn create_network(stream: &mut UnixStream) -> AdaCommand {
+    let mut command = AdaCommand::new(AdaCommandType::CreateNetwork);
+    command.header.network = Some(Uuid::new_v4());
+    stream.write_all(&command.to_bytes()).unwrap();
+    read_command(stream).unwrap()
+}
+
+#[test]
+fn frames_are_exchanged_over_the_socket() {
+    let dir = TempDir::new("unix");
+    let path = dir.join("ada.sock");
+    let queue = Arc::new(Mutex::new(Vec::new()));
+    command_scheduler::listen(Arc::clone(&queue), Dispatcher::new());
+    let listener = UnixServer::new(path.to_str().unwrap(), 0o600, Arc::clone(&queue)).bind().unwrap();
+    assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    // Only the socket is left in the directory, the staging directory is gone
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+    thread::spawn(move || transport::serve(&listener, ConnectionContext::new(queue)));
+    let mut stream = UnixStream::connect(&path).unwrap();
+    let response = create_network(&mut stream);
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+}
+
+#[test]
+fn stale_sockets_are_replaced() {
+    let dir = TempDir::new("unix");
+    let path = dir.join("ada.sock");
+    drop(UnixListener::bind(&path).unwrap());
+    assert!(path.exists());
+
+    let server = server(&path, 0o660);
+    thread::spawn(move || server.run());
+    // The server replaces the stale socket in the background
+    let mut stream = (0..500)
+        .find_map(|_| UnixStream::connect(&path).ok().or_else(|| { thread::sleep(Duration::from_millis(10)); None }))
+        .expect("server never started listening");
+    let response = create_network(&mut stream);
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+}
+
+#[test]
+fn other_files_at_the_path_are_left_alone() {
+    let dir = TempDir::new("unix");
+    let path = dir.join("ada.sock");
+    fs::write(&path, "not a socket").unwrap();
+    assert!(server(&path, 0o660).run().is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "not a socket");
+}
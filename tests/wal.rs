@@ -0,0 +1,214 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use ada_executor::command_processor::command_scheduler::{self, SchedulerOptions};
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::protocol::commands::{AdaCommand, AdaCommandType};
+use ada_executor::protocol::memory_transport::MemoryTransport;
+use ada_executor::protocol::protocol_parser::read_command;
+use ada_executor::protocol::transport::{self, ConnectionContext};
+use ada_executor::protocol::wal::WriteAheadLog;
+
+mod common;
+
+use common::TempDir;
+
+fn create_network(network: Uuid) -> AdaCommand {
+    let mut data = Container::new();
+    data.add_item("name", Data::Text("logged".to_string()));
+    let mut command = AdaCommand::new(AdaCommandType::CreateNetwork);
+    command.header.network = Some(network);
+    command.data = Some(data);
+    command
+}
+
+fn segment_count(dir: &Path) -> usize {
+    fs::read_dir(dir).unwrap().count()
+}
+
+#[test]
+fn commands_not_marked_done_are_replayed() {
+    let dir = TempDir::new("wal");
+    let (first, second, third) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    {
+        let (wal, replayed) = WriteAheadLog::open(dir.path()).unwrap();
+        assert!(replayed.is_empty());
+        let done = wal.append(&create_network(first), None).unwrap();
+        wal.append(&create_network(second), None).unwrap();
+        wal.append(&create_network(third), None).unwrap();
+        wal.mark_done(done).unwrap();
+        assert_eq!(wal.pending(), 2);
+    }
+
+    let (wal, replayed) = WriteAheadLog::open(dir.path()).unwrap();
+    let networks: Vec<_> = replayed.iter().map(|command| command.header.network).collect();
+    assert_eq!(networks, vec![Some(second), Some(third)]);
+    assert_eq!(replayed[0].data.as_ref().unwrap().get_item("name"), Some(&Data::Text("logged".to_string())));
+
+    // Sequence numbers keep growing across restarts
+    let sequences: Vec<_> = replayed.iter().map(|command| command.origin.as_ref().unwrap().wal_sequence.unwrap()).collect();
+    let next = wal.append(&create_network(Uuid::new_v4()), None).unwrap();
+    assert!(sequences.iter().all(|sequence| *sequence < next));
+    for sequence in sequences {
+        wal.mark_done(sequence).unwrap();
+    }
+    wal.mark_done(next).unwrap();
+    drop(wal);
+    assert!(WriteAheadLog::open(dir.path()).unwrap().1.is_empty());
+}
+
+#[test]
+fn replayed_commands_keep_their_identity() {
+    let dir = TempDir::new("wal");
+    {
+        let (wal, _) = WriteAheadLog::open(dir.path()).unwrap();
+        wal.append(&create_network(Uuid::new_v4()), Some("alice")).unwrap();
+        wal.append(&create_network(Uuid::new_v4()), None).unwrap();
+    }
+    let (_, replayed) = WriteAheadLog::open(dir.path()).unwrap();
+    assert_eq!(replayed[0].origin.as_ref().unwrap().identity.as_deref(), Some("alice"));
+    // An origin without identity is treated as anonymous, not as the executor itself
+    assert!(replayed[1].origin.as_ref().unwrap().identity.is_none());
+}
+
+#[test]
+fn a_torn_record_at_the_end_is_ignored() {
+    let dir = TempDir::new("wal");
+    let network = Uuid::new_v4();
+    {
+        let (wal, _) = WriteAheadLog::open(dir.path()).unwrap();
+        wal.append(&create_network(network), None).unwrap();
+    }
+    let segment = fs::read_dir(dir.path()).unwrap().next().unwrap().unwrap().path();
+    let mut file = OpenOptions::new().append(true).open(&segment).unwrap();
+    // The start of an accepted record, cut off by a crash
+    file.write_all(&[1, 0, 0, 0, 0, 0, 0, 0, 9, 0xff, 0xff, 0, 0]).unwrap();
+    drop(file);
+
+    let (wal, replayed) = WriteAheadLog::open(dir.path()).unwrap();
+    assert_eq!(replayed.len(), 1);
+    assert_eq!(replayed[0].header.network, Some(network));
+    assert_eq!(wal.pending(), 1);
+}
+
+#[test]
+fn rotation_keeps_only_pending_commands() {
+    let dir = TempDir::new("wal");
+    let kept = Uuid::new_v4();
+    {
+        let (wal, _) = WriteAheadLog::open(dir.path()).unwrap();
+        let wal = wal.with_segment_size(512);
+        wal.append(&create_network(kept), None).unwrap();
+        for _ in 0..200 {
+            let sequence = wal.append(&create_network(Uuid::new_v4()), None).unwrap();
+            wal.mark_done(sequence).unwrap();
+            assert_eq!(segment_count(dir.path()), 1);
+        }
+        let size: u64 = fs::read_dir(dir.path()).unwrap().map(|entry| entry.unwrap().metadata().unwrap().len()).sum();
+        assert!(size < 1024, "log grew to {} bytes", size);
+    }
+
+    let (_, replayed) = WriteAheadLog::open(dir.path()).unwrap();
+    assert_eq!(replayed.len(), 1);
+    assert_eq!(replayed[0].header.network, Some(kept));
+    assert_eq!(segment_count(dir.path()), 1);
+}
+
+#[test]
+fn pending_commands_larger_than_a_segment_do_not_rotate_every_append() {
+    let dir = TempDir::new("wal");
+    let (wal, _) = WriteAheadLog::open(dir.path()).unwrap();
+    let wal = wal.with_segment_size(512);
+    let mut large = create_network(Uuid::new_v4());
+    large.data.as_mut().unwrap().add_item("padding", Data::Text("x".repeat(1024)));
+    wal.append(&large, None).unwrap();
+
+    let segment = || fs::read_dir(dir.path()).unwrap().next().unwrap().unwrap().file_name();
+    let first = segment();
+    for _ in 0..3 {
+        let sequence = wal.append(&create_network(Uuid::new_v4()), None).unwrap();
+        wal.mark_done(sequence).unwrap();
+    }
+    assert_eq!(segment(), first);
+}
+
+#[test]
+fn commands_written_before_a_failed_rotation_are_accepted() {
+    let dir = TempDir::new("wal");
+    let (wal, _) = WriteAheadLog::open(dir.path()).unwrap();
+    let wal = wal.with_segment_size(512);
+    // A directory where the next segment goes makes creating it fail
+    let current = fs::read_dir(dir.path()).unwrap().next().unwrap().unwrap().path();
+    let number: u64 = current.file_stem().unwrap().to_str().unwrap().parse().unwrap();
+    let blocked = dir.join(format!("{:020}.wal", number + 1));
+    fs::create_dir(&blocked).unwrap();
+
+    let mut large = create_network(Uuid::new_v4());
+    large.data.as_mut().unwrap().add_item("padding", Data::Text("x".repeat(1024)));
+    assert!(wal.append(&large, None).is_ok());
+    assert_eq!(wal.pending(), 1);
+    drop(wal);
+
+    fs::remove_dir(&blocked).unwrap();
+    let (_, replayed) = WriteAheadLog::open(dir.path()).unwrap();
+    assert_eq!(replayed.len(), 1);
+    assert_eq!(replayed[0].header.network, large.header.network);
+}
+
+#[test]
+fn commands_accepted_by_the_transport_are_marked_done_once_handled() {
+    let dir = TempDir::new("wal");
+    let (wal, _) = WriteAheadLog::open(dir.path()).unwrap();
+    let wal = Arc::new(wal);
+    let queue = Arc::new(Mutex::new(Vec::new()));
+    let options = SchedulerOptions { wal: Some(Arc::clone(&wal)), ..SchedulerOptions::default() };
+    command_scheduler::start(Arc::clone(&queue), Dispatcher::new(), options);
+
+    let memory_transport = Arc::new(MemoryTransport::new());
+    let server_transport = Arc::clone(&memory_transport);
+    let mut context = ConnectionContext::new(queue);
+    context.wal = Some(Arc::clone(&wal));
+    thread::spawn(move || transport::serve(&*server_transport, context));
+
+    let mut client = memory_transport.connect();
+    client.write_all(&create_network(Uuid::new_v4()).to_bytes()).unwrap();
+    let response = read_command(&mut client).unwrap();
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+
+    // The done record is written right after the response went out
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while wal.pending() > 0 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(wal.pending(), 0);
+}
+
+#[test]
+fn replayed_commands_run_against_a_fresh_executor() {
+    let dir = TempDir::new("wal");
+    let network = Uuid::new_v4();
+    {
+        let (wal, _) = WriteAheadLog::open(dir.path()).unwrap();
+        wal.append(&create_network(network), Some("alice")).unwrap();
+    }
+    let (wal, replayed) = WriteAheadLog::open(dir.path()).unwrap();
+    let wal = Arc::new(wal);
+    let queue = Arc::new(Mutex::new(replayed));
+    let options = SchedulerOptions { wal: Some(Arc::clone(&wal)), ..SchedulerOptions::default() };
+    command_scheduler::start(Arc::clone(&queue), Dispatcher::new(), options);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while wal.pending() > 0 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(wal.pending(), 0);
+    drop(wal);
+    assert!(WriteAheadLog::open(dir.path()).unwrap().1.is_empty());
+}
@@ -0,0 +1,115 @@
+#![cfg(feature = "wasm")]
+
+use std::fs;
+
+use uuid::Uuid;
+
+use ada_executor::network::data_container::{Container, Data};
+use ada_executor::network::plugins::wasm;
+use ada_executor::network::registry::NodeRegistry;
+
+mod common;
+
+use common::TempDir;
+
+// Bump allocator plus the given `ada_execute` body, `ptr` and `len` are the input
+fn module(version: i32, execute_body: &str) -> Vec<u8> {
+    wat::parse_str(format!(r#"
+        (module
+            (memory (export "memory") 1)
+            (global $heap (mut i32) (i32.const 1024))
+            (func (export "ada_abi_version") (result i32) (i32.const {version}))
+            (func (export "ada_alloc") (param $len i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (global.get $heap))
+                (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+                (local.get $ptr))
+            (func (export "ada_execute") (param $ptr i32) (param $len i32) (result i64)
+                {execute_body}))
+    "#)).unwrap()
+}
+
+const ECHO: &str = "(i64.or (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32)) (i64.extend_i32_u (local.get $len)))";
+
+fn registry_with(modules: &[(&str, Vec<u8>)]) -> std::io::Result<NodeRegistry> {
+    registry_limited(modules, wasm::Limits::default())
+}
+
+fn registry_limited(modules: &[(&str, Vec<u8>)], max: wasm::Limits) -> std::io::Result<NodeRegistry> {
+    // Modules are compiled while loading, the files can go right after
+    let dir = TempDir::new("wasm");
+    for (name, wasm) in modules {
+        fs::write(dir.join(format!("{}.wasm", name)), wasm)?;
+    }
+    let mut registry = NodeRegistry::new();
+    wasm::load_dir(&mut registry, dir.path(), max)?;
+    Ok(registry)
+}
+
+fn run(registry: &NodeRegistry, type_name: &str, config: Container) -> Container {
+    let mut input = Container::new();
+    input.add_item("value", Data::Integer(42));
+    input.add_item("name", Data::Text("ada".to_string()));
+    registry.create_node(type_name, Uuid::new_v4(), config).unwrap().execute(input)
+}
+
+#[test]
+fn container_round_trips_through_module() {
+    let registry = registry_with(&[("echo", module(wasm::WASM_ABI_VERSION, ECHO))]).unwrap();
+    assert!(registry.descriptor("wasm.echo").is_some());
+    let output = run(&registry, "wasm.echo", Container::new());
+    assert_eq!(output.get_item("value"), Some(&Data::Integer(42)));
+    assert_eq!(output.get_item("name"), Some(&Data::Text("ada".to_string())));
+}
+
+#[test]
+fn runaway_module_runs_out_of_fuel() {
+    let registry = registry_with(&[("spin", module(wasm::WASM_ABI_VERSION, "(loop $spin (br $spin)) (unreachable)"))]).unwrap();
+    let mut config = Container::new();
+    config.add_item("fuel", Data::Integer(10_000));
+    let output = run(&registry, "wasm.spin", config);
+    assert!(matches!(output.get_item("error"), Some(Data::Text(error)) if error.contains("fuel")), "{:?}", output);
+}
+
+#[test]
+fn memory_growth_is_limited() {
+    // Traps if growing by 64 MiB fails
+    let body = format!("(if (i32.eq (memory.grow (i32.const 1024)) (i32.const -1)) (then unreachable)) {}", ECHO);
+    let max = wasm::Limits { max_memory_bytes: 256 * 1024 * 1024, ..wasm::Limits::default() };
+    let registry = registry_limited(&[("hog", module(wasm::WASM_ABI_VERSION, &body))], max).unwrap();
+    assert!(run(&registry, "wasm.hog", Container::new()).get_item("error").is_some());
+
+    let mut config = Container::new();
+    config.add_item("max_memory_bytes", Data::Integer(128 * 1024 * 1024));
+    assert_eq!(run(&registry, "wasm.hog", config).get_item("value"), Some(&Data::Integer(42)));
+}
+
+#[test]
+fn nodes_cannot_ask_for_more_than_the_operator_limits() {
+    let registry = registry_with(&[("echo", module(wasm::WASM_ABI_VERSION, ECHO))]).unwrap();
+    let default = wasm::Limits::default();
+    for (key, limit) in [("fuel", default.fuel as i32), ("max_memory_bytes", default.max_memory_bytes as i32)] {
+        let mut config = Container::new();
+        config.add_item(key, Data::Integer(limit + 1));
+        let error = registry.create_node("wasm.echo", Uuid::new_v4(), config).err().unwrap();
+        assert!(error.contains("exceeds the maximum"), "{}", error);
+    }
+
+    // Lower limits are still up to the node
+    let mut config = Container::new();
+    config.add_item("fuel", Data::Integer(1_000_000));
+    assert_eq!(run(&registry, "wasm.echo", config).get_item("value"), Some(&Data::Integer(42)));
+}
+
+#[test]
+fn output_outside_memory_is_an_error() {
+    // Claims 16 bytes at 1 MiB, beyond the single page of memory
+    let registry = registry_with(&[("garbage", module(wasm::WASM_ABI_VERSION, "(i64.const 0x0010000000000010)"))]).unwrap();
+    assert!(run(&registry, "wasm.garbage", Container::new()).get_item("error").is_some());
+}
+
+#[test]
+fn mismatched_abi_version_is_rejected() {
+    let error = registry_with(&[("future", module(wasm::WASM_ABI_VERSION + 1, ECHO))]).err().unwrap();
+    assert!(error.to_string().contains("ABI version"));
+}
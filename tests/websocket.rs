@@ -0,0 +1,71 @@
+#![cfg(feature = "websocket")]
+
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use tungstenite::{Message, WebSocket};
+use uuid::Uuid;
+
+use ada_executor::command_processor::command_scheduler;
+use ada_executor::command_processor::dispatcher::Dispatcher;
+use ada_executor::protocol::commands::{AdaCommand, AdaCommandType};
+use ada_executor::protocol::protocol_parser::parse_command;
+use ada_executor::protocol::wal::WriteAheadLog;
+use ada_executor::protocol::websocket_server::WebSocketServer;
+
+mod common;
+
+type Socket = WebSocket<TcpStream>;
+
+fn connect(configure: impl FnOnce(WebSocketServer) -> WebSocketServer) -> Socket {
+    let queue = Arc::new(Mutex::new(Vec::new()));
+    command_scheduler::listen(Arc::clone(&queue), Dispatcher::new());
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = configure(WebSocketServer::new(&address.to_string(), queue));
+    thread::spawn(move || server.serve(listener));
+
+    let stream = TcpStream::connect(address).unwrap();
+    tungstenite::client(format!("ws://{}/", address), stream).unwrap().0
+}
+
+fn read_response(socket: &mut Socket) -> AdaCommand {
+    loop {
+        match socket.read().unwrap() {
+            Message::Binary(frame) => break parse_command(&frame).unwrap(),
+            Message::Ping(_) | Message::Pong(_) => continue,
+            other => panic!("expected a binary frame, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn binary_frames_are_answered_with_binary_frames() {
+    let mut socket = connect(|server| server);
+    let network = Uuid::new_v4();
+    let mut command = AdaCommand::new(AdaCommandType::CreateNetwork);
+    command.header.network = Some(network);
+    socket.send(Message::Binary(command.to_bytes())).unwrap();
+
+    let response = read_response(&mut socket);
+    assert!(matches!(response.header.command_type, AdaCommandType::Response));
+    assert_eq!(response.header.network, Some(network));
+
+    // Frames that are no commands are dropped without closing the connection
+    socket.send(Message::Binary(vec![1, 2, 3])).unwrap();
+    socket.send(Message::Binary(command.to_bytes())).unwrap();
+    assert!(matches!(read_response(&mut socket).header.command_type, AdaCommandType::Error));
+}
+
+#[test]
+fn commands_are_logged_before_they_are_queued() {
+    let dir = common::TempDir::new("websocket-wal");
+    let (wal, _) = WriteAheadLog::open(dir.path()).unwrap();
+    let wal = Arc::new(wal);
+    // Nothing marks the commands done without a scheduler owning the log
+    let mut socket = connect(|server| server.with_wal(Arc::clone(&wal)));
+    socket.send(Message::Binary(AdaCommand::new(AdaCommandType::CreateNetwork).to_bytes())).unwrap();
+    assert!(matches!(read_response(&mut socket).header.command_type, AdaCommandType::Response));
+    assert_eq!(wal.pending(), 1);
+}